@@ -0,0 +1,3 @@
+DROP TABLE submission;
+DROP TABLE test_case;
+DROP TABLE problem;
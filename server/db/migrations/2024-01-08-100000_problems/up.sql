@@ -0,0 +1,44 @@
+CREATE TABLE problem (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  title varchar(256) NOT NULL,
+  statement text NOT NULL,
+  time_limit_ms integer NOT NULL,
+  memory_limit_bytes bigint NOT NULL,
+  checker varchar(32) NOT NULL DEFAULT 'tokens',
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+
+-- Test data is either stored inline or referenced by a path relative to the
+-- problem's data directory.
+CREATE TABLE test_case (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  problem_id UUID NOT NULL REFERENCES problem (id) ON DELETE CASCADE,
+  ordinal integer NOT NULL,
+  test_group integer NOT NULL DEFAULT 0,
+  is_sample boolean NOT NULL DEFAULT false,
+  input bytea,
+  input_path text,
+  answer bytea,
+  answer_path text,
+  UNIQUE (problem_id, ordinal),
+  CHECK (input IS NOT NULL OR input_path IS NOT NULL),
+  CHECK (answer IS NOT NULL OR answer_path IS NOT NULL)
+);
+
+
+CREATE TABLE submission (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  user_id UUID NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+  problem_id UUID NOT NULL REFERENCES problem (id) ON DELETE CASCADE,
+  language varchar(64) NOT NULL,
+  source text NOT NULL,
+  status varchar(16) NOT NULL DEFAULT 'queued',
+  verdict varchar(8),
+  time_ms integer,
+  memory_bytes bigint,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX submission_user_id_idx ON submission (user_id);
+CREATE INDEX submission_problem_id_idx ON submission (problem_id);
@@ -0,0 +1,7 @@
+DROP TABLE test_result;
+
+DROP INDEX submission_status_idx;
+
+ALTER TABLE submission
+  DROP COLUMN heartbeat_at,
+  DROP COLUMN compile_log;
@@ -0,0 +1,17 @@
+-- A worker refreshes heartbeat_at while it judges a submission. Submissions
+-- whose worker stopped doing so are picked up again by the next worker.
+ALTER TABLE submission
+  ADD COLUMN heartbeat_at timestamptz,
+  ADD COLUMN compile_log text;
+
+CREATE INDEX submission_status_idx ON submission (status) WHERE status <> 'finished';
+
+
+CREATE TABLE test_result (
+  submission_id UUID NOT NULL REFERENCES submission (id) ON DELETE CASCADE,
+  test_case_id UUID NOT NULL REFERENCES test_case (id) ON DELETE CASCADE,
+  verdict varchar(8) NOT NULL,
+  time_ms integer NOT NULL,
+  memory_bytes bigint NOT NULL,
+  PRIMARY KEY (submission_id, test_case_id)
+);
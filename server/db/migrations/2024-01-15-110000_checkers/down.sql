@@ -0,0 +1,9 @@
+ALTER TABLE test_result
+  DROP COLUMN score,
+  DROP COLUMN checker_message;
+
+ALTER TABLE problem
+  DROP COLUMN checker_absolute_epsilon,
+  DROP COLUMN checker_relative_epsilon,
+  DROP COLUMN checker_source,
+  DROP COLUMN checker_language;
@@ -0,0 +1,12 @@
+-- Epsilons are used by the float checker, source and language by custom
+-- checkers speaking the testlib protocol.
+ALTER TABLE problem
+  ADD COLUMN checker_absolute_epsilon double precision,
+  ADD COLUMN checker_relative_epsilon double precision,
+  ADD COLUMN checker_source text,
+  ADD COLUMN checker_language varchar(64);
+
+
+ALTER TABLE test_result
+  ADD COLUMN score double precision NOT NULL DEFAULT 0,
+  ADD COLUMN checker_message text;
@@ -0,0 +1,7 @@
+-- A problem with an interactor is interactive: the interactor talks to the
+-- submission over its stdin and stdout and decides the verdict.
+ALTER TABLE problem
+  ADD COLUMN interactor_source text,
+  ADD COLUMN interactor_language varchar(64),
+  ADD CONSTRAINT problem_interactor_check
+    CHECK ((interactor_source IS NULL) = (interactor_language IS NULL));
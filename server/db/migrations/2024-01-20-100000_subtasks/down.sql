@@ -0,0 +1,5 @@
+ALTER TABLE submission DROP COLUMN score;
+
+DROP TABLE subtask_result;
+
+DROP TABLE subtask;
@@ -0,0 +1,23 @@
+-- A subtask scores the tests of the problem sharing its test_group.
+CREATE TABLE subtask (
+  problem_id UUID NOT NULL REFERENCES problem (id) ON DELETE CASCADE,
+  test_group integer NOT NULL,
+  points integer NOT NULL,
+  scoring varchar(16) NOT NULL DEFAULT 'all_or_nothing',
+  -- Groups that must earn full points for this one to earn any.
+  depends_on integer[] NOT NULL DEFAULT '{}',
+  PRIMARY KEY (problem_id, test_group),
+  CHECK (points >= 0),
+  CHECK (scoring IN ('all_or_nothing', 'min', 'sum'))
+);
+
+
+CREATE TABLE subtask_result (
+  submission_id UUID NOT NULL REFERENCES submission (id) ON DELETE CASCADE,
+  test_group integer NOT NULL,
+  points double precision NOT NULL,
+  PRIMARY KEY (submission_id, test_group)
+);
+
+
+ALTER TABLE submission ADD COLUMN score double precision;
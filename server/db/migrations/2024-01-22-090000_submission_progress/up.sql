@@ -0,0 +1,2 @@
+-- Ordinal of the test a running submission is on, for live status updates.
+ALTER TABLE submission ADD COLUMN current_test integer;
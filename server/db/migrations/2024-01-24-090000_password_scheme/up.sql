@@ -0,0 +1,5 @@
+-- Existing rows hold the client-side hash as is. The launcher rehashes them
+-- with Argon2 right after migrating, see web::auth::password::upgrade_legacy.
+ALTER TABLE "user" ADD COLUMN password_scheme varchar(16) NOT NULL DEFAULT 'legacy';
+
+ALTER TABLE "user" ALTER COLUMN password_scheme SET DEFAULT 'argon2';
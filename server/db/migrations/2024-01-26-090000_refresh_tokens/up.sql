@@ -0,0 +1,15 @@
+-- Every login starts a family of refresh tokens, each replaced by the next on
+-- refresh. Access tokens carry the id of the refresh token issued with them
+-- as their jti and stop working once it is revoked.
+CREATE TABLE refresh_token (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  user_id UUID NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+  family_id UUID NOT NULL,
+  expires_at timestamptz NOT NULL,
+  rotated_at timestamptz,
+  revoked_at timestamptz,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX refresh_token_user_id_idx ON refresh_token (user_id);
+CREATE INDEX refresh_token_family_id_idx ON refresh_token (family_id);
@@ -0,0 +1,2 @@
+ALTER TABLE "user" ADD COLUMN role varchar(32) NOT NULL DEFAULT 'contestant'
+    CHECK (role IN ('admin', 'problem_setter', 'contest_manager', 'contestant'));
@@ -0,0 +1,4 @@
+ALTER TABLE problem
+  DROP COLUMN checker_protocol,
+  DROP COLUMN validator_source,
+  DROP COLUMN validator_language;
@@ -0,0 +1,11 @@
+-- Checkers and interactors imported from Kattis packages report through the
+-- Kattis output validator protocol instead of testlib's. The input validator
+-- is kept so that tests can be checked again after editing.
+ALTER TABLE problem
+  ADD COLUMN checker_protocol varchar(16) NOT NULL DEFAULT 'testlib',
+  ADD COLUMN validator_source text,
+  ADD COLUMN validator_language varchar(64),
+  ADD CONSTRAINT problem_checker_protocol_check
+    CHECK (checker_protocol IN ('testlib', 'kattis')),
+  ADD CONSTRAINT problem_validator_check
+    CHECK ((validator_source IS NULL) = (validator_language IS NULL));
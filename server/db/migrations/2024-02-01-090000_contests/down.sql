@@ -0,0 +1,7 @@
+ALTER TABLE submission DROP COLUMN contest_id;
+
+DROP TABLE contest_participant;
+
+DROP TABLE contest_problem;
+
+DROP TABLE contest;
@@ -0,0 +1,38 @@
+-- A contest runs between starts_at and ends_at on a set of problems, each
+-- under a letter. Only registered participants may submit and show up on the
+-- scoreboard. Results of submissions made in the last freeze_minutes stay
+-- hidden from participants until the contest is unfrozen.
+CREATE TABLE contest (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  title varchar(256) NOT NULL,
+  starts_at timestamptz NOT NULL,
+  ends_at timestamptz NOT NULL,
+  freeze_minutes integer NOT NULL DEFAULT 60,
+  -- Added to the penalty time for every rejected attempt at a solved problem.
+  penalty_minutes integer NOT NULL DEFAULT 20,
+  unfrozen_at timestamptz,
+  created_at timestamptz NOT NULL DEFAULT now(),
+  CHECK (starts_at < ends_at),
+  CHECK (freeze_minutes >= 0),
+  CHECK (penalty_minutes >= 0)
+);
+
+CREATE TABLE contest_problem (
+  contest_id UUID NOT NULL REFERENCES contest (id) ON DELETE CASCADE,
+  problem_id UUID NOT NULL REFERENCES problem (id) ON DELETE CASCADE,
+  letter varchar(8) NOT NULL,
+  PRIMARY KEY (contest_id, letter),
+  UNIQUE (contest_id, problem_id)
+);
+
+CREATE TABLE contest_participant (
+  contest_id UUID NOT NULL REFERENCES contest (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+  registered_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (contest_id, user_id)
+);
+
+ALTER TABLE submission
+  ADD COLUMN contest_id UUID REFERENCES contest (id) ON DELETE SET NULL;
+
+CREATE INDEX submission_contest_id_idx ON submission (contest_id) WHERE contest_id IS NOT NULL;
@@ -0,0 +1,6 @@
+ALTER TABLE contest
+  DROP COLUMN rules,
+  DROP COLUMN scoring,
+  DROP COLUMN feedback,
+  DROP COLUMN submission_limit,
+  DROP COLUMN submission_delay_seconds;
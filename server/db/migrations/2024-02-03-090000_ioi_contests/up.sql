@@ -0,0 +1,15 @@
+-- IOI contests score every problem by its subtasks instead of ranking by
+-- solved problems and penalty time. What participants learn about their own
+-- submissions while the contest runs depends on feedback.
+ALTER TABLE contest
+  ADD COLUMN rules varchar(16) NOT NULL DEFAULT 'icpc'
+    CHECK (rules IN ('icpc', 'ioi')),
+  ADD COLUMN scoring varchar(16) NOT NULL DEFAULT 'best_subtasks'
+    CHECK (scoring IN ('best_subtasks', 'best_submission')),
+  ADD COLUMN feedback varchar(16) NOT NULL DEFAULT 'full'
+    CHECK (feedback IN ('full', 'subtasks', 'none')),
+  -- Submissions a participant may make to each problem, any number if NULL.
+  ADD COLUMN submission_limit integer CHECK (submission_limit > 0),
+  -- Time a participant has to wait between two submissions.
+  ADD COLUMN submission_delay_seconds integer NOT NULL DEFAULT 0
+    CHECK (submission_delay_seconds >= 0);
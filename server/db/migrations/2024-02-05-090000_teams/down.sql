@@ -0,0 +1,7 @@
+ALTER TABLE contest_participant DROP COLUMN team_id;
+
+DROP TABLE team_invitation;
+
+DROP TABLE team_member;
+
+DROP TABLE team;
@@ -0,0 +1,27 @@
+-- Users join teams by accepting an invitation from a member.
+CREATE TABLE team (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  name varchar(64) NOT NULL UNIQUE,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE TABLE team_member (
+  team_id UUID NOT NULL REFERENCES team (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+  joined_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (team_id, user_id)
+);
+
+CREATE INDEX team_member_user_id_idx ON team_member (user_id);
+
+CREATE TABLE team_invitation (
+  team_id UUID NOT NULL REFERENCES team (id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+  created_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (team_id, user_id)
+);
+
+-- A team is registered for a contest by registering each of its members
+-- with its team_id, so that nobody competes in two teams at once.
+ALTER TABLE contest_participant
+  ADD COLUMN team_id UUID REFERENCES team (id) ON DELETE CASCADE;
@@ -0,0 +1,3 @@
+DROP TABLE rejudge_submission;
+
+DROP TABLE rejudge;
@@ -0,0 +1,23 @@
+-- A rejudge re-queues the finished submissions matching its filters; unset
+-- filters match everything.
+CREATE TABLE rejudge (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  -- NULL when started from the command line.
+  created_by UUID REFERENCES "user" (id) ON DELETE SET NULL,
+  problem_id UUID REFERENCES problem (id) ON DELETE CASCADE,
+  contest_id UUID REFERENCES contest (id) ON DELETE CASCADE,
+  verdict varchar(8),
+  submission_id UUID REFERENCES submission (id) ON DELETE CASCADE,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+-- The outcome of a submission before it was rejudged.
+CREATE TABLE rejudge_submission (
+  rejudge_id UUID NOT NULL REFERENCES rejudge (id) ON DELETE CASCADE,
+  submission_id UUID NOT NULL REFERENCES submission (id) ON DELETE CASCADE,
+  verdict varchar(8),
+  time_ms integer,
+  memory_bytes bigint,
+  score double precision,
+  PRIMARY KEY (rejudge_id, submission_id)
+);
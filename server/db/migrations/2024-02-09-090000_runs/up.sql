@@ -0,0 +1,23 @@
+-- Custom invocations: a program run once on input of the user's choosing.
+-- Judge workers take them only when no submission is waiting.
+CREATE TABLE run (
+  id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+  user_id UUID NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+  language varchar(64) NOT NULL,
+  source text NOT NULL,
+  stdin text NOT NULL,
+  status varchar(16) NOT NULL DEFAULT 'queued',
+  heartbeat_at timestamptz,
+  compile_log text,
+  -- NULL if the program exited on its own, with exit_code.
+  verdict varchar(8),
+  exit_code integer,
+  stdout text,
+  stderr text,
+  time_ms integer,
+  memory_bytes bigint,
+  created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX run_status_idx ON run (status) WHERE status <> 'finished';
+CREATE INDEX run_user_id_created_at_idx ON run (user_id, created_at);
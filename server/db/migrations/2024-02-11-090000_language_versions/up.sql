@@ -0,0 +1,4 @@
+-- Languages come in variants, each its own language id. What the variant's
+-- toolchain reported as its version when it judged a submission is kept
+-- next to the id, so that the result can be reproduced.
+ALTER TABLE submission ADD COLUMN language_version text;
@@ -0,0 +1,2 @@
+ALTER TABLE run DROP COLUMN compiled_from_cache;
+ALTER TABLE submission DROP COLUMN compiled_from_cache;
@@ -0,0 +1,4 @@
+-- Whether the judge took the program from the compile cache rather than
+-- compiling it, NULL until it got that far.
+ALTER TABLE submission ADD COLUMN compiled_from_cache boolean;
+ALTER TABLE run ADD COLUMN compiled_from_cache boolean;
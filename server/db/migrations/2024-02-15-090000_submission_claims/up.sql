@@ -0,0 +1,3 @@
+-- Set anew whenever a worker claims the submission, so a worker that lost it
+-- to another one, after its heartbeat stopped, can no longer write to it.
+ALTER TABLE submission ADD COLUMN claim_id uuid;
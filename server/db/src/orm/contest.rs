@@ -0,0 +1,389 @@
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+text_enum! {
+    /// How participants of a contest are ranked.
+    pub enum ContestRules {
+        /// By problems solved, then by penalty time.
+        Icpc => "icpc",
+        /// By points earned on the subtasks of the problems.
+        Ioi => "ioi",
+    }
+}
+
+text_enum! {
+    /// How the points of an IOI contest's problem add up over submissions.
+    pub enum ContestScoring {
+        /// The best points on each subtask across all submissions.
+        BestSubtasks => "best_subtasks",
+        /// The points of the best submission.
+        BestSubmission => "best_submission",
+    }
+}
+
+text_enum! {
+    /// What participants learn about their submissions while a contest
+    /// runs.
+    pub enum Feedback {
+        /// Everything, down to every test.
+        Full => "full",
+        /// Verdicts and points of subtasks, but nothing about single tests.
+        Subtasks => "subtasks",
+        /// Only whether the submission compiled.
+        None => "none",
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::contest)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Contest {
+    pub id: Uuid,
+    pub title: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// How long before the end the scoreboard freezes.
+    pub freeze_minutes: i32,
+    /// Added for every rejected attempt at a solved problem.
+    pub penalty_minutes: i32,
+    /// Set once the final results are shown to everybody.
+    pub unfrozen_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub rules: ContestRules,
+    /// How problems are scored under IOI rules.
+    pub scoring: ContestScoring,
+    pub feedback: Feedback,
+    /// Submissions a participant may make to each problem.
+    pub submission_limit: Option<i32>,
+    /// Time a participant has to wait between two submissions.
+    pub submission_delay_seconds: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::contest)]
+pub struct NewContest<'a> {
+    pub title: &'a str,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub freeze_minutes: i32,
+    pub penalty_minutes: i32,
+    pub rules: ContestRules,
+    pub scoring: ContestScoring,
+    pub feedback: Feedback,
+    pub submission_limit: Option<i32>,
+    pub submission_delay_seconds: i32,
+}
+
+/// A problem of a contest, under a letter.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::contest_problem)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ContestProblem {
+    pub contest_id: Uuid,
+    pub problem_id: Uuid,
+    pub letter: String,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::contest_participant)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ContestParticipant {
+    pub contest_id: Uuid,
+    pub user_id: Uuid,
+    pub registered_at: DateTime<Utc>,
+    /// The team the user competes in, if not alone.
+    pub team_id: Option<Uuid>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::contest_participant)]
+pub struct NewContestParticipant {
+    pub contest_id: Uuid,
+    pub user_id: Uuid,
+    pub team_id: Option<Uuid>,
+}
+
+impl Contest {
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        new_contest: &NewContest<'_>,
+    ) -> QueryResult<Self> {
+        use crate::schema::contest::dsl;
+
+        let query = diesel::insert_into(dsl::contest)
+            .values(new_contest)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn delete(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<usize> {
+        use crate::schema::contest::dsl;
+
+        let query = diesel::delete(dsl::contest.filter(dsl::id.eq(id)));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::contest::dsl;
+
+        let query = dsl::contest
+            .filter(dsl::id.eq(id))
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// All contests, latest first.
+    pub async fn list(conn: &mut AsyncPgConnection) -> QueryResult<Vec<Self>> {
+        use crate::schema::contest::dsl;
+
+        let query = dsl::contest
+            .order(dsl::starts_at.desc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// A contest running at `now` that the user takes part in and the
+    /// problem is part of, if there is one.
+    pub async fn running_with_problem(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        problem_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> QueryResult<Option<Uuid>> {
+        use crate::schema::{contest::dsl, contest_participant, contest_problem};
+
+        let query = dsl::contest
+            .inner_join(contest_problem::table)
+            .inner_join(contest_participant::table)
+            .filter(contest_problem::problem_id.eq(problem_id))
+            .filter(contest_participant::user_id.eq(user_id))
+            .filter(dsl::starts_at.le(now))
+            .filter(dsl::ends_at.gt(now))
+            .order(dsl::starts_at.asc())
+            .select(dsl::id);
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Shows the final results to everybody.
+    pub async fn unfreeze(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<usize> {
+        use crate::schema::contest::dsl;
+
+        let query = diesel::update(dsl::contest.filter(dsl::id.eq(id)))
+            .set(dsl::unfrozen_at.eq(diesel::dsl::now));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// When the scoreboard freezes. Results of submissions made from then on
+    /// are hidden from participants until the contest is unfrozen.
+    pub fn frozen_at(&self) -> DateTime<Utc> {
+        let frozen_at = self.ends_at - Duration::minutes(self.freeze_minutes.into());
+        frozen_at.max(self.starts_at)
+    }
+
+    pub fn is_running(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+}
+
+impl ContestProblem {
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        contest_problem: &ContestProblem,
+    ) -> QueryResult<Self> {
+        use crate::schema::contest_problem::dsl;
+
+        let query = diesel::insert_into(dsl::contest_problem)
+            .values(contest_problem)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn delete(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        letter: &str,
+    ) -> QueryResult<usize> {
+        use crate::schema::contest_problem::dsl;
+
+        let query = diesel::delete(
+            dsl::contest_problem
+                .filter(dsl::contest_id.eq(contest_id))
+                .filter(dsl::letter.eq(letter)),
+        );
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Problems of a contest, by letter.
+    pub async fn list_for_contest(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::contest_problem::dsl;
+
+        let query = dsl::contest_problem
+            .filter(dsl::contest_id.eq(contest_id))
+            .order(dsl::letter.asc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
+
+impl ContestParticipant {
+    /// Registers a user for a contest; registering again changes nothing.
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::contest_participant::dsl;
+
+        let new_participant = NewContestParticipant {
+            contest_id,
+            user_id,
+            team_id: None,
+        };
+
+        let query = diesel::insert_into(dsl::contest_participant)
+            .values(&new_participant)
+            .on_conflict_do_nothing();
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Registers the members of a team for a contest. Fails without
+    /// registering anybody if one of them already is registered.
+    pub async fn create_team(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        team_id: Uuid,
+        member_ids: &[Uuid],
+    ) -> QueryResult<usize> {
+        use crate::schema::contest_participant::dsl;
+
+        let new_participants: Vec<_> = member_ids
+            .iter()
+            .map(|&user_id| NewContestParticipant {
+                contest_id,
+                user_id,
+                team_id: Some(team_id),
+            })
+            .collect();
+
+        let query = diesel::insert_into(dsl::contest_participant).values(&new_participants);
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn delete(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::contest_participant::dsl;
+
+        let query = diesel::delete(
+            dsl::contest_participant
+                .filter(dsl::contest_id.eq(contest_id))
+                .filter(dsl::user_id.eq(user_id)),
+        );
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn get(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<Option<Self>> {
+        use crate::schema::contest_participant::dsl;
+
+        let query = dsl::contest_participant
+            .filter(dsl::contest_id.eq(contest_id))
+            .filter(dsl::user_id.eq(user_id))
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Like [`get`](Self::get), but also locks the row, and the one of the
+    /// participant's team, until the end of the transaction. Submissions of
+    /// the same participant or team are then checked against the limits one
+    /// after another.
+    pub async fn lock(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<Option<Self>> {
+        use crate::schema::{contest_participant::dsl, team};
+
+        let query = dsl::contest_participant
+            .filter(dsl::contest_id.eq(contest_id))
+            .filter(dsl::user_id.eq(user_id))
+            .select(Self::as_select())
+            .for_update();
+
+        let participant = diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()?;
+
+        // Teammates lock their own rows first, so the team's row is what
+        // makes them wait for each other.
+        if let Some(team_id) = participant.as_ref().and_then(|p: &Self| p.team_id) {
+            let query = team::table.find(team_id).select(team::id).for_update();
+            diesel_async::RunQueryDsl::get_result::<Uuid>(query, conn).await?;
+        }
+
+        Ok(participant)
+    }
+
+    pub async fn exists(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<bool> {
+        use crate::schema::contest_participant::dsl;
+
+        let query = diesel::dsl::select(diesel::dsl::exists(
+            dsl::contest_participant
+                .filter(dsl::contest_id.eq(contest_id))
+                .filter(dsl::user_id.eq(user_id)),
+        ));
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    /// Participants of a contest with their usernames and the names of
+    /// their teams, in order of registration.
+    pub async fn list_for_contest(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+    ) -> QueryResult<Vec<(Self, String, Option<String>)>> {
+        use crate::schema::{contest_participant::dsl, team, user};
+
+        let query = dsl::contest_participant
+            .inner_join(user::table)
+            .left_join(team::table)
+            .filter(dsl::contest_id.eq(contest_id))
+            .order(dsl::registered_at.asc())
+            .select((Self::as_select(), user::username, team::name.nullable()));
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
@@ -1,3 +1,28 @@
+#[macro_use]
+mod text_enum;
+
+pub mod contest;
+pub mod problem;
+pub mod refresh_token;
+pub mod rejudge;
+pub mod run;
+pub mod submission;
+pub mod subtask;
+pub mod subtask_result;
+pub mod team;
+pub mod test_case;
+pub mod test_result;
 pub mod user;
 
+pub use contest::*;
+pub use problem::*;
+pub use refresh_token::*;
+pub use rejudge::*;
+pub use run::*;
+pub use submission::*;
+pub use subtask::*;
+pub use subtask_result::*;
+pub use team::*;
+pub use test_case::*;
+pub use test_result::*;
 pub use user::*;
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+text_enum! {
+    /// How a program's output is compared with the answer.
+    pub enum CheckerKind {
+        /// Byte for byte.
+        Exact => "exact",
+        /// Token by token, ignoring whitespace.
+        Tokens => "tokens",
+        /// Token by token, ignoring whitespace and letter case.
+        CaseInsensitive => "case_insensitive",
+        /// Token by token, numbers within the problem's epsilons.
+        Float => "float",
+        /// A checker program supplied with the problem, speaking its
+        /// [`CheckerProtocol`].
+        Custom => "custom",
+    }
+}
+
+text_enum! {
+    /// How a custom checker or interactor reports its verdict.
+    pub enum CheckerProtocol {
+        /// Through testlib's exit codes.
+        Testlib => "testlib",
+        /// Through the exit codes of Kattis output validators, 42 and 43.
+        Kattis => "kattis",
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::problem)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Problem {
+    pub id: Uuid,
+    pub title: String,
+    pub statement: String,
+    pub time_limit_ms: i32,
+    pub memory_limit_bytes: i64,
+    pub checker: CheckerKind,
+    pub created_at: DateTime<Utc>,
+    pub checker_absolute_epsilon: Option<f64>,
+    pub checker_relative_epsilon: Option<f64>,
+    pub checker_source: Option<String>,
+    pub checker_language: Option<String>,
+    /// Interactive problems come with an interactor deciding the verdict
+    /// instead of the checker.
+    pub interactor_source: Option<String>,
+    pub interactor_language: Option<String>,
+    pub checker_protocol: CheckerProtocol,
+    /// Checks that test inputs are well-formed. Not used in judging.
+    pub validator_source: Option<String>,
+    pub validator_language: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::problem)]
+pub struct NewProblem<'a> {
+    pub title: &'a str,
+    pub statement: &'a str,
+    pub time_limit_ms: i32,
+    pub memory_limit_bytes: i64,
+    pub checker: CheckerKind,
+    pub checker_absolute_epsilon: Option<f64>,
+    pub checker_relative_epsilon: Option<f64>,
+    pub checker_source: Option<&'a str>,
+    pub checker_language: Option<&'a str>,
+    pub interactor_source: Option<&'a str>,
+    pub interactor_language: Option<&'a str>,
+    pub checker_protocol: CheckerProtocol,
+    pub validator_source: Option<&'a str>,
+    pub validator_language: Option<&'a str>,
+}
+
+impl Problem {
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        new_problem: &NewProblem<'_>,
+    ) -> QueryResult<Self> {
+        use crate::schema::problem::dsl;
+
+        let query = diesel::insert_into(dsl::problem)
+            .values(new_problem)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn delete(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<usize> {
+        use crate::schema::problem::dsl;
+
+        let query = diesel::delete(dsl::problem.filter(dsl::id.eq(id)));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::problem::dsl;
+
+        let query = dsl::problem.filter(dsl::id.eq(id));
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    pub async fn list(conn: &mut AsyncPgConnection) -> QueryResult<Vec<Self>> {
+        use crate::schema::problem::dsl;
+
+        let query = dsl::problem.order(dsl::created_at.asc());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    pub async fn update(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        changes: &NewProblem<'_>,
+    ) -> QueryResult<Self> {
+        use crate::schema::problem::dsl;
+
+        let query = diesel::update(dsl::problem.filter(dsl::id.eq(id)))
+            .set((
+                dsl::title.eq(changes.title),
+                dsl::statement.eq(changes.statement),
+                dsl::time_limit_ms.eq(changes.time_limit_ms),
+                dsl::memory_limit_bytes.eq(changes.memory_limit_bytes),
+                dsl::checker.eq(changes.checker),
+                dsl::checker_absolute_epsilon.eq(changes.checker_absolute_epsilon),
+                dsl::checker_relative_epsilon.eq(changes.checker_relative_epsilon),
+                dsl::checker_source.eq(changes.checker_source),
+                dsl::checker_language.eq(changes.checker_language),
+                dsl::interactor_source.eq(changes.interactor_source),
+                dsl::interactor_language.eq(changes.interactor_language),
+                dsl::checker_protocol.eq(changes.checker_protocol),
+                dsl::validator_source.eq(changes.validator_source),
+                dsl::validator_language.eq(changes.validator_language),
+            ))
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+/// A refresh token handed out on login or refresh. Its id is the `jti` of
+/// both the refresh token and the access token issued with it.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::refresh_token)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Shared by all tokens descending from one login.
+    pub family_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    /// Set once the token has been exchanged for a new one.
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// Set once the token and its access token may no longer be used.
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::refresh_token)]
+pub struct NewRefreshToken {
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl RefreshToken {
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        family_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> QueryResult<Self> {
+        use crate::schema::refresh_token::dsl;
+
+        let new_token = NewRefreshToken {
+            user_id,
+            family_id,
+            expires_at,
+        };
+
+        let query = diesel::insert_into(dsl::refresh_token)
+            .values(&new_token)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::refresh_token::dsl;
+
+        let query = dsl::refresh_token
+            .filter(dsl::id.eq(id))
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Marks the token as exchanged. Returns `false` if it already was, in
+    /// which case it has been used twice.
+    pub async fn rotate(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<bool> {
+        use crate::schema::refresh_token::dsl;
+
+        let query = diesel::update(
+            dsl::refresh_token
+                .filter(dsl::id.eq(id))
+                .filter(dsl::rotated_at.is_null()),
+        )
+        .set(dsl::rotated_at.eq(diesel::dsl::now));
+
+        Ok(diesel_async::RunQueryDsl::execute(query, conn).await? == 1)
+    }
+
+    pub async fn revoke_family(
+        conn: &mut AsyncPgConnection,
+        family_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::refresh_token::dsl;
+
+        let query = diesel::update(
+            dsl::refresh_token
+                .filter(dsl::family_id.eq(family_id))
+                .filter(dsl::revoked_at.is_null()),
+        )
+        .set(dsl::revoked_at.eq(diesel::dsl::now));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn revoke_all_for_user(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::refresh_token::dsl;
+
+        let query = diesel::update(
+            dsl::refresh_token
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::revoked_at.is_null()),
+        )
+        .set(dsl::revoked_at.eq(diesel::dsl::now));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Forgets the tokens of a user that can no longer be used anyway.
+    pub async fn delete_expired_for_user(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::refresh_token::dsl;
+
+        let query = diesel::delete(
+            dsl::refresh_token
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::expires_at.lt(diesel::dsl::now)),
+        );
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+use super::{Submission, Verdict};
+
+/// Judging again finished submissions, for instance after fixing the tests
+/// or the checker of a problem.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::rejudge)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Rejudge {
+    pub id: Uuid,
+    /// The user who started the rejudge, if not started from the command
+    /// line.
+    pub created_by: Option<Uuid>,
+    pub problem_id: Option<Uuid>,
+    pub contest_id: Option<Uuid>,
+    pub verdict: Option<Verdict>,
+    pub submission_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Which submissions to rejudge: those matching every filter that is set.
+#[derive(Debug, Default, Insertable)]
+#[diesel(table_name = crate::schema::rejudge)]
+pub struct NewRejudge {
+    pub created_by: Option<Uuid>,
+    pub problem_id: Option<Uuid>,
+    pub contest_id: Option<Uuid>,
+    pub verdict: Option<Verdict>,
+    pub submission_id: Option<Uuid>,
+}
+
+/// The outcome of a submission before a rejudge.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::rejudge_submission)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RejudgeSubmission {
+    pub rejudge_id: Uuid,
+    pub submission_id: Uuid,
+    pub verdict: Option<Verdict>,
+    pub time_ms: Option<i32>,
+    pub memory_bytes: Option<i64>,
+    pub score: Option<f64>,
+}
+
+impl NewRejudge {
+    pub fn has_filters(&self) -> bool {
+        self.problem_id.is_some()
+            || self.contest_id.is_some()
+            || self.verdict.is_some()
+            || self.submission_id.is_some()
+    }
+}
+
+impl Rejudge {
+    /// Creates a rejudge and queues the finished submissions it matches
+    /// again, keeping their outcomes. Returns it with the number of queued
+    /// submissions.
+    ///
+    /// Submissions still being judged are left alone.
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        new_rejudge: &NewRejudge,
+    ) -> QueryResult<(Self, usize)> {
+        use crate::schema::rejudge::dsl;
+
+        let query = diesel::insert_into(dsl::rejudge)
+            .values(new_rejudge)
+            .returning(Self::as_returning());
+
+        let rejudge: Self = diesel_async::RunQueryDsl::get_result(query, conn).await?;
+
+        let query = diesel::sql_query(
+            r#"
+            WITH rejudged AS (
+                SELECT s.id, s.verdict, s.time_ms, s.memory_bytes, s.score
+                FROM submission s, rejudge r
+                WHERE r.id = $1
+                  AND s.status = 'finished'
+                  AND (r.problem_id IS NULL OR s.problem_id = r.problem_id)
+                  AND (r.contest_id IS NULL OR s.contest_id = r.contest_id)
+                  AND (r.verdict IS NULL OR s.verdict = r.verdict)
+                  AND (r.submission_id IS NULL OR s.id = r.submission_id)
+                FOR UPDATE OF s
+            ), history AS (
+                INSERT INTO rejudge_submission
+                    (rejudge_id, submission_id, verdict, time_ms, memory_bytes, score)
+                SELECT $1, id, verdict, time_ms, memory_bytes, score FROM rejudged
+            )
+            UPDATE submission
+            SET status = 'queued', verdict = NULL, time_ms = NULL, memory_bytes = NULL,
+                compile_log = NULL, score = NULL, current_test = NULL, heartbeat_at = NULL
+            FROM rejudged
+            WHERE submission.id = rejudged.id
+            "#,
+        )
+        .bind::<diesel::sql_types::Uuid, _>(rejudge.id);
+
+        let queued = diesel_async::RunQueryDsl::execute(query, conn).await?;
+
+        Ok((rejudge, queued))
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::rejudge::dsl;
+
+        let query = dsl::rejudge
+            .filter(dsl::id.eq(id))
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// All rejudges, newest first.
+    pub async fn list(conn: &mut AsyncPgConnection) -> QueryResult<Vec<Self>> {
+        use crate::schema::rejudge::dsl;
+
+        let query = dsl::rejudge
+            .order(dsl::created_at.desc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
+
+impl RejudgeSubmission {
+    /// Submissions of a rejudge with their outcomes before and now, oldest
+    /// first.
+    ///
+    /// A submission rejudged again later shows the latest outcome.
+    pub async fn list_for_rejudge(
+        conn: &mut AsyncPgConnection,
+        rejudge_id: Uuid,
+    ) -> QueryResult<Vec<(Self, Submission)>> {
+        use crate::schema::{rejudge_submission::dsl, submission};
+
+        let query = dsl::rejudge_submission
+            .inner_join(submission::table)
+            .filter(dsl::rejudge_id.eq(rejudge_id))
+            .order(submission::created_at.asc())
+            .select((Self::as_select(), Submission::as_select()));
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// Whether judging again changed the verdict or the points of the
+    /// submission, which has to be finished for this to mean anything.
+    pub fn is_changed(&self, submission: &Submission) -> bool {
+        self.verdict != submission.verdict || self.score != submission.score
+    }
+}
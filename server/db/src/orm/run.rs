@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+use super::{SubmissionStatus, Verdict};
+
+/// A program run once on input of the user's choosing, outside of any
+/// problem. Goes through the judge like a submission, after them.
+#[derive(Debug, Clone, Queryable, QueryableByName, Selectable)]
+#[diesel(table_name = crate::schema::run)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Run {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub language: String,
+    pub source: String,
+    pub stdin: String,
+    pub status: SubmissionStatus,
+    pub heartbeat_at: Option<DateTime<Utc>>,
+    pub compile_log: Option<String>,
+    /// Why the program did not exit on its own, if it did not.
+    pub verdict: Option<Verdict>,
+    pub exit_code: Option<i32>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub time_ms: Option<i32>,
+    pub memory_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    /// Whether the program was taken from the compile cache.
+    pub compiled_from_cache: Option<bool>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::run)]
+pub struct NewRun<'a> {
+    pub user_id: Uuid,
+    pub language: &'a str,
+    pub source: &'a str,
+    pub stdin: &'a str,
+}
+
+/// What the judge found out about a run.
+#[derive(Debug, Default, AsChangeset)]
+#[diesel(table_name = crate::schema::run)]
+#[diesel(treat_none_as_null = true)]
+pub struct RunOutput {
+    pub compile_log: Option<String>,
+    pub verdict: Option<Verdict>,
+    pub exit_code: Option<i32>,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub time_ms: Option<i32>,
+    pub memory_bytes: Option<i64>,
+    pub compiled_from_cache: Option<bool>,
+}
+
+impl Run {
+    pub async fn create(conn: &mut AsyncPgConnection, new_run: &NewRun<'_>) -> QueryResult<Self> {
+        use crate::schema::run::dsl;
+
+        let query = diesel::insert_into(dsl::run)
+            .values(new_run)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::run::dsl;
+
+        let query = dsl::run.filter(dsl::id.eq(id)).select(Self::as_select());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Runs a user made since `since`.
+    pub async fn count_since(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> QueryResult<i64> {
+        use crate::schema::run::dsl;
+
+        let query = dsl::run
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::created_at.ge(since))
+            .count();
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    /// Forgets the finished runs of a user made before `before`.
+    pub async fn delete_finished_before(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        before: DateTime<Utc>,
+    ) -> QueryResult<usize> {
+        use crate::schema::run::dsl;
+
+        let query = diesel::delete(
+            dsl::run
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::status.eq(SubmissionStatus::Finished))
+                .filter(dsl::created_at.lt(before)),
+        );
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Takes the oldest run waiting for the judge and marks it as compiling,
+    /// like [`Submission::claim_next`](super::Submission::claim_next).
+    pub async fn claim_next(
+        conn: &mut AsyncPgConnection,
+        stale_after: Duration,
+    ) -> QueryResult<Option<Self>> {
+        let query = diesel::sql_query(
+            r#"
+            UPDATE run
+            SET status = 'compiling', heartbeat_at = now()
+            WHERE id = (
+                SELECT id FROM run
+                WHERE status = 'queued'
+                   OR (status <> 'finished' AND heartbeat_at < now() - make_interval(secs => $1))
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind::<diesel::sql_types::Double, _>(stale_after.as_secs_f64());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Tells other workers that the run is still going.
+    pub async fn heartbeat(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<usize> {
+        use crate::schema::run::dsl;
+
+        let query = diesel::update(dsl::run.filter(dsl::id.eq(id)))
+            .set(dsl::heartbeat_at.eq(diesel::dsl::now));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn set_status(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        status: SubmissionStatus,
+    ) -> QueryResult<usize> {
+        use crate::schema::run::dsl;
+
+        let query = diesel::update(dsl::run.filter(dsl::id.eq(id))).set(dsl::status.eq(status));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Stores the output of the run and marks it finished.
+    pub async fn finish(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        output: &RunOutput,
+    ) -> QueryResult<usize> {
+        use crate::schema::run::dsl;
+
+        let query = diesel::update(dsl::run.filter(dsl::id.eq(id))).set((
+            output,
+            dsl::status.eq(SubmissionStatus::Finished),
+            dsl::heartbeat_at.eq(None::<DateTime<Utc>>),
+        ));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+}
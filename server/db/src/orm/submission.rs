@@ -0,0 +1,356 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+text_enum! {
+    /// Where a submission is in the judging pipeline.
+    pub enum SubmissionStatus {
+        Queued => "queued",
+        Compiling => "compiling",
+        Running => "running",
+        Finished => "finished",
+    }
+}
+
+text_enum! {
+    /// Final outcome of judging a submission or a single test.
+    pub enum Verdict {
+        Accepted => "AC",
+        WrongAnswer => "WA",
+        PresentationError => "PE",
+        /// The checker awarded part of the test's points.
+        PartiallyCorrect => "PC",
+        TimeLimitExceeded => "TLE",
+        MemoryLimitExceeded => "MLE",
+        OutputLimitExceeded => "OLE",
+        /// The submission and the interactor were waiting on each other.
+        IdlenessLimitExceeded => "ILE",
+        RuntimeError => "RE",
+        CompilationError => "CE",
+        SecurityViolation => "SV",
+        /// The judge itself failed; the submission needs to be rejudged.
+        JudgementFailed => "FAIL",
+    }
+}
+
+#[derive(Debug, Clone, Queryable, QueryableByName, Selectable)]
+#[diesel(table_name = crate::schema::submission)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Submission {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub problem_id: Uuid,
+    pub language: String,
+    pub source: String,
+    pub status: SubmissionStatus,
+    pub verdict: Option<Verdict>,
+    pub time_ms: Option<i32>,
+    pub memory_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub heartbeat_at: Option<DateTime<Utc>>,
+    pub compile_log: Option<String>,
+    /// Points earned on a problem with subtasks.
+    pub score: Option<f64>,
+    /// Ordinal of the test being run.
+    pub current_test: Option<i32>,
+    /// The contest the submission was made in, if any.
+    pub contest_id: Option<Uuid>,
+    /// Version of the toolchain of `language` that last judged the
+    /// submission.
+    pub language_version: Option<String>,
+    /// Whether the program was taken from the compile cache when last
+    /// judged.
+    pub compiled_from_cache: Option<bool>,
+    /// Set anew by [`claim_next`](Self::claim_next); the worker judging the
+    /// submission only writes to it while this stays the same.
+    pub claim_id: Option<Uuid>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::submission)]
+pub struct NewSubmission<'a> {
+    pub user_id: Uuid,
+    pub problem_id: Uuid,
+    pub language: &'a str,
+    pub source: &'a str,
+    pub contest_id: Option<Uuid>,
+}
+
+impl Submission {
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+        problem_id: Uuid,
+        language: &str,
+        source: &str,
+        contest_id: Option<Uuid>,
+    ) -> QueryResult<Self> {
+        use crate::schema::submission::dsl;
+
+        let new_submission = NewSubmission {
+            user_id,
+            problem_id,
+            language,
+            source,
+            contest_id,
+        };
+
+        let query = diesel::insert_into(dsl::submission)
+            .values(&new_submission)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn delete(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query = diesel::delete(dsl::submission.filter(dsl::id.eq(id)));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::submission::dsl;
+
+        let query = dsl::submission.filter(dsl::id.eq(id));
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Submissions of a user, newest first.
+    pub async fn list_for_user(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::submission::dsl;
+
+        let query = dsl::submission
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::created_at.desc());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// Submissions to a problem, newest first.
+    pub async fn list_for_problem(
+        conn: &mut AsyncPgConnection,
+        problem_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::submission::dsl;
+
+        let query = dsl::submission
+            .filter(dsl::problem_id.eq(problem_id))
+            .order(dsl::created_at.desc());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// Submissions made in a contest, oldest first.
+    pub async fn list_for_contest(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::submission::dsl;
+
+        let query = dsl::submission
+            .filter(dsl::contest_id.eq(contest_id))
+            .order(dsl::created_at.asc());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// Submissions of some participants in a contest, such as the members
+    /// of a team, oldest first.
+    pub async fn list_for_participants(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+        user_ids: &[Uuid],
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::submission::dsl;
+
+        let query = dsl::submission
+            .filter(dsl::contest_id.eq(contest_id))
+            .filter(dsl::user_id.eq_any(user_ids))
+            .order(dsl::created_at.asc());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// Takes the oldest submission waiting to be judged and marks it as
+    /// compiling, under a new [`claim_id`](Self::claim_id).
+    ///
+    /// Submissions whose worker has not sent a heartbeat for `stale_after`
+    /// are considered abandoned and are taken again, which takes them away
+    /// from that worker.
+    pub async fn claim_next(
+        conn: &mut AsyncPgConnection,
+        stale_after: Duration,
+    ) -> QueryResult<Option<Self>> {
+        let query = diesel::sql_query(
+            r#"
+            UPDATE submission
+            SET status = 'compiling', heartbeat_at = now(), verdict = NULL,
+                time_ms = NULL, memory_bytes = NULL, compile_log = NULL,
+                score = NULL, current_test = NULL, compiled_from_cache = NULL,
+                claim_id = gen_random_uuid()
+            WHERE id = (
+                SELECT id FROM submission
+                WHERE status = 'queued'
+                   OR (status <> 'finished' AND heartbeat_at < now() - make_interval(secs => $1))
+                ORDER BY created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind::<diesel::sql_types::Double, _>(stale_after.as_secs_f64());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Tells other workers that the submission is still being judged under
+    /// `claim_id`. Updates nothing once it is not.
+    pub async fn heartbeat(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        claim_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query = diesel::update(
+            dsl::submission
+                .filter(dsl::id.eq(id))
+                .filter(dsl::claim_id.eq(claim_id)),
+        )
+        .set(dsl::heartbeat_at.eq(diesel::dsl::now));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Whether the submission is still judged under `claim_id`. Also locks
+    /// it until the end of the transaction, so it cannot be claimed again
+    /// while results are written for it.
+    pub async fn lock_claimed(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        claim_id: Uuid,
+    ) -> QueryResult<bool> {
+        use crate::schema::submission::dsl;
+
+        let query = dsl::submission
+            .filter(dsl::id.eq(id))
+            .filter(dsl::claim_id.eq(claim_id))
+            .select(dsl::id)
+            .for_update();
+
+        let locked = diesel_async::RunQueryDsl::get_result::<Uuid>(query, conn)
+            .await
+            .optional()?;
+        Ok(locked.is_some())
+    }
+
+    pub async fn set_compile_log(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        compile_log: &str,
+    ) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query = diesel::update(dsl::submission.filter(dsl::id.eq(id)))
+            .set(dsl::compile_log.eq(compile_log));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn set_language_version(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        language_version: &str,
+    ) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query = diesel::update(dsl::submission.filter(dsl::id.eq(id)))
+            .set(dsl::language_version.eq(language_version));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn set_compiled_from_cache(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        compiled_from_cache: bool,
+    ) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query = diesel::update(dsl::submission.filter(dsl::id.eq(id)))
+            .set(dsl::compiled_from_cache.eq(compiled_from_cache));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn set_status(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        status: SubmissionStatus,
+    ) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query =
+            diesel::update(dsl::submission.filter(dsl::id.eq(id))).set(dsl::status.eq(status));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn set_current_test(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        ordinal: i32,
+    ) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query = diesel::update(dsl::submission.filter(dsl::id.eq(id)))
+            .set(dsl::current_test.eq(ordinal));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Stores the outcome of judging and marks the submission finished,
+    /// unless it is no longer judged under `claim_id`.
+    pub async fn finish(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        claim_id: Uuid,
+        verdict: Verdict,
+        time_ms: Option<i32>,
+        memory_bytes: Option<i64>,
+        score: Option<f64>,
+    ) -> QueryResult<usize> {
+        use crate::schema::submission::dsl;
+
+        let query = diesel::update(
+            dsl::submission
+                .filter(dsl::id.eq(id))
+                .filter(dsl::claim_id.eq(claim_id)),
+        )
+        .set((
+            dsl::status.eq(SubmissionStatus::Finished),
+            dsl::verdict.eq(verdict),
+            dsl::time_ms.eq(time_ms),
+            dsl::memory_bytes.eq(memory_bytes),
+            dsl::score.eq(score),
+            dsl::current_test.eq(None::<i32>),
+            dsl::heartbeat_at.eq(None::<DateTime<Utc>>),
+        ));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+}
@@ -0,0 +1,70 @@
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+text_enum! {
+    /// How the scores of a subtask's tests add up to its points.
+    pub enum ScoringPolicy {
+        /// Full points if every test is passed, none otherwise.
+        AllOrNothing => "all_or_nothing",
+        /// Points times the lowest test score.
+        Min => "min",
+        /// Points split evenly between the tests.
+        Sum => "sum",
+    }
+}
+
+/// Points for the tests of a problem sharing `test_group`.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::subtask)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Subtask {
+    pub problem_id: Uuid,
+    pub test_group: i32,
+    pub points: i32,
+    pub scoring: ScoringPolicy,
+    /// Groups that must earn full points for this one to earn any.
+    pub depends_on: Vec<i32>,
+}
+
+impl Subtask {
+    pub async fn create(conn: &mut AsyncPgConnection, subtask: &Subtask) -> QueryResult<Self> {
+        use crate::schema::subtask::dsl;
+
+        let query = diesel::insert_into(dsl::subtask)
+            .values(subtask)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn delete(
+        conn: &mut AsyncPgConnection,
+        problem_id: Uuid,
+        test_group: i32,
+    ) -> QueryResult<usize> {
+        use crate::schema::subtask::dsl;
+
+        let query = diesel::delete(
+            dsl::subtask
+                .filter(dsl::problem_id.eq(problem_id))
+                .filter(dsl::test_group.eq(test_group)),
+        );
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn list_for_problem(
+        conn: &mut AsyncPgConnection,
+        problem_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::subtask::dsl;
+
+        let query = dsl::subtask
+            .filter(dsl::problem_id.eq(problem_id))
+            .order(dsl::test_group.asc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
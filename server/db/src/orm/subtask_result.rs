@@ -0,0 +1,66 @@
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+/// Points a submission earned on one subtask.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::subtask_result)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubtaskResult {
+    pub submission_id: Uuid,
+    pub test_group: i32,
+    pub points: f64,
+}
+
+impl SubtaskResult {
+    pub async fn create(conn: &mut AsyncPgConnection, result: &SubtaskResult) -> QueryResult<Self> {
+        use crate::schema::subtask_result::dsl;
+
+        let query = diesel::insert_into(dsl::subtask_result)
+            .values(result)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn list_for_submission(
+        conn: &mut AsyncPgConnection,
+        submission_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::subtask_result::dsl;
+
+        let query = dsl::subtask_result
+            .filter(dsl::submission_id.eq(submission_id))
+            .order(dsl::test_group.asc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// Points on subtasks of all submissions made in a contest.
+    pub async fn list_for_contest(
+        conn: &mut AsyncPgConnection,
+        contest_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::{submission, subtask_result::dsl};
+
+        let query = dsl::subtask_result
+            .inner_join(submission::table)
+            .filter(submission::contest_id.eq(contest_id))
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    pub async fn delete_for_submission(
+        conn: &mut AsyncPgConnection,
+        submission_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::subtask_result::dsl;
+
+        let query =
+            diesel::delete(dsl::subtask_result.filter(dsl::submission_id.eq(submission_id)));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+}
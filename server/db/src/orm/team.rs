@@ -0,0 +1,234 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::team)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Team {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::team)]
+pub struct NewTeam<'a> {
+    pub name: &'a str,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::team_member)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TeamMember {
+    pub team_id: Uuid,
+    pub user_id: Uuid,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::team_member)]
+pub struct NewTeamMember {
+    pub team_id: Uuid,
+    pub user_id: Uuid,
+}
+
+/// A user asked to join a team by one of its members.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::team_invitation)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TeamInvitation {
+    pub team_id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::team_invitation)]
+pub struct NewTeamInvitation {
+    pub team_id: Uuid,
+    pub user_id: Uuid,
+}
+
+impl Team {
+    pub async fn create(conn: &mut AsyncPgConnection, name: &str) -> QueryResult<Self> {
+        use crate::schema::team::dsl;
+
+        let new_team = NewTeam { name };
+
+        let query = diesel::insert_into(dsl::team)
+            .values(&new_team)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn delete(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<usize> {
+        use crate::schema::team::dsl;
+
+        let query = diesel::delete(dsl::team.filter(dsl::id.eq(id)));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::team::dsl;
+
+        let query = dsl::team.filter(dsl::id.eq(id)).select(Self::as_select());
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Teams a user is a member of, by name.
+    pub async fn list_for_user(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::{team::dsl, team_member};
+
+        let query = dsl::team
+            .inner_join(team_member::table)
+            .filter(team_member::user_id.eq(user_id))
+            .order(dsl::name.asc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    /// Teams a user is invited to, by name.
+    pub async fn list_invited(
+        conn: &mut AsyncPgConnection,
+        user_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::{team::dsl, team_invitation};
+
+        let query = dsl::team
+            .inner_join(team_invitation::table)
+            .filter(team_invitation::user_id.eq(user_id))
+            .order(dsl::name.asc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
+
+impl TeamMember {
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        team_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::team_member::dsl;
+
+        let new_member = NewTeamMember { team_id, user_id };
+
+        let query = diesel::insert_into(dsl::team_member)
+            .values(&new_member)
+            .on_conflict_do_nothing();
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn delete(
+        conn: &mut AsyncPgConnection,
+        team_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::team_member::dsl;
+
+        let query = diesel::delete(
+            dsl::team_member
+                .filter(dsl::team_id.eq(team_id))
+                .filter(dsl::user_id.eq(user_id)),
+        );
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn exists(
+        conn: &mut AsyncPgConnection,
+        team_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<bool> {
+        use crate::schema::team_member::dsl;
+
+        let query = diesel::dsl::select(diesel::dsl::exists(
+            dsl::team_member
+                .filter(dsl::team_id.eq(team_id))
+                .filter(dsl::user_id.eq(user_id)),
+        ));
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    /// Members of a team with their usernames, in order of joining.
+    pub async fn list_for_team(
+        conn: &mut AsyncPgConnection,
+        team_id: Uuid,
+    ) -> QueryResult<Vec<(Self, String)>> {
+        use crate::schema::{team_member::dsl, user};
+
+        let query = dsl::team_member
+            .inner_join(user::table)
+            .filter(dsl::team_id.eq(team_id))
+            .order(dsl::joined_at.asc())
+            .select((Self::as_select(), user::username));
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
+
+impl TeamInvitation {
+    /// Invites a user to a team; inviting again changes nothing.
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        team_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::team_invitation::dsl;
+
+        let new_invitation = NewTeamInvitation { team_id, user_id };
+
+        let query = diesel::insert_into(dsl::team_invitation)
+            .values(&new_invitation)
+            .on_conflict_do_nothing();
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn delete(
+        conn: &mut AsyncPgConnection,
+        team_id: Uuid,
+        user_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::team_invitation::dsl;
+
+        let query = diesel::delete(
+            dsl::team_invitation
+                .filter(dsl::team_id.eq(team_id))
+                .filter(dsl::user_id.eq(user_id)),
+        );
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    /// Users invited to a team with their usernames, oldest invitation
+    /// first.
+    pub async fn list_for_team(
+        conn: &mut AsyncPgConnection,
+        team_id: Uuid,
+    ) -> QueryResult<Vec<(Self, String)>> {
+        use crate::schema::{team_invitation::dsl, user};
+
+        let query = dsl::team_invitation
+            .inner_join(user::table)
+            .filter(dsl::team_id.eq(team_id))
+            .order(dsl::created_at.asc())
+            .select((Self::as_select(), user::username));
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
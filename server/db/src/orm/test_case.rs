@@ -0,0 +1,80 @@
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+/// One test of a problem. Input and answer are stored either inline or as a
+/// path relative to the problem's data directory.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::test_case)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TestCase {
+    pub id: Uuid,
+    pub problem_id: Uuid,
+    pub ordinal: i32,
+    pub test_group: i32,
+    pub is_sample: bool,
+    pub input: Option<Vec<u8>>,
+    pub input_path: Option<String>,
+    pub answer: Option<Vec<u8>>,
+    pub answer_path: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::test_case)]
+pub struct NewTestCase<'a> {
+    pub problem_id: Uuid,
+    pub ordinal: i32,
+    pub test_group: i32,
+    pub is_sample: bool,
+    pub input: Option<&'a [u8]>,
+    pub input_path: Option<&'a str>,
+    pub answer: Option<&'a [u8]>,
+    pub answer_path: Option<&'a str>,
+}
+
+impl TestCase {
+    pub async fn create(
+        conn: &mut AsyncPgConnection,
+        new_test_case: &NewTestCase<'_>,
+    ) -> QueryResult<Self> {
+        use crate::schema::test_case::dsl;
+
+        let query = diesel::insert_into(dsl::test_case)
+            .values(new_test_case)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    pub async fn delete(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<usize> {
+        use crate::schema::test_case::dsl;
+
+        let query = diesel::delete(dsl::test_case.filter(dsl::id.eq(id)));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn get(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<Option<Self>> {
+        use crate::schema::test_case::dsl;
+
+        let query = dsl::test_case.filter(dsl::id.eq(id));
+
+        diesel_async::RunQueryDsl::get_result(query, conn)
+            .await
+            .optional()
+    }
+
+    /// Tests of a problem in the order they are judged.
+    pub async fn list_for_problem(
+        conn: &mut AsyncPgConnection,
+        problem_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::test_case::dsl;
+
+        let query = dsl::test_case
+            .filter(dsl::problem_id.eq(problem_id))
+            .order(dsl::ordinal.asc());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+}
@@ -0,0 +1,59 @@
+use diesel::prelude::*;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+use super::Verdict;
+
+/// Outcome of running a submission on one test.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::test_result)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TestResult {
+    pub submission_id: Uuid,
+    pub test_case_id: Uuid,
+    pub verdict: Verdict,
+    pub time_ms: i32,
+    pub memory_bytes: i64,
+    /// Share of the test's points earned, from 0 to 1.
+    pub score: f64,
+    pub checker_message: Option<String>,
+}
+
+impl TestResult {
+    pub async fn create(conn: &mut AsyncPgConnection, result: &TestResult) -> QueryResult<Self> {
+        use crate::schema::test_result::dsl;
+
+        let query = diesel::insert_into(dsl::test_result)
+            .values(result)
+            .returning(Self::as_returning());
+
+        diesel_async::RunQueryDsl::get_result(query, conn).await
+    }
+
+    /// Results of a submission in the order its tests were judged.
+    pub async fn list_for_submission(
+        conn: &mut AsyncPgConnection,
+        submission_id: Uuid,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::{test_case, test_result::dsl};
+
+        let query = dsl::test_result
+            .inner_join(test_case::table)
+            .filter(dsl::submission_id.eq(submission_id))
+            .order(test_case::ordinal.asc())
+            .select(Self::as_select());
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    pub async fn delete_for_submission(
+        conn: &mut AsyncPgConnection,
+        submission_id: Uuid,
+    ) -> QueryResult<usize> {
+        use crate::schema::test_result::dsl;
+
+        let query = diesel::delete(dsl::test_result.filter(dsl::submission_id.eq(submission_id)));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+}
@@ -2,6 +2,28 @@ use diesel::prelude::*;
 use diesel_async::AsyncPgConnection;
 use uuid::Uuid;
 
+text_enum! {
+    /// How `password_hash` was derived from what the client sends.
+    pub enum PasswordScheme {
+        /// The client-side hash itself, from before the server hashed
+        /// passwords. Such rows are rehashed into `Argon2` ones by the
+        /// launcher after migrating and never accepted at login.
+        Legacy => "legacy",
+        /// An Argon2 PHC string over the client-side hash.
+        Argon2 => "argon2",
+    }
+}
+
+text_enum! {
+    /// What a user is trusted with, see `web::auth::Permission`.
+    pub enum Role {
+        Admin => "admin",
+        ProblemSetter => "problem_setter",
+        ContestManager => "contest_manager",
+        Contestant => "contestant",
+    }
+}
+
 #[derive(Debug, Clone, Queryable, Selectable)]
 #[diesel(table_name = crate::schema::user)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -10,6 +32,8 @@ pub struct User {
     pub username: String,
     pub salt: Vec<u8>,
     pub password_hash: Vec<u8>,
+    pub password_scheme: PasswordScheme,
+    pub role: Role,
 }
 
 #[derive(Insertable)]
@@ -18,6 +42,7 @@ pub struct NewUser<'a> {
     pub username: &'a str,
     pub salt: &'a [u8],
     pub password_hash: &'a [u8],
+    pub password_scheme: PasswordScheme,
 }
 
 impl User {
@@ -26,6 +51,7 @@ impl User {
         username: &str,
         salt: &[u8],
         password_hash: &[u8],
+        password_scheme: PasswordScheme,
     ) -> QueryResult<Self> {
         use crate::schema::user::dsl;
 
@@ -33,6 +59,7 @@ impl User {
             username,
             salt,
             password_hash,
+            password_scheme,
         };
 
         let query = diesel::insert_into(dsl::user)
@@ -60,6 +87,21 @@ impl User {
             .optional()
     }
 
+    /// Locks the user's row until the end of the transaction, so that
+    /// requests checking a per-user limit run one after another.
+    pub async fn lock(conn: &mut AsyncPgConnection, id: Uuid) -> QueryResult<()> {
+        use crate::schema::user::dsl;
+
+        let query = dsl::user
+            .filter(dsl::id.eq(id))
+            .select(dsl::id)
+            .for_update();
+
+        diesel_async::RunQueryDsl::get_result::<Uuid>(query, conn)
+            .await
+            .map(drop)
+    }
+
     pub async fn get_by_username(
         conn: &mut AsyncPgConnection,
         username: &str,
@@ -73,6 +115,63 @@ impl User {
             .optional()
     }
 
+    pub async fn list_by_password_scheme(
+        conn: &mut AsyncPgConnection,
+        password_scheme: PasswordScheme,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::user::dsl;
+
+        let query = dsl::user.filter(dsl::password_scheme.eq(password_scheme));
+
+        diesel_async::RunQueryDsl::load(query, conn).await
+    }
+
+    pub async fn set_password_hash(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        password_hash: &[u8],
+        password_scheme: PasswordScheme,
+    ) -> QueryResult<usize> {
+        use crate::schema::user::dsl;
+
+        let query = diesel::update(dsl::user.filter(dsl::id.eq(id))).set((
+            dsl::password_hash.eq(password_hash),
+            dsl::password_scheme.eq(password_scheme),
+        ));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn set_password(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        salt: &[u8],
+        password_hash: &[u8],
+        password_scheme: PasswordScheme,
+    ) -> QueryResult<usize> {
+        use crate::schema::user::dsl;
+
+        let query = diesel::update(dsl::user.filter(dsl::id.eq(id))).set((
+            dsl::salt.eq(salt),
+            dsl::password_hash.eq(password_hash),
+            dsl::password_scheme.eq(password_scheme),
+        ));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
+    pub async fn set_role(
+        conn: &mut AsyncPgConnection,
+        id: Uuid,
+        role: Role,
+    ) -> QueryResult<usize> {
+        use crate::schema::user::dsl;
+
+        let query = diesel::update(dsl::user.filter(dsl::id.eq(id))).set(dsl::role.eq(role));
+
+        diesel_async::RunQueryDsl::execute(query, conn).await
+    }
+
     pub async fn exists_with_username(
         conn: &mut AsyncPgConnection,
         username: &str,
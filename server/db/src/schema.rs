@@ -1,5 +1,230 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    contest (id) {
+        id -> Uuid,
+        #[max_length = 256]
+        title -> Varchar,
+        starts_at -> Timestamptz,
+        ends_at -> Timestamptz,
+        freeze_minutes -> Int4,
+        penalty_minutes -> Int4,
+        unfrozen_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        #[max_length = 16]
+        rules -> Varchar,
+        #[max_length = 16]
+        scoring -> Varchar,
+        #[max_length = 16]
+        feedback -> Varchar,
+        submission_limit -> Nullable<Int4>,
+        submission_delay_seconds -> Int4,
+    }
+}
+
+diesel::table! {
+    contest_participant (contest_id, user_id) {
+        contest_id -> Uuid,
+        user_id -> Uuid,
+        registered_at -> Timestamptz,
+        team_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    contest_problem (contest_id, letter) {
+        contest_id -> Uuid,
+        problem_id -> Uuid,
+        #[max_length = 8]
+        letter -> Varchar,
+    }
+}
+
+diesel::table! {
+    problem (id) {
+        id -> Uuid,
+        #[max_length = 256]
+        title -> Varchar,
+        statement -> Text,
+        time_limit_ms -> Int4,
+        memory_limit_bytes -> Int8,
+        #[max_length = 32]
+        checker -> Varchar,
+        created_at -> Timestamptz,
+        checker_absolute_epsilon -> Nullable<Float8>,
+        checker_relative_epsilon -> Nullable<Float8>,
+        checker_source -> Nullable<Text>,
+        #[max_length = 64]
+        checker_language -> Nullable<Varchar>,
+        interactor_source -> Nullable<Text>,
+        #[max_length = 64]
+        interactor_language -> Nullable<Varchar>,
+        #[max_length = 16]
+        checker_protocol -> Varchar,
+        validator_source -> Nullable<Text>,
+        #[max_length = 64]
+        validator_language -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    refresh_token (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        family_id -> Uuid,
+        expires_at -> Timestamptz,
+        rotated_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    rejudge (id) {
+        id -> Uuid,
+        created_by -> Nullable<Uuid>,
+        problem_id -> Nullable<Uuid>,
+        contest_id -> Nullable<Uuid>,
+        #[max_length = 8]
+        verdict -> Nullable<Varchar>,
+        submission_id -> Nullable<Uuid>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    rejudge_submission (rejudge_id, submission_id) {
+        rejudge_id -> Uuid,
+        submission_id -> Uuid,
+        #[max_length = 8]
+        verdict -> Nullable<Varchar>,
+        time_ms -> Nullable<Int4>,
+        memory_bytes -> Nullable<Int8>,
+        score -> Nullable<Float8>,
+    }
+}
+
+diesel::table! {
+    run (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 64]
+        language -> Varchar,
+        source -> Text,
+        stdin -> Text,
+        #[max_length = 16]
+        status -> Varchar,
+        heartbeat_at -> Nullable<Timestamptz>,
+        compile_log -> Nullable<Text>,
+        #[max_length = 8]
+        verdict -> Nullable<Varchar>,
+        exit_code -> Nullable<Int4>,
+        stdout -> Nullable<Text>,
+        stderr -> Nullable<Text>,
+        time_ms -> Nullable<Int4>,
+        memory_bytes -> Nullable<Int8>,
+        created_at -> Timestamptz,
+        compiled_from_cache -> Nullable<Bool>,
+    }
+}
+
+diesel::table! {
+    submission (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        problem_id -> Uuid,
+        #[max_length = 64]
+        language -> Varchar,
+        source -> Text,
+        #[max_length = 16]
+        status -> Varchar,
+        #[max_length = 8]
+        verdict -> Nullable<Varchar>,
+        time_ms -> Nullable<Int4>,
+        memory_bytes -> Nullable<Int8>,
+        created_at -> Timestamptz,
+        heartbeat_at -> Nullable<Timestamptz>,
+        compile_log -> Nullable<Text>,
+        score -> Nullable<Float8>,
+        current_test -> Nullable<Int4>,
+        contest_id -> Nullable<Uuid>,
+        language_version -> Nullable<Text>,
+        compiled_from_cache -> Nullable<Bool>,
+        claim_id -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    subtask (problem_id, test_group) {
+        problem_id -> Uuid,
+        test_group -> Int4,
+        points -> Int4,
+        #[max_length = 16]
+        scoring -> Varchar,
+        depends_on -> Array<Int4>,
+    }
+}
+
+diesel::table! {
+    subtask_result (submission_id, test_group) {
+        submission_id -> Uuid,
+        test_group -> Int4,
+        points -> Float8,
+    }
+}
+
+diesel::table! {
+    team (id) {
+        id -> Uuid,
+        #[max_length = 64]
+        name -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    team_invitation (team_id, user_id) {
+        team_id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    team_member (team_id, user_id) {
+        team_id -> Uuid,
+        user_id -> Uuid,
+        joined_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    test_case (id) {
+        id -> Uuid,
+        problem_id -> Uuid,
+        ordinal -> Int4,
+        test_group -> Int4,
+        is_sample -> Bool,
+        input -> Nullable<Bytea>,
+        input_path -> Nullable<Text>,
+        answer -> Nullable<Bytea>,
+        answer_path -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    test_result (submission_id, test_case_id) {
+        submission_id -> Uuid,
+        test_case_id -> Uuid,
+        #[max_length = 8]
+        verdict -> Varchar,
+        time_ms -> Int4,
+        memory_bytes -> Int8,
+        score -> Float8,
+        checker_message -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     user (id) {
         id -> Uuid,
@@ -7,5 +232,54 @@ diesel::table! {
         username -> Varchar,
         salt -> Bytea,
         password_hash -> Bytea,
+        #[max_length = 16]
+        password_scheme -> Varchar,
+        #[max_length = 32]
+        role -> Varchar,
     }
 }
+
+diesel::joinable!(contest_participant -> contest (contest_id));
+diesel::joinable!(contest_participant -> team (team_id));
+diesel::joinable!(contest_participant -> user (user_id));
+diesel::joinable!(contest_problem -> contest (contest_id));
+diesel::joinable!(contest_problem -> problem (problem_id));
+diesel::joinable!(refresh_token -> user (user_id));
+diesel::joinable!(rejudge -> contest (contest_id));
+diesel::joinable!(rejudge -> problem (problem_id));
+diesel::joinable!(rejudge -> user (created_by));
+diesel::joinable!(rejudge_submission -> rejudge (rejudge_id));
+diesel::joinable!(rejudge_submission -> submission (submission_id));
+diesel::joinable!(run -> user (user_id));
+diesel::joinable!(submission -> contest (contest_id));
+diesel::joinable!(submission -> problem (problem_id));
+diesel::joinable!(submission -> user (user_id));
+diesel::joinable!(subtask -> problem (problem_id));
+diesel::joinable!(subtask_result -> submission (submission_id));
+diesel::joinable!(team_invitation -> team (team_id));
+diesel::joinable!(team_invitation -> user (user_id));
+diesel::joinable!(team_member -> team (team_id));
+diesel::joinable!(team_member -> user (user_id));
+diesel::joinable!(test_case -> problem (problem_id));
+diesel::joinable!(test_result -> submission (submission_id));
+diesel::joinable!(test_result -> test_case (test_case_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    contest,
+    contest_participant,
+    contest_problem,
+    problem,
+    refresh_token,
+    rejudge,
+    rejudge_submission,
+    run,
+    submission,
+    subtask,
+    subtask_result,
+    team,
+    team_invitation,
+    team_member,
+    test_case,
+    test_result,
+    user,
+);
@@ -0,0 +1,94 @@
+use db::orm::Verdict;
+
+use super::CheckOutcome;
+
+pub fn exact(output: &[u8], answer: &[u8]) -> CheckOutcome {
+    if output == answer {
+        return CheckOutcome::accepted();
+    }
+
+    let offset = output
+        .iter()
+        .zip(answer)
+        .position(|(a, b)| a != b)
+        .unwrap_or(output.len().min(answer.len()));
+    CheckOutcome::rejected(
+        Verdict::WrongAnswer,
+        format!("Output differs from the answer at byte {offset}"),
+    )
+}
+
+/// Compares whitespace separated tokens pairwise with `matches`.
+pub fn tokens(
+    output: &[u8],
+    answer: &[u8],
+    matches: impl Fn(&[u8], &[u8]) -> bool,
+) -> CheckOutcome {
+    let mut output = split(output);
+    let mut answer = split(answer);
+    let mut index = 1;
+
+    loop {
+        let message = match (output.next(), answer.next()) {
+            (None, None) => return CheckOutcome::accepted(),
+            (Some(found), Some(expected)) if matches(found, expected) => {
+                index += 1;
+                continue;
+            }
+            (Some(found), Some(expected)) => format!(
+                "Token {index} differs: expected {}, found {}",
+                quote(expected),
+                quote(found)
+            ),
+            (None, Some(expected)) => {
+                format!(
+                    "Output ended before token {index}, expected {}",
+                    quote(expected)
+                )
+            }
+            (Some(found), None) => format!("Extra token {index} in output: {}", quote(found)),
+        };
+
+        return CheckOutcome::rejected(Verdict::WrongAnswer, message);
+    }
+}
+
+/// Whether `found` is within `absolute` or `relative` tolerance of
+/// `expected` if both are numbers, or is the same token otherwise.
+pub fn floats_match(found: &[u8], expected: &[u8], absolute: f64, relative: f64) -> bool {
+    if found == expected {
+        return true;
+    }
+
+    let (Some(found), Some(expected)) = (number(found), number(expected)) else {
+        return false;
+    };
+    let difference = (found - expected).abs();
+
+    difference <= absolute || difference <= relative * expected.abs()
+}
+
+fn number(token: &[u8]) -> Option<f64> {
+    std::str::from_utf8(token)
+        .ok()?
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+}
+
+fn split(text: &[u8]) -> impl Iterator<Item = &[u8]> {
+    text.split(u8::is_ascii_whitespace)
+        .filter(|token| !token.is_empty())
+}
+
+/// A token for a message, shortened if it is too long to be useful.
+fn quote(token: &[u8]) -> String {
+    const MAX_LEN: usize = 64;
+
+    let text = String::from_utf8_lossy(&token[..token.len().min(MAX_LEN)]);
+    if token.len() > MAX_LEN {
+        format!("\"{text}...\"")
+    } else {
+        format!("\"{text}\"")
+    }
+}
@@ -0,0 +1,44 @@
+use std::{io, path::Path};
+
+use db::orm::Verdict;
+use language_module::{RunOutcome, Termination};
+
+use super::CheckOutcome;
+
+/// Exit codes of the Kattis output validator protocol.
+const ACCEPTED: i32 = 42;
+const WRONG_ANSWER: i32 = 43;
+
+/// Where a validator started with `feedback_dir` leaves its messages.
+const JUDGE_MESSAGE: &str = "judgemessage.txt";
+
+/// Verdict reported by a Kattis output validator or interactor, which was
+/// started with `feedback_dir` as its last argument.
+pub(crate) fn interpret(outcome: &RunOutcome, feedback_dir: &Path) -> CheckOutcome {
+    let message = std::fs::read_to_string(feedback_dir.join(JUDGE_MESSAGE))
+        .map(|message| message.trim().to_string())
+        .unwrap_or_default();
+
+    match outcome.status {
+        Termination::Exited(ACCEPTED) => CheckOutcome {
+            message: Some(message).filter(|message| !message.is_empty()),
+            ..CheckOutcome::accepted()
+        },
+        Termination::Exited(WRONG_ANSWER) => CheckOutcome::rejected(Verdict::WrongAnswer, message),
+        status => CheckOutcome::rejected(
+            Verdict::JudgementFailed,
+            format!("Validator did not finish properly: {status}\n{message}"),
+        ),
+    }
+}
+
+/// Creates an empty feedback directory at `path`, dropping what a previous
+/// run left there.
+pub(crate) fn reset_feedback_dir(path: &Path) -> io::Result<()> {
+    match std::fs::remove_dir_all(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    std::fs::create_dir_all(path)
+}
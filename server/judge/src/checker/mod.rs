@@ -0,0 +1,98 @@
+mod builtin;
+pub(crate) mod kattis;
+pub(crate) mod testlib;
+
+use db::orm::{CheckerKind, Problem, Verdict};
+
+pub use testlib::CustomChecker;
+
+/// Tolerance of the float checker when a problem does not set one.
+pub const DEFAULT_EPSILON: f64 = 1e-6;
+
+/// Decides whether a program's output answers a test.
+pub enum Checker {
+    Exact,
+    Tokens,
+    CaseInsensitive,
+    /// Numbers are accepted if they are within `absolute` or `relative`
+    /// tolerance of the answer, other tokens must match exactly.
+    Float {
+        absolute: f64,
+        relative: f64,
+    },
+    Custom(CustomChecker),
+}
+
+/// What a checker made of one output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckOutcome {
+    pub verdict: Verdict,
+    /// Share of the test's points earned, from 0 to 1.
+    pub score: f64,
+    /// Explanation meant for the problem's authors.
+    pub message: Option<String>,
+}
+
+impl CheckOutcome {
+    pub fn accepted() -> Self {
+        Self {
+            verdict: Verdict::Accepted,
+            score: 1.0,
+            message: None,
+        }
+    }
+
+    pub fn rejected(verdict: Verdict, message: impl Into<String>) -> Self {
+        Self {
+            verdict,
+            score: 0.0,
+            message: Some(message.into()),
+        }
+    }
+}
+
+impl Checker {
+    /// The built-in checker `problem` is configured with, `None` if it comes
+    /// with a checker program of its own.
+    pub fn builtin(problem: &Problem) -> Option<Self> {
+        let checker = match problem.checker {
+            CheckerKind::Exact => Self::Exact,
+            CheckerKind::Tokens => Self::Tokens,
+            CheckerKind::CaseInsensitive => Self::CaseInsensitive,
+            CheckerKind::Float => {
+                let (absolute, relative) = match (
+                    problem.checker_absolute_epsilon,
+                    problem.checker_relative_epsilon,
+                ) {
+                    (None, None) => (DEFAULT_EPSILON, DEFAULT_EPSILON),
+                    (absolute, relative) => (absolute.unwrap_or(0.0), relative.unwrap_or(0.0)),
+                };
+                Self::Float { absolute, relative }
+            }
+            CheckerKind::Custom => return None,
+        };
+
+        Some(checker)
+    }
+
+    /// Checks `output` of a program given `input` against the expected
+    /// `answer`.
+    ///
+    /// Blocks while a custom checker runs.
+    pub fn check(
+        &self,
+        input: &[u8],
+        output: &[u8],
+        answer: &[u8],
+    ) -> std::io::Result<CheckOutcome> {
+        Ok(match self {
+            Self::Exact => builtin::exact(output, answer),
+            Self::Tokens => builtin::tokens(output, answer, |a, b| a == b),
+            Self::CaseInsensitive => builtin::tokens(output, answer, <[u8]>::eq_ignore_ascii_case),
+            Self::Float { absolute, relative } => builtin::tokens(output, answer, |a, b| {
+                builtin::floats_match(a, b, *absolute, *relative)
+            }),
+            Self::Custom(checker) => return checker.check(input, output, answer),
+        })
+    }
+}
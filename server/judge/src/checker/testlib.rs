@@ -0,0 +1,354 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use db::orm::{CheckerProtocol, Verdict};
+use language_module::{
+    Artifact, CompileError, LanguageModule, Limits, RunOutcome, Sandbox, Termination,
+};
+
+use super::{kattis, CheckOutcome};
+
+/// Exit codes of the testlib protocol.
+const OK: i32 = 0;
+const WRONG_ANSWER: i32 = 1;
+const PRESENTATION_ERROR: i32 = 2;
+const FAIL: i32 = 3;
+const DIRT: i32 = 4;
+const POINTS: i32 = 7;
+const UNEXPECTED_EOF: i32 = 8;
+/// `_pc(x)` exits with this plus `x`, the percentage of points earned.
+const PARTIALLY_CORRECT: i32 = 16;
+
+/// A checker program written against testlib, or a Kattis output
+/// validator.
+///
+/// A testlib checker is started as `checker <input> <output> <answer>` and
+/// reports through its exit code. Partial points come either from `quitp`,
+/// whose message reads `points <share>` with the share of the test's points
+/// earned from 0 to 1, or from `_pc(x)`, which adds the percentage earned to
+/// the exit code. A Kattis
+/// validator is started as `checker <input> <answer> <feedback_dir>` with
+/// the output on its stdin.
+pub struct CustomChecker {
+    module: Arc<dyn LanguageModule>,
+    sandbox: Sandbox,
+    artifact: Artifact,
+    protocol: CheckerProtocol,
+}
+
+impl CustomChecker {
+    /// Builds the checker in `workdir`, next to copies of the files in
+    /// `support_dir` (such as `testlib.h`) if it exists.
+    pub fn compile(
+        module: Arc<dyn LanguageModule>,
+        sandbox: Sandbox,
+        source: &str,
+        workdir: &Path,
+        support_dir: &Path,
+    ) -> Result<Self, CompileError> {
+        sandbox.prepare_workdir(workdir)?;
+        copy_support_files(support_dir, workdir)?;
+
+        let artifact = module.compile(&sandbox, source, workdir)?;
+
+        Ok(Self {
+            module,
+            sandbox,
+            artifact,
+            protocol: CheckerProtocol::Testlib,
+        })
+    }
+
+    pub fn speaking(self, protocol: CheckerProtocol) -> Self {
+        Self { protocol, ..self }
+    }
+
+    pub fn check(&self, input: &[u8], output: &[u8], answer: &[u8]) -> io::Result<CheckOutcome> {
+        let invocation = self.module.invocation(&self.artifact);
+
+        match self.protocol {
+            CheckerProtocol::Testlib => {
+                let files = [
+                    self.write("input.txt", input)?,
+                    self.write("output.txt", output)?,
+                    self.write("answer.txt", answer)?,
+                ];
+                let outcome =
+                    self.sandbox
+                        .execute(&invocation.args(files), &[], &Limits::CHECKER, None)?;
+
+                Ok(interpret(&outcome))
+            }
+            CheckerProtocol::Kattis => {
+                let feedback_dir = self.artifact.workdir.join("feedback");
+                kattis::reset_feedback_dir(&feedback_dir)?;
+                let args = [
+                    self.write("input.txt", input)?,
+                    self.write("answer.txt", answer)?,
+                    feedback_dir.clone(),
+                ];
+                let outcome =
+                    self.sandbox
+                        .execute(&invocation.args(args), output, &Limits::CHECKER, None)?;
+
+                Ok(kattis::interpret(&outcome, &feedback_dir))
+            }
+        }
+    }
+
+    fn write(&self, name: &str, data: &[u8]) -> io::Result<PathBuf> {
+        let path = self.artifact.workdir.join(name);
+        std::fs::write(&path, data)?;
+        Ok(path)
+    }
+}
+
+/// Verdict reported through the exit code of a checker or interactor.
+pub(crate) fn interpret(outcome: &RunOutcome) -> CheckOutcome {
+    let message = message(outcome);
+
+    let verdict = match outcome.status {
+        Termination::Exited(OK) => Verdict::Accepted,
+        Termination::Exited(WRONG_ANSWER) => Verdict::WrongAnswer,
+        Termination::Exited(PRESENTATION_ERROR | DIRT | UNEXPECTED_EOF) => {
+            Verdict::PresentationError
+        }
+        Termination::Exited(POINTS) => return points(message),
+        Termination::Exited(FAIL) => Verdict::JudgementFailed,
+        Termination::Exited(code @ PARTIALLY_CORRECT..) => {
+            return partially_correct(code - PARTIALLY_CORRECT, message)
+        }
+        status => {
+            return CheckOutcome::rejected(
+                Verdict::JudgementFailed,
+                format!("Checker did not finish properly: {status}\n{message}"),
+            )
+        }
+    };
+
+    CheckOutcome {
+        verdict,
+        score: if verdict == Verdict::Accepted {
+            1.0
+        } else {
+            0.0
+        },
+        message: Some(message).filter(|message| !message.is_empty()),
+    }
+}
+
+/// `quitp`: testlib prefixes the message with `points`, checkers printing
+/// to stdout may not.
+fn points(message: String) -> CheckOutcome {
+    let mut words = message.split_whitespace().peekable();
+    words.next_if_eq(&"points");
+    let score = words
+        .next()
+        .and_then(|word| word.parse::<f64>().ok())
+        .filter(|score| (0.0..=1.0).contains(score));
+
+    match score {
+        Some(score) => scored(score, message),
+        None => CheckOutcome::rejected(
+            Verdict::JudgementFailed,
+            format!("Checker awarded points without a share from 0 to 1: {message}"),
+        ),
+    }
+}
+
+/// `_pc(percent)`.
+fn partially_correct(percent: i32, message: String) -> CheckOutcome {
+    if percent > 100 {
+        return CheckOutcome::rejected(
+            Verdict::JudgementFailed,
+            format!("Checker awarded more than 100% of the points: {percent}\n{message}"),
+        );
+    }
+
+    scored(f64::from(percent) / 100.0, message)
+}
+
+fn scored(score: f64, message: String) -> CheckOutcome {
+    let verdict = if score == 1.0 {
+        Verdict::Accepted
+    } else if score == 0.0 {
+        Verdict::WrongAnswer
+    } else {
+        Verdict::PartiallyCorrect
+    };
+
+    CheckOutcome {
+        verdict,
+        score,
+        message: Some(message),
+    }
+}
+
+/// testlib reports to stderr, but plenty of checkers print to stdout.
+fn message(outcome: &RunOutcome) -> String {
+    let stream = if outcome.stderr.iter().all(u8::is_ascii_whitespace) {
+        &outcome.stdout
+    } else {
+        &outcome.stderr
+    };
+
+    String::from_utf8_lossy(stream).trim().to_string()
+}
+
+pub(crate) fn copy_support_files(from: &Path, to: &Path) -> io::Result<()> {
+    let entries = match std::fs::read_dir(from) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// What a testlib checker leaves behind: its exit code and its report on
+    /// stderr.
+    fn exited(code: i32, stderr: &str) -> RunOutcome {
+        RunOutcome {
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+            status: Termination::Exited(code),
+            wall_time: Duration::ZERO,
+            cpu_time: Duration::ZERO,
+            peak_memory: 0,
+        }
+    }
+
+    #[test]
+    fn ok() {
+        let outcome = interpret(&exited(0, "ok 1 number(s): \"3\"\n"));
+
+        assert_eq!(outcome.verdict, Verdict::Accepted);
+        assert_eq!(outcome.score, 1.0);
+        assert_eq!(outcome.message.as_deref(), Some("ok 1 number(s): \"3\""));
+    }
+
+    #[test]
+    fn wrong_answer() {
+        let outcome = interpret(&exited(
+            1,
+            "wrong answer 1st numbers differ - expected: '3', found: '4'\n",
+        ));
+
+        assert_eq!(outcome.verdict, Verdict::WrongAnswer);
+        assert_eq!(outcome.score, 0.0);
+    }
+
+    #[test]
+    fn presentation_error() {
+        let outcome = interpret(&exited(
+            2,
+            "wrong output format Extra information in the output file\n",
+        ));
+
+        assert_eq!(outcome.verdict, Verdict::PresentationError);
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let outcome = interpret(&exited(
+            8,
+            "wrong output format Unexpected end of file - int32 expected\n",
+        ));
+
+        assert_eq!(outcome.verdict, Verdict::PresentationError);
+        assert_eq!(outcome.score, 0.0);
+    }
+
+    #[test]
+    fn fail() {
+        let outcome = interpret(&exited(3, "FAIL Answer file is empty\n"));
+
+        assert_eq!(outcome.verdict, Verdict::JudgementFailed);
+    }
+
+    #[test]
+    fn quitp() {
+        let outcome = interpret(&exited(7, "points 0.25 one of four queries answered\n"));
+
+        assert_eq!(outcome.verdict, Verdict::PartiallyCorrect);
+        assert_eq!(outcome.score, 0.25);
+        assert_eq!(
+            outcome.message.as_deref(),
+            Some("points 0.25 one of four queries answered")
+        );
+    }
+
+    #[test]
+    fn quitp_full_and_none() {
+        assert_eq!(
+            interpret(&exited(7, "points 1\n")).verdict,
+            Verdict::Accepted
+        );
+        assert_eq!(
+            interpret(&exited(7, "points 0 nothing\n")).verdict,
+            Verdict::WrongAnswer
+        );
+    }
+
+    #[test]
+    fn quitp_on_stdout() {
+        let mut outcome = exited(7, "");
+        outcome.stdout = b"0.5\n".to_vec();
+
+        assert_eq!(interpret(&outcome).score, 0.5);
+    }
+
+    #[test]
+    fn quitp_out_of_range() {
+        let outcome = interpret(&exited(7, "points 40 out of 100\n"));
+
+        assert_eq!(outcome.verdict, Verdict::JudgementFailed);
+    }
+
+    #[test]
+    fn pc() {
+        let outcome = interpret(&exited(16 + 40, "partially correct almost there\n"));
+
+        assert_eq!(outcome.verdict, Verdict::PartiallyCorrect);
+        assert_eq!(outcome.score, 0.4);
+        assert_eq!(
+            interpret(&exited(16, "partially correct \n")).verdict,
+            Verdict::WrongAnswer
+        );
+        assert_eq!(
+            interpret(&exited(116, "partially correct \n")).verdict,
+            Verdict::Accepted
+        );
+    }
+
+    #[test]
+    fn pc_over_100() {
+        let outcome = interpret(&exited(16 + 150, "partially correct \n"));
+
+        assert_eq!(outcome.verdict, Verdict::JudgementFailed);
+    }
+
+    #[test]
+    fn crashed() {
+        let mut outcome = exited(0, "");
+        outcome.status = Termination::Signaled(11);
+
+        assert_eq!(interpret(&outcome).verdict, Verdict::JudgementFailed);
+    }
+}
@@ -0,0 +1,37 @@
+use std::{path::PathBuf, time::Duration};
+
+use language_module::CompileCache;
+
+#[derive(Debug, Clone)]
+pub struct JudgeConfig {
+    /// Submissions judged at the same time.
+    pub workers: usize,
+    /// Directory under which every submission gets a scratch directory.
+    pub workdir: PathBuf,
+    /// Directory holding test files referenced by path, one subdirectory per
+    /// problem.
+    pub data_dir: PathBuf,
+    /// How long an idle worker waits before looking for work again.
+    pub poll_interval: Duration,
+    /// How long a submission may go without a heartbeat before another
+    /// worker takes it over.
+    pub stale_after: Duration,
+    /// Artifacts of submissions compiled before.
+    pub compile_cache: CompileCache,
+}
+
+impl Default for JudgeConfig {
+    fn default() -> Self {
+        Self {
+            workers: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            workdir: std::env::temp_dir().join("solve-judge"),
+            data_dir: PathBuf::from("data"),
+            poll_interval: Duration::from_secs(1),
+            stale_after: Duration::from_secs(60),
+            compile_cache: CompileCache {
+                dir: std::env::temp_dir().join("solve-compile-cache"),
+                max_bytes: 1024 * 1024 * 1024,
+            },
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+
+use diesel_async::pooled_connection::deadpool::PoolError;
+
+#[derive(Debug)]
+pub enum JudgeError {
+    Database(db::diesel::result::Error),
+    Pool(PoolError),
+    Io(std::io::Error),
+    /// The submission refers to a problem that no longer exists.
+    MissingProblem(uuid::Uuid),
+    /// The problem's checker or interactor is misconfigured or does not
+    /// compile.
+    ProblemSetup(String),
+    /// The worker lost what it was busy with: another worker claimed the
+    /// submission after the heartbeat stopped, or the run is gone.
+    ClaimLost,
+}
+
+impl fmt::Display for JudgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "Database error: {e}"),
+            Self::Pool(e) => write!(f, "Connection pool error: {e}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::MissingProblem(id) => write!(f, "Problem {id} does not exist"),
+            Self::ProblemSetup(e) => write!(f, "Problem setup error: {e}"),
+            Self::ClaimLost => f.write_str("Claim lost to another worker"),
+        }
+    }
+}
+
+impl std::error::Error for JudgeError {}
+
+impl From<db::diesel::result::Error> for JudgeError {
+    fn from(e: db::diesel::result::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+impl From<PoolError> for JudgeError {
+    fn from(e: PoolError) -> Self {
+        Self::Pool(e)
+    }
+}
+
+impl From<std::io::Error> for JudgeError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
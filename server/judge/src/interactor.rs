@@ -0,0 +1,117 @@
+use std::{io, path::Path, sync::Arc};
+
+use db::orm::{CheckerProtocol, Verdict};
+use language_module::{
+    Artifact, CompileError, Execution, LanguageModule, Limits, RunOutcome, Sandbox, Termination,
+};
+
+use crate::{
+    checker::{kattis, testlib, CheckOutcome},
+    judging::rejection,
+};
+
+/// The program an interactive problem talks to submissions through, written
+/// against testlib or the Kattis protocol.
+///
+/// It is started as `interactor <input> <output> <answer>`, or as
+/// `interactor <input> <answer> <feedback_dir>` for Kattis, with the
+/// submission on its stdin and stdout, and reports the verdict through its
+/// exit code like a checker does.
+pub struct Interactor {
+    module: Arc<dyn LanguageModule>,
+    sandbox: Sandbox,
+    artifact: Artifact,
+    protocol: CheckerProtocol,
+}
+
+impl Interactor {
+    /// Builds the interactor in `workdir`, next to copies of the files in
+    /// `support_dir` (such as `testlib.h`) if it exists.
+    pub fn compile(
+        module: Arc<dyn LanguageModule>,
+        sandbox: Sandbox,
+        source: &str,
+        workdir: &Path,
+        support_dir: &Path,
+    ) -> Result<Self, CompileError> {
+        sandbox.prepare_workdir(workdir)?;
+        testlib::copy_support_files(support_dir, workdir)?;
+
+        let artifact = module.compile(&sandbox, source, workdir)?;
+
+        Ok(Self {
+            module,
+            sandbox,
+            artifact,
+            protocol: CheckerProtocol::Testlib,
+        })
+    }
+
+    pub fn speaking(self, protocol: CheckerProtocol) -> Self {
+        Self { protocol, ..self }
+    }
+
+    /// Runs `program` against the interactor on a test with the given
+    /// `input` and `answer`.
+    pub fn interact(
+        &self,
+        program: &Execution,
+        input: &[u8],
+        answer: &[u8],
+    ) -> io::Result<(RunOutcome, CheckOutcome)> {
+        let input_path = self.artifact.workdir.join("input.txt");
+        let answer_path = self.artifact.workdir.join("answer.txt");
+        std::fs::write(&input_path, input)?;
+        std::fs::write(&answer_path, answer)?;
+
+        let feedback_dir = self.artifact.workdir.join("feedback");
+        let args = match self.protocol {
+            CheckerProtocol::Testlib => [
+                input_path,
+                self.artifact.workdir.join("output.txt"),
+                answer_path,
+            ],
+            CheckerProtocol::Kattis => {
+                kattis::reset_feedback_dir(&feedback_dir)?;
+                [input_path, answer_path, feedback_dir.clone()]
+            }
+        };
+
+        let mut interactor = self.module.execution(&self.artifact, &Limits::CHECKER);
+        interactor.invocation = interactor.invocation.args(args);
+        // Problem authors are trusted, and testlib needs to write its output.
+        interactor.profile = None;
+
+        let interaction = self.sandbox.interact(program, &interactor)?;
+        let check = match self.protocol {
+            CheckerProtocol::Testlib => testlib::interpret(&interaction.interactor),
+            CheckerProtocol::Kattis => kattis::interpret(&interaction.interactor, &feedback_dir),
+        };
+
+        let check = verdict(&interaction.program, check);
+        Ok((interaction.program, check))
+    }
+}
+
+/// A program that breaks a limit is judged by that, whatever the interactor
+/// thinks. Otherwise the interactor rejecting the answer comes first, as the
+/// program may well crash after the interactor has quit on it.
+fn verdict(program: &RunOutcome, check: CheckOutcome) -> CheckOutcome {
+    match program.status {
+        Termination::Exited(0) => check,
+        status
+            if status.is_limit_exceeded()
+                || matches!(status, Termination::SecurityViolation(_)) =>
+        {
+            rejection(status)
+        }
+        _ if matches!(
+            check.verdict,
+            Verdict::WrongAnswer | Verdict::PresentationError
+        ) =>
+        {
+            check
+        }
+        status => rejection(status),
+    }
+}
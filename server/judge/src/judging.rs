@@ -0,0 +1,446 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use db::orm::{
+    Problem, ScoringPolicy, Submission, SubmissionStatus, Subtask, SubtaskResult, TestCase,
+    TestResult, Verdict,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection};
+use language_module::{CompileError, LanguageModule, Limits, RunOutcome, Sandbox, Termination};
+use uuid::Uuid;
+
+use crate::{
+    checker::{CheckOutcome, Checker, CustomChecker},
+    interactor::Interactor,
+    scoring::Scoring,
+    JudgeError, JudgeState,
+};
+
+/// Compiles a submission claimed under `claim_id`, runs it on every test of
+/// its problem and stores the results.
+pub async fn judge(
+    state: &JudgeState,
+    submission: &Submission,
+    claim_id: Uuid,
+) -> Result<(), JudgeError> {
+    let mut conn = state.db.get().await?;
+
+    TestResult::delete_for_submission(&mut conn, submission.id).await?;
+    SubtaskResult::delete_for_submission(&mut conn, submission.id).await?;
+    let problem = Problem::get(&mut conn, submission.problem_id)
+        .await?
+        .ok_or(JudgeError::MissingProblem(submission.problem_id))?;
+    let tests = TestCase::list_for_problem(&mut conn, problem.id).await?;
+    let subtasks = Subtask::list_for_problem(&mut conn, problem.id).await?;
+
+    let Some(language) = state.languages.get(&submission.language) else {
+        let log = format!("Language {} is not available", submission.language);
+        Submission::set_compile_log(&mut conn, submission.id, &log).await?;
+        Submission::finish(
+            &mut conn,
+            submission.id,
+            claim_id,
+            Verdict::CompilationError,
+            None,
+            None,
+            None,
+        )
+        .await?;
+        return Ok(());
+    };
+    let module = language.module.clone();
+    Submission::set_language_version(&mut conn, submission.id, &language.version).await?;
+
+    // Compiling may take a while, the connection is better off in the pool.
+    drop(conn);
+
+    let workdir = Workdir(state.config.workdir.join(submission.id.to_string()));
+    let compiled = {
+        let language = language.clone();
+        let cache = state.config.compile_cache.clone();
+        let sandbox = state.sandbox.clone();
+        let source = submission.source.clone();
+        let workdir = workdir.0.clone();
+        blocking(move || {
+            sandbox.prepare_workdir(&workdir)?;
+            Ok(cache.compile(&language, &sandbox, &source, &workdir))
+        })
+        .await?
+    };
+
+    let mut conn = state.db.get().await?;
+    let artifact = match compiled {
+        Ok(compiled) => {
+            Submission::set_compiled_from_cache(&mut conn, submission.id, compiled.cached).await?;
+            compiled.artifact
+        }
+        Err(CompileError::Io(e)) => return Err(e.into()),
+        Err(e) => {
+            Submission::set_compile_log(&mut conn, submission.id, &e.log()).await?;
+            Submission::finish(
+                &mut conn,
+                submission.id,
+                claim_id,
+                Verdict::CompilationError,
+                None,
+                None,
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    drop(conn);
+
+    let evaluator_workdir = Workdir(
+        state
+            .config
+            .workdir
+            .join(format!("{}-evaluator", submission.id)),
+    );
+    let evaluator = Arc::new(evaluator(state, &problem, &evaluator_workdir.0).await?);
+
+    let mut conn = state.db.get().await?;
+    Submission::set_status(&mut conn, submission.id, SubmissionStatus::Running).await?;
+    drop(conn);
+
+    let limits = limits(&problem, module.as_ref());
+    let artifact = Arc::new(artifact);
+
+    let mut verdict = Verdict::Accepted;
+    let mut max_time_ms = 0;
+    let mut max_memory_bytes = 0;
+    let mut scoring = Scoring::new(&subtasks, &tests);
+
+    for (group, tests) in groups(&tests, !subtasks.is_empty()) {
+        if !scoring.may_score(group) {
+            continue;
+        }
+        // Tests outside of any subtask are worth nothing, like samples.
+        let all_or_nothing = match subtasks.iter().find(|subtask| subtask.test_group == group) {
+            Some(subtask) => subtask.scoring == ScoringPolicy::AllOrNothing,
+            None => true,
+        };
+
+        for test in tests {
+            let input = test_data(
+                &state.config.data_dir,
+                &problem,
+                &test.input,
+                &test.input_path,
+            )?;
+            let answer = test_data(
+                &state.config.data_dir,
+                &problem,
+                &test.answer,
+                &test.answer_path,
+            )?;
+
+            let mut conn = state.db.get().await?;
+            Submission::set_current_test(&mut conn, submission.id, test.ordinal).await?;
+            drop(conn);
+
+            let (outcome, check) = {
+                let module = module.clone();
+                let sandbox = state.sandbox.clone();
+                let artifact = artifact.clone();
+                let evaluator = evaluator.clone();
+                blocking(move || match evaluator.as_ref() {
+                    Evaluator::Checker(checker) => {
+                        let outcome = module.run(&sandbox, &artifact, &input, &limits)?;
+                        let check = match outcome.status {
+                            Termination::Exited(0) => {
+                                checker.check(&input, &outcome.stdout, &answer)?
+                            }
+                            status => rejection(status),
+                        };
+                        Ok((outcome, check))
+                    }
+                    Evaluator::Interactor(interactor) => {
+                        interactor.interact(&module.execution(&artifact, &limits), &input, &answer)
+                    }
+                })
+                .await?
+            };
+
+            let test_verdict = check.verdict;
+            let result = TestResult {
+                submission_id: submission.id,
+                test_case_id: test.id,
+                verdict: test_verdict,
+                time_ms: millis(&outcome),
+                memory_bytes: outcome.peak_memory as i64,
+                score: check.score,
+                checker_message: check.message,
+            };
+
+            max_time_ms = max_time_ms.max(result.time_ms);
+            max_memory_bytes = max_memory_bytes.max(result.memory_bytes);
+
+            let mut conn = state.db.get().await?;
+            let result = &result;
+            conn.transaction(|conn| {
+                async move {
+                    if !Submission::lock_claimed(conn, submission.id, claim_id).await? {
+                        return Err(JudgeError::ClaimLost);
+                    }
+                    TestResult::create(conn, result).await?;
+                    Ok(())
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+            scoring.record(group, check.score);
+            if test_verdict != Verdict::Accepted {
+                if verdict == Verdict::Accepted {
+                    verdict = test_verdict;
+                }
+                if all_or_nothing {
+                    break;
+                }
+            }
+        }
+    }
+
+    let points = scoring.points();
+    let score = (!subtasks.is_empty()).then(|| points.iter().map(|(_, points)| points).sum());
+
+    let mut conn = state.db.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            if !Submission::lock_claimed(conn, submission.id, claim_id).await? {
+                return Err(JudgeError::ClaimLost);
+            }
+            if !subtasks.is_empty() {
+                for &(test_group, points) in &points {
+                    let result = SubtaskResult {
+                        submission_id: submission.id,
+                        test_group,
+                        points,
+                    };
+                    SubtaskResult::create(conn, &result).await?;
+                }
+            }
+            Submission::finish(
+                conn,
+                submission.id,
+                claim_id,
+                verdict,
+                Some(max_time_ms),
+                Some(max_memory_bytes),
+                score,
+            )
+            .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Tests split by group in ascending order, or all in one group if the
+/// problem has no subtasks.
+fn groups(tests: &[TestCase], by_group: bool) -> Vec<(i32, Vec<&TestCase>)> {
+    let mut groups = BTreeMap::<i32, Vec<&TestCase>>::new();
+    for test in tests {
+        let group = if by_group { test.test_group } else { 0 };
+        groups.entry(group).or_default().push(test);
+    }
+    groups.into_iter().collect()
+}
+
+/// Decides the verdict on every test of a problem.
+enum Evaluator {
+    Checker(Checker),
+    Interactor(Interactor),
+}
+
+/// Sets up the evaluator of `problem`, compiling its checker or interactor
+/// in `workdir` if it has one.
+async fn evaluator(
+    state: &JudgeState,
+    problem: &Problem,
+    workdir: &Path,
+) -> Result<Evaluator, JudgeError> {
+    if let Some(source) = &problem.interactor_source {
+        let language = problem.interactor_language.as_deref().unwrap_or_default();
+        let interactor = compile_program(
+            state,
+            problem,
+            "interactor",
+            source,
+            language,
+            workdir,
+            Interactor::compile,
+        )
+        .await?;
+        return Ok(Evaluator::Interactor(
+            interactor.speaking(problem.checker_protocol),
+        ));
+    }
+
+    if let Some(checker) = Checker::builtin(problem) {
+        return Ok(Evaluator::Checker(checker));
+    }
+
+    let (Some(source), Some(language)) = (&problem.checker_source, &problem.checker_language)
+    else {
+        return Err(JudgeError::ProblemSetup(format!(
+            "Problem {} has a custom checker without source or language",
+            problem.id
+        )));
+    };
+    let checker = compile_program(
+        state,
+        problem,
+        "checker",
+        source,
+        language,
+        workdir,
+        CustomChecker::compile,
+    )
+    .await?;
+    Ok(Evaluator::Checker(Checker::Custom(
+        checker.speaking(problem.checker_protocol),
+    )))
+}
+
+/// [`CustomChecker::compile`] or [`Interactor::compile`].
+type CompileProgram<T> =
+    fn(Arc<dyn LanguageModule>, Sandbox, &str, &Path, &Path) -> Result<T, CompileError>;
+
+/// Compiles a program that comes with `problem` in `workdir`, next to the
+/// files in the problem's data directory named after its `role`.
+async fn compile_program<T: Send + 'static>(
+    state: &JudgeState,
+    problem: &Problem,
+    role: &'static str,
+    source: &str,
+    language: &str,
+    workdir: &Path,
+    compile: CompileProgram<T>,
+) -> Result<T, JudgeError> {
+    let Some(language) = state.languages.get(language) else {
+        return Err(JudgeError::ProblemSetup(format!(
+            "Language {language} of the {role} is not available"
+        )));
+    };
+
+    let module = language.module.clone();
+    let sandbox = state.sandbox.clone();
+    let source = source.to_string();
+    let workdir = workdir.to_path_buf();
+    let support_dir = state
+        .config
+        .data_dir
+        .join(problem.id.to_string())
+        .join(role);
+
+    let compiled =
+        blocking(move || Ok(compile(module, sandbox, &source, &workdir, &support_dir))).await?;
+
+    match compiled {
+        Ok(program) => Ok(program),
+        Err(CompileError::Io(e)) => Err(e.into()),
+        Err(e) => Err(JudgeError::ProblemSetup(format!(
+            "The {role} does not compile:\n{}",
+            e.log()
+        ))),
+    }
+}
+
+/// Limits for running a submission: the problem decides time and memory,
+/// scaled for the language, which decides everything else.
+fn limits(problem: &Problem, module: &dyn LanguageModule) -> Limits {
+    module.limits(
+        Duration::from_millis(problem.time_limit_ms as u64),
+        problem.memory_limit_bytes as u64,
+    )
+}
+
+/// Outcome of a test the program did not exit cleanly on, saying how it
+/// ended, such as which system call broke the sandbox.
+pub(crate) fn rejection(status: Termination) -> CheckOutcome {
+    CheckOutcome::rejected(verdict_of(status), status.to_string())
+}
+
+/// Verdict for a run that did not exit cleanly.
+pub(crate) fn verdict_of(status: Termination) -> Verdict {
+    match status {
+        Termination::Exited(_) | Termination::Signaled(_) => Verdict::RuntimeError,
+        Termination::TimeLimitExceeded | Termination::WallTimeLimitExceeded => {
+            Verdict::TimeLimitExceeded
+        }
+        Termination::MemoryLimitExceeded => Verdict::MemoryLimitExceeded,
+        Termination::OutputLimitExceeded => Verdict::OutputLimitExceeded,
+        Termination::IdlenessLimitExceeded => Verdict::IdlenessLimitExceeded,
+        Termination::SecurityViolation(_) => Verdict::SecurityViolation,
+    }
+}
+
+pub(crate) fn millis(outcome: &RunOutcome) -> i32 {
+    outcome.cpu_time.as_millis().try_into().unwrap_or(i32::MAX)
+}
+
+/// Input or answer of a test, stored inline or under the problem's data
+/// directory.
+pub(crate) fn test_data(
+    data_dir: &Path,
+    problem: &Problem,
+    inline: &Option<Vec<u8>>,
+    path: &Option<String>,
+) -> std::io::Result<Vec<u8>> {
+    match (inline, path) {
+        (Some(data), _) => Ok(data.clone()),
+        (None, Some(path)) => std::fs::read(data_dir.join(problem.id.to_string()).join(path)),
+        (None, None) => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "test has neither inline data nor a file",
+        )),
+    }
+}
+
+pub(crate) async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> std::io::Result<T> + Send + 'static,
+) -> std::io::Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .expect("Blocking judge task panicked")
+}
+
+/// Scratch directory of a submission or import, removed once it is done.
+pub(crate) struct Workdir(pub(crate) PathBuf);
+
+impl Drop for Workdir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Failed to remove {}: {e}", self.0.display());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn security_violations_name_the_system_call() {
+        let check = rejection(Termination::SecurityViolation("execve"));
+
+        assert_eq!(check.verdict, Verdict::SecurityViolation);
+        assert_eq!(check.score, 0.0);
+        assert_eq!(
+            check.message.as_deref(),
+            Some("security violation (execve)")
+        );
+    }
+}
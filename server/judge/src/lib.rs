@@ -0,0 +1,52 @@
+pub mod checker;
+pub mod config;
+pub mod error;
+pub mod interactor;
+mod judging;
+pub mod package;
+mod running;
+mod scoring;
+mod worker;
+
+use std::sync::Arc;
+
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use language_module::{LanguageRegistry, Sandbox};
+
+pub use config::JudgeConfig;
+pub use error::JudgeError;
+
+#[derive(Clone)]
+pub struct JudgeState {
+    pub db: Pool<AsyncPgConnection>,
+    pub languages: LanguageRegistry,
+    pub sandbox: Sandbox,
+    pub config: JudgeConfig,
+}
+
+/// Judges queued submissions with `config.workers` concurrent workers.
+///
+/// Never returns: a worker that panics is started again.
+pub async fn run_judge(state: JudgeState) {
+    let state = Arc::new(state);
+
+    println!("Starting {} judge workers", state.config.workers);
+
+    let workers: Vec<_> = (0..state.config.workers)
+        .map(|worker| tokio::spawn(restarting(state.clone(), worker)))
+        .collect();
+
+    for worker in workers {
+        let _ = worker.await;
+    }
+}
+
+/// Runs a worker, starting it over whenever it panics. What it was busy
+/// with goes stale without its heartbeat and is picked up again.
+async fn restarting(state: Arc<JudgeState>, worker: usize) {
+    loop {
+        if let Err(e) = tokio::spawn(worker::run(state.clone(), worker)).await {
+            eprintln!("Judge worker {worker} panicked, restarting it: {e}");
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Exporting problems as Kattis problem packages.
+
+use std::{
+    fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use db::orm::{Problem, Subtask, TestCase};
+use diesel_async::AsyncPgConnection;
+use language_module::LanguageRegistry;
+use uuid::Uuid;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use super::kattis;
+use crate::judging::test_data;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Database(db::diesel::result::Error),
+    Io(std::io::Error),
+    ProblemNotFound,
+    /// The problem uses something Kattis packages cannot express.
+    Unsupported(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "Database error: {e}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::ProblemNotFound => f.write_str("Problem not found"),
+            Self::Unsupported(e) => write!(f, "Cannot export: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<db::diesel::result::Error> for ExportError {
+    fn from(e: db::diesel::result::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<zip::result::ZipError> for ExportError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => Self::Io(e),
+            e => Self::Io(std::io::Error::other(e)),
+        }
+    }
+}
+
+/// Everything about a problem that goes into its package.
+pub(super) struct Exported {
+    pub problem: Problem,
+    pub tests: Vec<ExportedTest>,
+    pub subtasks: Vec<Subtask>,
+    /// Files the checker is built next to, by name.
+    pub checker_files: Vec<(String, Vec<u8>)>,
+    /// Files the interactor is built next to, by name.
+    pub interactor_files: Vec<(String, Vec<u8>)>,
+    /// Files the validator is built next to, by name.
+    pub validator_files: Vec<(String, Vec<u8>)>,
+}
+
+pub(super) struct ExportedTest {
+    pub ordinal: i32,
+    pub test_group: i32,
+    pub is_sample: bool,
+    pub input: Vec<u8>,
+    pub answer: Vec<u8>,
+}
+
+/// Exports the problem with id `problem_id` as a Kattis package at `path`:
+/// a zip archive if it ends with `.zip`, a directory otherwise. Test files
+/// and support files are read from `data_dir`. Programs are written with
+/// the extension of their language in `languages`.
+pub async fn export(
+    conn: &mut AsyncPgConnection,
+    data_dir: &Path,
+    languages: &LanguageRegistry,
+    problem_id: Uuid,
+    path: &Path,
+) -> Result<Problem, ExportError> {
+    let problem = Problem::get(conn, problem_id)
+        .await?
+        .ok_or(ExportError::ProblemNotFound)?;
+    let tests = TestCase::list_for_problem(conn, problem_id).await?;
+    let subtasks = Subtask::list_for_problem(conn, problem_id).await?;
+
+    let data_dir = data_dir.to_path_buf();
+    let languages = languages.clone();
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let exported = load(&data_dir, problem, tests, subtasks)?;
+
+        let mut output = Output::create(&path)?;
+        kattis::write(&exported, &languages, &mut output)?;
+        output.finish()?;
+
+        Ok(exported.problem)
+    })
+    .await
+    .expect("Blocking export task panicked")
+}
+
+fn load(
+    data_dir: &Path,
+    problem: Problem,
+    tests: Vec<TestCase>,
+    subtasks: Vec<Subtask>,
+) -> Result<Exported, ExportError> {
+    let tests = tests
+        .into_iter()
+        .map(|test| {
+            Ok(ExportedTest {
+                ordinal: test.ordinal,
+                test_group: test.test_group,
+                is_sample: test.is_sample,
+                input: test_data(data_dir, &problem, &test.input, &test.input_path)?,
+                answer: test_data(data_dir, &problem, &test.answer, &test.answer_path)?,
+            })
+        })
+        .collect::<std::io::Result<_>>()?;
+
+    let problem_dir = data_dir.join(problem.id.to_string());
+    let checker_files = read_files(&problem_dir.join("checker"))?;
+    let interactor_files = read_files(&problem_dir.join("interactor"))?;
+    let validator_files = read_files(&problem_dir.join("validator"))?;
+
+    Ok(Exported {
+        problem,
+        tests,
+        subtasks,
+        checker_files,
+        interactor_files,
+        validator_files,
+    })
+}
+
+/// Files directly in `dir`, none if it does not exist.
+fn read_files(dir: &Path) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            files.push((name, std::fs::read(entry.path())?));
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Where the files of an exported package go.
+pub(super) enum Output {
+    Directory(PathBuf),
+    Zip(ZipWriter<File>),
+}
+
+impl Output {
+    fn create(path: &Path) -> std::io::Result<Self> {
+        if path.extension().is_some_and(|extension| extension == "zip") {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            Ok(Self::Zip(ZipWriter::new(File::create(path)?)))
+        } else {
+            std::fs::create_dir_all(path)?;
+            Ok(Self::Directory(path.to_path_buf()))
+        }
+    }
+
+    /// Writes the file at `path`, relative to the root of the package.
+    pub(super) fn write(&mut self, path: &str, data: &[u8]) -> Result<(), ExportError> {
+        match self {
+            Self::Directory(root) => {
+                let path = root.join(path);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, data)?;
+            }
+            Self::Zip(zip) => {
+                let options =
+                    FileOptions::default().compression_method(CompressionMethod::Deflated);
+                zip.start_file(path, options)?;
+                zip.write_all(data)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), ExportError> {
+        if let Self::Zip(mut zip) = self {
+            zip.finish()?;
+        }
+        Ok(())
+    }
+}
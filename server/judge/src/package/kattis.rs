@@ -0,0 +1,1059 @@
+//! Kattis problem packages, described by `problem.yaml`.
+//!
+//! See <https://www.kattis.com/problem-package-format/>. Two keys are an
+//! extension of this server, so that exported problems come back the same:
+//! `checker_protocol: testlib` in `problem.yaml` for programs that speak
+//! testlib's protocol instead, and `depends_on` in `testdata.yaml`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use db::orm::{CheckerKind, CheckerProtocol, Problem, ScoringPolicy, Subtask};
+use language_module::LanguageRegistry;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    export::{ExportError, Exported, ExportedTest, Output},
+    extension_of, invalid, language_of, read_program, ImportError, Package, PackageSubtask,
+    PackageTest, Program,
+};
+use crate::checker::DEFAULT_EPSILON;
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// Statement languages, by preference.
+const LANGUAGES: &[&str] = &["ru", "en"];
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct ProblemYaml {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<Name>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validator_flags: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checker_protocol: Option<String>,
+    limits: Limits,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Name {
+    Plain(String),
+    /// Names by language code.
+    Translated(BTreeMap<String, String>),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Limits {
+    /// Seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_limit: Option<f64>,
+    /// MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<f64>,
+}
+
+/// Grading settings of a test data directory, from its `testdata.yaml`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct TestdataYaml {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accept_score: Option<serde_yaml::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grader_flags: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on_reject: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<i32>,
+}
+
+pub(super) fn read(languages: &LanguageRegistry, root: &Path) -> Result<Package, ImportError> {
+    let yaml = std::fs::read_to_string(root.join("problem.yaml"))?;
+    let config: ProblemYaml = serde_yaml::from_str::<Option<ProblemYaml>>(&yaml)
+        .map_err(|e| invalid(format!("problem.yaml: {e}")))?
+        .unwrap_or_default();
+
+    let title = match config.name {
+        Some(Name::Plain(name)) => name,
+        Some(Name::Translated(names)) => LANGUAGES
+            .iter()
+            .find_map(|&language| names.get(language).cloned())
+            .or_else(|| names.into_values().next())
+            .unwrap_or_default(),
+        None => root
+            .canonicalize()?
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    let mut package = Package::new(title);
+    // Output and input validators report valid through exit code 42.
+    package.checker_protocol = match config.checker_protocol.as_deref() {
+        Some(protocol) => protocol.parse().map_err(invalid)?,
+        None => CheckerProtocol::Kattis,
+    };
+    package.statement = read_statement(root)?;
+
+    let time_limit = match config.limits.time_limit {
+        Some(seconds) => Some(seconds),
+        None => match std::fs::read_to_string(root.join(".timelimit")) {
+            Ok(seconds) => Some(
+                seconds
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid(".timelimit is not a number"))?,
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        },
+    };
+    package.time_limit_ms = time_limit.map(|seconds: f64| (seconds * 1000.0).round() as i32);
+    package.memory_limit_bytes = config.limits.memory.map(|mib| (mib * MIB).round() as i64);
+
+    // An interactor leaves the flags to configure the checker it replaces.
+    read_validator_flags(
+        config.validator_flags.as_deref().unwrap_or_default(),
+        &mut package,
+    )?;
+
+    let validation = config.validation.as_deref().unwrap_or("default");
+    let mut validation = validation.split_whitespace();
+    if validation.next() == Some("custom") {
+        let (program, support_files) = read_validator(languages, &root.join("output_validators"))?
+            .ok_or_else(|| invalid("custom validation without output validators"))?;
+        package.support_files = support_files;
+        if validation.any(|word| word == "interactive") {
+            package.interactor = Some(program);
+        } else {
+            package.checker = CheckerKind::Custom;
+            package.checker_program = Some(program);
+        }
+    }
+
+    if let Some((program, support_files)) =
+        read_validator(languages, &root.join("input_validators"))?
+    {
+        package.validator = Some(program);
+        package.support_files.extend(support_files);
+    }
+
+    let scoring = config.kind.as_deref() == Some("scoring");
+    read_tests(&root.join("data"), scoring, &mut package)?;
+
+    Ok(package)
+}
+
+/// `problem_statement/problem.<language>.tex`, or the Markdown statement of
+/// newer packages.
+fn read_statement(root: &Path) -> Result<String, ImportError> {
+    for dir in ["problem_statement", "statement"] {
+        for extension in ["tex", "md"] {
+            let candidates = LANGUAGES
+                .iter()
+                .map(|language| format!("problem.{language}.{extension}"))
+                .chain([format!("problem.{extension}")]);
+
+            for name in candidates {
+                let path = root.join(dir).join(name);
+                if path.is_file() {
+                    return Ok(std::fs::read_to_string(path)?);
+                }
+            }
+        }
+    }
+
+    Ok(String::new())
+}
+
+/// The default validator compares tokens case-insensitively, numbers within
+/// the given tolerances.
+fn read_validator_flags(flags: &str, package: &mut Package) -> Result<(), ImportError> {
+    let mut case_sensitive = false;
+    let mut space_change_sensitive = false;
+
+    let mut flags = flags.split_whitespace();
+    while let Some(flag) = flags.next() {
+        match flag {
+            "case_sensitive" => case_sensitive = true,
+            "space_change_sensitive" => space_change_sensitive = true,
+            "float_tolerance" | "float_absolute_tolerance" | "float_relative_tolerance" => {
+                let tolerance = flags
+                    .next()
+                    .and_then(|tolerance| tolerance.parse().ok())
+                    .ok_or_else(|| invalid(format!("{flag} needs a number")))?;
+                if flag != "float_relative_tolerance" {
+                    package.checker_absolute_epsilon = Some(tolerance);
+                }
+                if flag != "float_absolute_tolerance" {
+                    package.checker_relative_epsilon = Some(tolerance);
+                }
+            }
+            flag => return Err(invalid(format!("unknown validator flag {flag}"))),
+        }
+    }
+
+    package.checker = if package.checker_absolute_epsilon.is_some()
+        || package.checker_relative_epsilon.is_some()
+    {
+        CheckerKind::Float
+    } else if space_change_sensitive {
+        CheckerKind::Exact
+    } else if case_sensitive {
+        CheckerKind::Tokens
+    } else {
+        CheckerKind::CaseInsensitive
+    };
+
+    Ok(())
+}
+
+/// The program in a validators directory, which holds either its sources
+/// or a directory per validator, of which only the first is taken. The
+/// source named after its directory is the program if there is one, files
+/// next to it are returned as its support files.
+fn read_validator(
+    languages: &LanguageRegistry,
+    dir: &Path,
+) -> Result<Option<(Program, Vec<PathBuf>)>, ImportError> {
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+
+    let mut entries = sorted_entries(dir)?;
+    if !entries.iter().any(|path| path.is_file()) {
+        match entries.into_iter().find(|path| path.is_dir()) {
+            Some(validator) => entries = sorted_entries(&validator)?,
+            None => return Ok(None),
+        }
+    }
+
+    let files: Vec<PathBuf> = entries.into_iter().filter(|path| path.is_file()).collect();
+    let sources: Vec<&PathBuf> = files
+        .iter()
+        .filter(|path| language_of(languages, path).is_some())
+        .collect();
+    let named_after_dir = sources
+        .iter()
+        .find(|path| path.file_stem() == path.parent().and_then(Path::file_name));
+    let Some(main) = named_after_dir
+        .or(sources.first())
+        .map(|path| path.to_path_buf())
+    else {
+        return Err(invalid(format!(
+            "no source in a known language in {}",
+            dir.display()
+        )));
+    };
+
+    let program = read_program(languages, &main, None)?;
+    let support_files = files.into_iter().filter(|path| *path != main).collect();
+    Ok(Some((program, support_files)))
+}
+
+/// Samples from `data/sample` go into group 0, secret tests after them.
+///
+/// Each subdirectory of `data/secret` is a group of its own. Directories
+/// named `group<N>` or `<N>` are group N, the rest are numbered after them
+/// in name order; tests right in `data/secret` come first. Samples in such a
+/// subdirectory of `data/sample` join that group.
+///
+/// For scoring problems every group gets a subtask: a test earns its
+/// `accept_score` and the group adds them up, or with the `min` grader flag
+/// takes the lowest. Samples are worth nothing unless a `testdata.yaml` says
+/// otherwise.
+fn read_tests(data: &Path, scoring: bool, package: &mut Package) -> Result<(), ImportError> {
+    let sample = data.join("sample");
+    let secret = data.join("secret");
+    if !secret.is_dir() {
+        return Err(invalid("the package has no data/secret directory"));
+    }
+
+    // Directories of tests, with their group if their name gives it.
+    let mut dirs = Vec::new();
+    if sample.is_dir() {
+        dirs.push((sample.clone(), Some(0), true, false));
+        for dir in subdirectories(&sample)? {
+            let test_group = group_number(&dir).unwrap_or(0);
+            dirs.push((dir, Some(test_group), true, true));
+        }
+    }
+    dirs.push((secret.clone(), None, false, false));
+    for dir in subdirectories(&secret)? {
+        let test_group = group_number(&dir);
+        dirs.push((dir, test_group, false, true));
+    }
+
+    let mut next_group = dirs
+        .iter()
+        .filter_map(|(_, test_group, _, _)| *test_group)
+        .max()
+        .map_or(1, |max| max.max(0) + 1);
+    let mut samples = Vec::new();
+    let mut groups: BTreeMap<i32, Group> = BTreeMap::new();
+
+    for (dir, test_group, is_sample, nested) in dirs {
+        let mut tests = read_test_dir(&dir, 0, is_sample, nested)?;
+        if tests.is_empty() {
+            continue;
+        }
+        let test_group = test_group.unwrap_or_else(|| {
+            next_group += 1;
+            next_group - 1
+        });
+        for test in &mut tests {
+            test.test_group = test_group;
+        }
+
+        let group = groups.entry(test_group).or_default();
+        group.tests += tests.len();
+        if group.testdata.is_none() {
+            group.testdata = read_testdata(&dir)?;
+        }
+        group.secret |= !is_sample;
+
+        if is_sample {
+            samples.extend(tests);
+        } else {
+            group.secret_tests.extend(tests);
+        }
+    }
+
+    package.tests = samples;
+    if scoring {
+        let secret_testdata = read_testdata(&secret)?;
+        for (&test_group, group) in &groups {
+            let testdata = group
+                .testdata
+                .as_ref()
+                .or(secret_testdata.as_ref().filter(|_| group.secret));
+            let subtask = match testdata {
+                Some(testdata) => subtask(test_group, group.tests, testdata)?,
+                None if group.secret => subtask(test_group, group.tests, &TestdataYaml::default())?,
+                None => continue,
+            };
+            package.subtasks.push(subtask);
+        }
+    }
+    for group in groups.into_values() {
+        package.tests.extend(group.secret_tests);
+    }
+
+    Ok(())
+}
+
+/// Tests of one group, gathered from all of its directories.
+#[derive(Default)]
+struct Group {
+    tests: usize,
+    /// From the first of its directories that has a `testdata.yaml`.
+    testdata: Option<TestdataYaml>,
+    /// Whether any of its tests are secret.
+    secret: bool,
+    secret_tests: Vec<PackageTest>,
+}
+
+fn group_number(dir: &Path) -> Option<i32> {
+    let name = dir.file_name()?.to_str()?;
+    name.strip_prefix("group").unwrap_or(name).parse().ok()
+}
+
+/// Tests of `dir` and, if `nested`, of its subdirectories.
+fn read_test_dir(
+    dir: &Path,
+    test_group: i32,
+    is_sample: bool,
+    nested: bool,
+) -> Result<Vec<PackageTest>, ImportError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut tests = Vec::new();
+    for path in sorted_entries(dir)? {
+        if nested && path.is_dir() {
+            tests.extend(read_test_dir(&path, test_group, is_sample, nested)?);
+        } else if path.extension().is_some_and(|extension| extension == "in") {
+            let answer = path.with_extension("ans");
+            if !answer.is_file() {
+                return Err(invalid(format!("{} has no answer file", path.display())));
+            }
+            tests.push(PackageTest {
+                test_group,
+                is_sample,
+                input: path,
+                answer,
+            });
+        }
+    }
+
+    Ok(tests)
+}
+
+fn read_testdata(dir: &Path) -> Result<Option<TestdataYaml>, ImportError> {
+    let path = dir.join("testdata.yaml");
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let yaml = std::fs::read_to_string(&path)?;
+    serde_yaml::from_str::<Option<TestdataYaml>>(&yaml)
+        .map_err(|e| invalid(format!("{}: {e}", path.display())))
+}
+
+fn subtask(
+    test_group: i32,
+    tests: usize,
+    testdata: &TestdataYaml,
+) -> Result<PackageSubtask, ImportError> {
+    let number = |value: &str| {
+        value
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| invalid(format!("{value} in testdata.yaml is not a number")))
+    };
+
+    let accept_score = match &testdata.accept_score {
+        None => 1.0,
+        Some(serde_yaml::Value::Number(score)) => score.as_f64().unwrap_or(1.0),
+        Some(serde_yaml::Value::String(score)) => number(score)?,
+        Some(_) => return Err(invalid("accept_score in testdata.yaml is not a number")),
+    };
+    let min = testdata
+        .grader_flags
+        .as_deref()
+        .is_some_and(|flags| flags.split_whitespace().any(|flag| flag == "min"));
+
+    // Rejecting a test breaks off its group unless told to continue, so a
+    // group graded by its lowest score is all or nothing.
+    let (scoring, total) = if min && testdata.on_reject.as_deref() == Some("continue") {
+        (ScoringPolicy::Min, accept_score)
+    } else if min {
+        (ScoringPolicy::AllOrNothing, accept_score)
+    } else {
+        (ScoringPolicy::Sum, accept_score * tests as f64)
+    };
+    // The upper end of the range caps what the group can earn.
+    let total = match testdata
+        .range
+        .as_deref()
+        .and_then(|range| range.split_whitespace().nth(1))
+    {
+        Some(max) => number(max)?.min(total),
+        None => total,
+    };
+
+    Ok(PackageSubtask {
+        test_group,
+        points: total.round() as i32,
+        scoring,
+        depends_on: testdata.depends_on.clone(),
+    })
+}
+
+fn subdirectories(dir: &Path) -> Result<Vec<PathBuf>, ImportError> {
+    Ok(sorted_entries(dir)?
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .collect())
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, ImportError> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        entries.push(entry?.path());
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Writes `exported` as a Kattis package, see [`super::export`].
+pub(super) fn write(
+    exported: &Exported,
+    languages: &LanguageRegistry,
+    output: &mut Output,
+) -> Result<(), ExportError> {
+    let problem = &exported.problem;
+
+    let mut config = ProblemYaml {
+        name: Some(Name::Plain(problem.title.clone())),
+        kind: Some(
+            if exported.subtasks.is_empty() {
+                "pass-fail"
+            } else {
+                "scoring"
+            }
+            .to_owned(),
+        ),
+        validation: None,
+        validator_flags: validator_flags(problem),
+        checker_protocol: (problem.checker_protocol == CheckerProtocol::Testlib)
+            .then(|| CheckerProtocol::Testlib.to_string()),
+        limits: Limits {
+            time_limit: Some(problem.time_limit_ms as f64 / 1000.0),
+            memory: Some(problem.memory_limit_bytes as f64 / MIB),
+        },
+    };
+
+    let custom_checker = problem.checker == CheckerKind::Custom;
+    match (&problem.interactor_source, &problem.interactor_language) {
+        (Some(_), _) if custom_checker => {
+            return Err(ExportError::Unsupported(
+                "a problem with both an interactor and a checker program".to_owned(),
+            ))
+        }
+        (Some(source), Some(language)) => {
+            config.validation = Some("custom interactive".to_owned());
+            write_program(
+                output,
+                languages,
+                "output_validators/interactor",
+                source,
+                language,
+                &exported.interactor_files,
+            )?;
+        }
+        _ if custom_checker => {
+            let (Some(source), Some(language)) =
+                (&problem.checker_source, &problem.checker_language)
+            else {
+                return Err(ExportError::Unsupported(
+                    "a custom checker without source or language".to_owned(),
+                ));
+            };
+            config.validation = Some("custom".to_owned());
+            write_program(
+                output,
+                languages,
+                "output_validators/checker",
+                source,
+                language,
+                &exported.checker_files,
+            )?;
+        }
+        _ => {}
+    }
+
+    if let (Some(source), Some(language)) = (&problem.validator_source, &problem.validator_language)
+    {
+        write_program(
+            output,
+            languages,
+            "input_validators/validator",
+            source,
+            language,
+            &exported.validator_files,
+        )?;
+    }
+
+    let yaml = serde_yaml::to_string(&config).expect("problem.yaml is serializable");
+    output.write("problem.yaml", yaml.as_bytes())?;
+    if !problem.statement.is_empty() {
+        output.write(
+            "problem_statement/problem.tex",
+            problem.statement.as_bytes(),
+        )?;
+    }
+
+    write_tests(exported, output)
+}
+
+/// Flags of the default validator that make it compare like the problem's
+/// checker.
+fn validator_flags(problem: &Problem) -> Option<String> {
+    match problem.checker {
+        CheckerKind::Exact => Some("case_sensitive space_change_sensitive".to_owned()),
+        CheckerKind::Tokens => Some("case_sensitive".to_owned()),
+        CheckerKind::CaseInsensitive | CheckerKind::Custom => None,
+        CheckerKind::Float => Some(
+            match (
+                problem.checker_absolute_epsilon,
+                problem.checker_relative_epsilon,
+            ) {
+                (None, None) => format!("float_tolerance {DEFAULT_EPSILON}"),
+                (Some(absolute), None) => format!("float_absolute_tolerance {absolute}"),
+                (None, Some(relative)) => format!("float_relative_tolerance {relative}"),
+                (Some(absolute), Some(relative)) => format!(
+                    "float_absolute_tolerance {absolute} float_relative_tolerance {relative}"
+                ),
+            },
+        ),
+    }
+}
+
+/// Writes a program into `dir`, named after it, next to its support files.
+fn write_program(
+    output: &mut Output,
+    languages: &LanguageRegistry,
+    dir: &str,
+    source: &str,
+    language: &str,
+    support_files: &[(String, Vec<u8>)],
+) -> Result<(), ExportError> {
+    let extension = extension_of(languages, language).ok_or_else(|| {
+        ExportError::Unsupported(format!(
+            "programs in language {language}, which is not available"
+        ))
+    })?;
+    let name = dir.rsplit('/').next().unwrap_or(dir);
+    let file_name = format!("{name}.{extension}");
+
+    output.write(&format!("{dir}/{file_name}"), source.as_bytes())?;
+    for (support_name, data) in support_files {
+        if *support_name != file_name {
+            output.write(&format!("{dir}/{support_name}"), data)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Samples go to `data/sample`, the rest to `data/secret/group<N>`. Groups
+/// of a subtask get a `testdata.yaml` that gives them its points.
+fn write_tests(exported: &Exported, output: &mut Output) -> Result<(), ExportError> {
+    let width = exported
+        .tests
+        .iter()
+        .map(|test| test.ordinal.to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+
+    let mut groups: BTreeMap<i32, Vec<&ExportedTest>> = BTreeMap::new();
+    for test in &exported.tests {
+        let dir = test_dir(test.test_group, test.is_sample);
+        let name = format!("{dir}/{:0width$}", test.ordinal);
+        output.write(&format!("{name}.in"), &test.input)?;
+        output.write(&format!("{name}.ans"), &test.answer)?;
+        groups.entry(test.test_group).or_default().push(test);
+    }
+
+    if exported.subtasks.is_empty() {
+        return Ok(());
+    }
+
+    for (test_group, tests) in groups {
+        let subtask = exported
+            .subtasks
+            .iter()
+            .find(|subtask| subtask.test_group == test_group);
+        let is_sample = tests.iter().all(|test| test.is_sample);
+        let testdata = match subtask {
+            Some(subtask) => testdata(subtask, tests.len()),
+            // Samples are worth nothing without a testdata.yaml already.
+            None if is_sample => continue,
+            // Secret tests outside of any subtask are worth nothing.
+            None => TestdataYaml {
+                accept_score: Some(0.into()),
+                ..TestdataYaml::default()
+            },
+        };
+
+        let yaml = serde_yaml::to_string(&testdata).expect("testdata.yaml is serializable");
+        output.write(
+            &format!("{}/testdata.yaml", test_dir(test_group, is_sample)),
+            yaml.as_bytes(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn test_dir(test_group: i32, is_sample: bool) -> String {
+    match (is_sample, test_group) {
+        (true, 0) => "data/sample".to_owned(),
+        (true, test_group) => format!("data/sample/group{test_group}"),
+        (false, test_group) => format!("data/secret/group{test_group}"),
+    }
+}
+
+fn testdata(subtask: &Subtask, tests: usize) -> TestdataYaml {
+    let points = subtask.points as f64;
+    let (accept_score, grader_flags, on_reject) = match subtask.scoring {
+        // Split evenly, with the range making up for rounding.
+        ScoringPolicy::Sum => (points / tests as f64, None, None),
+        ScoringPolicy::Min => (points, Some("min"), Some("continue")),
+        ScoringPolicy::AllOrNothing => (points, Some("min"), Some("break")),
+    };
+
+    TestdataYaml {
+        accept_score: Some(accept_score.into()),
+        range: Some(format!("0 {}", subtask.points)),
+        grader_flags: grader_flags.map(str::to_owned),
+        on_reject: on_reject.map(str::to_owned),
+        depends_on: subtask.depends_on.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{
+        export::Output,
+        testing::{languages, Fixture},
+    };
+
+    const TESTLIB: &str = "// testlib.h\n";
+
+    /// A scoring problem with a testlib checker and validator sharing
+    /// `testlib.h`, samples and a group for every scoring policy.
+    fn scoring_package() -> Fixture {
+        let fixture = Fixture::new();
+        fixture
+            .write(
+                "problem.yaml",
+                "name: Сумма\n\
+                 type: scoring\n\
+                 validation: custom\n\
+                 checker_protocol: testlib\n\
+                 limits:\n  time_limit: 2\n  memory: 256\n",
+            )
+            .write("problem_statement/problem.ru.tex", "Сложите числа.\n")
+            .write("output_validators/checker/checker.cpp", "// checker\n")
+            .write("output_validators/checker/testlib.h", TESTLIB)
+            .write("input_validators/validator/validator.cpp", "// validator\n")
+            .write("input_validators/validator/testlib.h", TESTLIB)
+            .write("data/sample/1.in", "1 2\n")
+            .write("data/sample/1.ans", "3\n")
+            .write("data/secret/group1/1.in", "2 2\n")
+            .write("data/secret/group1/1.ans", "4\n")
+            .write("data/secret/group1/2.in", "3 2\n")
+            .write("data/secret/group1/2.ans", "5\n")
+            .write(
+                "data/secret/group1/testdata.yaml",
+                "accept_score: 30\nrange: 0 30\ngrader_flags: min\n",
+            )
+            .write("data/secret/group2/1.in", "4 2\n")
+            .write("data/secret/group2/1.ans", "6\n")
+            .write("data/secret/group2/2.in", "5 2\n")
+            .write("data/secret/group2/2.ans", "7\n")
+            .write(
+                "data/secret/group2/testdata.yaml",
+                "accept_score: 25\ndepends_on: [1]\n",
+            )
+            .write("data/secret/group3/1.in", "6 2\n")
+            .write("data/secret/group3/1.ans", "8\n")
+            .write(
+                "data/secret/group3/testdata.yaml",
+                "accept_score: 20\ngrader_flags: min\non_reject: continue\n",
+            );
+        fixture
+    }
+
+    /// What storing `package` and loading it back for export gives.
+    fn exported(package: &Package) -> Exported {
+        let files = |present: bool| -> Vec<(String, Vec<u8>)> {
+            let mut files: Vec<_> = package
+                .support_files
+                .iter()
+                .filter(|_| present)
+                .map(|path| {
+                    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                    (name, std::fs::read(path).unwrap())
+                })
+                .collect();
+            files.sort();
+            files.dedup();
+            files
+        };
+
+        Exported {
+            problem: Problem {
+                id: uuid::Uuid::nil(),
+                title: package.title.clone(),
+                statement: package.statement.clone(),
+                time_limit_ms: package.time_limit_ms.unwrap(),
+                memory_limit_bytes: package.memory_limit_bytes.unwrap(),
+                checker: package.checker,
+                created_at: chrono::Utc::now(),
+                checker_absolute_epsilon: package.checker_absolute_epsilon,
+                checker_relative_epsilon: package.checker_relative_epsilon,
+                checker_source: package.checker_program.as_ref().map(|p| p.source.clone()),
+                checker_language: package.checker_program.as_ref().map(|p| p.language.clone()),
+                interactor_source: package.interactor.as_ref().map(|p| p.source.clone()),
+                interactor_language: package.interactor.as_ref().map(|p| p.language.clone()),
+                checker_protocol: package.checker_protocol,
+                validator_source: package.validator.as_ref().map(|p| p.source.clone()),
+                validator_language: package.validator.as_ref().map(|p| p.language.clone()),
+            },
+            tests: (1..)
+                .zip(&package.tests)
+                .map(|(ordinal, test)| ExportedTest {
+                    ordinal,
+                    test_group: test.test_group,
+                    is_sample: test.is_sample,
+                    input: std::fs::read(&test.input).unwrap(),
+                    answer: std::fs::read(&test.answer).unwrap(),
+                })
+                .collect(),
+            subtasks: package
+                .subtasks
+                .iter()
+                .map(|subtask| Subtask {
+                    problem_id: uuid::Uuid::nil(),
+                    test_group: subtask.test_group,
+                    points: subtask.points,
+                    scoring: subtask.scoring,
+                    depends_on: subtask.depends_on.clone(),
+                })
+                .collect(),
+            checker_files: files(package.checker_program.is_some()),
+            interactor_files: files(package.interactor.is_some()),
+            validator_files: files(package.validator.is_some()),
+        }
+    }
+
+    /// Tests as group, whether a sample, input and answer.
+    fn tests(package: &Package) -> Vec<(i32, bool, String, String)> {
+        package
+            .tests
+            .iter()
+            .map(|test| {
+                (
+                    test.test_group,
+                    test.is_sample,
+                    std::fs::read_to_string(&test.input).unwrap(),
+                    std::fs::read_to_string(&test.answer).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn subtasks(package: &Package) -> Vec<(i32, i32, ScoringPolicy, Vec<i32>)> {
+        package
+            .subtasks
+            .iter()
+            .map(|subtask| {
+                (
+                    subtask.test_group,
+                    subtask.points,
+                    subtask.scoring,
+                    subtask.depends_on.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn program(program: &Option<Program>) -> Option<(&str, &str)> {
+        program
+            .as_ref()
+            .map(|program| (program.source.as_str(), program.language.as_str()))
+    }
+
+    /// Support files by name, once for every program built next to them.
+    fn support_files(package: &Package) -> Vec<(String, String)> {
+        let mut files: Vec<_> = package
+            .support_files
+            .iter()
+            .map(|path| {
+                (
+                    path.file_name().unwrap().to_string_lossy().into_owned(),
+                    std::fs::read_to_string(path).unwrap(),
+                )
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn reads_scoring_package() {
+        let fixture = scoring_package();
+        let package = read(&languages(), fixture.root()).unwrap();
+
+        assert_eq!(package.title, "Сумма");
+        assert_eq!(package.statement, "Сложите числа.\n");
+        assert_eq!(package.time_limit_ms, Some(2000));
+        assert_eq!(package.memory_limit_bytes, Some(256 * 1024 * 1024));
+        assert_eq!(package.checker, CheckerKind::Custom);
+        assert_eq!(package.checker_protocol, CheckerProtocol::Testlib);
+        assert_eq!(
+            program(&package.checker_program),
+            Some(("// checker\n", "cpp"))
+        );
+        assert_eq!(program(&package.validator), Some(("// validator\n", "cpp")));
+        assert_eq!(
+            support_files(&package),
+            vec![
+                ("testlib.h".to_owned(), TESTLIB.to_owned()),
+                ("testlib.h".to_owned(), TESTLIB.to_owned()),
+            ]
+        );
+        assert_eq!(
+            tests(&package)
+                .iter()
+                .map(|(group, is_sample, input, _)| (*group, *is_sample, input.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, true, "1 2\n"),
+                (1, false, "2 2\n"),
+                (1, false, "3 2\n"),
+                (2, false, "4 2\n"),
+                (2, false, "5 2\n"),
+                (3, false, "6 2\n"),
+            ]
+        );
+        // Samples without a testdata.yaml get no subtask.
+        assert_eq!(
+            subtasks(&package),
+            vec![
+                (1, 30, ScoringPolicy::AllOrNothing, vec![]),
+                (2, 50, ScoringPolicy::Sum, vec![1]),
+                (3, 20, ScoringPolicy::Min, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_secret_groups() {
+        let fixture = Fixture::new();
+        fixture
+            .write("problem.yaml", "type: scoring\n")
+            .write("data/sample/1.in", "s\n")
+            .write("data/sample/1.ans", "s\n")
+            .write("data/secret/1.in", "root\n")
+            .write("data/secret/1.ans", "root\n")
+            .write("data/secret/easy/1.in", "easy\n")
+            .write("data/secret/easy/1.ans", "easy\n")
+            .write("data/secret/group2/1.in", "two\n")
+            .write("data/secret/group2/1.ans", "two\n")
+            .write("data/secret/group2/nested/2.in", "nested\n")
+            .write("data/secret/group2/nested/2.ans", "nested\n");
+        let package = read(&languages(), fixture.root()).unwrap();
+
+        assert_eq!(
+            tests(&package)
+                .iter()
+                .map(|(group, _, input, _)| (*group, input.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "s\n"),
+                (2, "two\n"),
+                (2, "nested\n"),
+                (3, "root\n"),
+                (4, "easy\n"),
+            ]
+        );
+        // Every test earns a point without a testdata.yaml.
+        assert_eq!(
+            subtasks(&package),
+            vec![
+                (2, 2, ScoringPolicy::Sum, vec![]),
+                (3, 1, ScoringPolicy::Sum, vec![]),
+                (4, 1, ScoringPolicy::Sum, vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_pass_fail_package() {
+        let fixture = Fixture::new();
+        fixture
+            .write(
+                "problem.yaml",
+                "name:\n  en: Sum\n  ru: Сумма\n\
+                 validator_flags: float_tolerance 1e-6\n",
+            )
+            .write(".timelimit", "3\n")
+            .write("data/secret/1.in", "1\n")
+            .write("data/secret/1.ans", "1\n");
+        let package = read(&languages(), fixture.root()).unwrap();
+
+        assert_eq!(package.title, "Сумма");
+        assert_eq!(package.time_limit_ms, Some(3000));
+        assert_eq!(package.memory_limit_bytes, None);
+        assert_eq!(package.checker, CheckerKind::Float);
+        assert_eq!(package.checker_absolute_epsilon, Some(1e-6));
+        assert_eq!(package.checker_relative_epsilon, Some(1e-6));
+        assert_eq!(package.checker_protocol, CheckerProtocol::Kattis);
+        assert!(package.subtasks.is_empty());
+    }
+
+    #[test]
+    fn reads_validator_flags() {
+        for (flags, checker) in [
+            ("", CheckerKind::CaseInsensitive),
+            ("case_sensitive", CheckerKind::Tokens),
+            ("case_sensitive space_change_sensitive", CheckerKind::Exact),
+        ] {
+            let mut package = Package::new(String::new());
+            read_validator_flags(flags, &mut package).unwrap();
+            assert_eq!(package.checker, checker, "{flags}");
+        }
+
+        let mut package = Package::new(String::new());
+        read_validator_flags("float_absolute_tolerance 0.5", &mut package).unwrap();
+        assert_eq!(package.checker, CheckerKind::Float);
+        assert_eq!(package.checker_absolute_epsilon, Some(0.5));
+        assert_eq!(package.checker_relative_epsilon, None);
+
+        assert!(read_validator_flags("float_tolerance", &mut package).is_err());
+        assert!(read_validator_flags("ignore_case", &mut package).is_err());
+    }
+
+    #[test]
+    fn reads_interactor() {
+        let fixture = Fixture::new();
+        fixture
+            .write("problem.yaml", "validation: custom interactive\n")
+            .write("output_validators/interactor.py", "# interactor\n")
+            .write("data/secret/1.in", "1\n")
+            .write("data/secret/1.ans", "1\n");
+        let package = read(&languages(), fixture.root()).unwrap();
+
+        assert_eq!(
+            program(&package.interactor),
+            Some(("# interactor\n", "python"))
+        );
+        assert!(package.checker_program.is_none());
+        assert!(package.validator.is_none());
+    }
+
+    #[test]
+    fn rejects_package_without_secret_tests() {
+        let fixture = Fixture::new();
+        fixture
+            .write("problem.yaml", "name: Sum\n")
+            .write("data/sample/1.in", "1\n")
+            .write("data/sample/1.ans", "1\n");
+
+        assert!(read(&languages(), fixture.root()).is_err());
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let languages = languages();
+        let fixture = scoring_package();
+        let imported = read(&languages, fixture.root()).unwrap();
+
+        let package = Fixture::new();
+        let mut output = Output::Directory(package.root().to_path_buf());
+        write(&exported(&imported), &languages, &mut output).unwrap();
+        assert!(!package.root().join("data/sample/testdata.yaml").exists());
+
+        let reimported = read(&languages, package.root()).unwrap();
+        assert_eq!(reimported.title, imported.title);
+        assert_eq!(reimported.statement, imported.statement);
+        assert_eq!(reimported.time_limit_ms, imported.time_limit_ms);
+        assert_eq!(reimported.memory_limit_bytes, imported.memory_limit_bytes);
+        assert_eq!(reimported.checker, imported.checker);
+        assert_eq!(reimported.checker_protocol, imported.checker_protocol);
+        assert_eq!(
+            program(&reimported.checker_program),
+            program(&imported.checker_program)
+        );
+        assert_eq!(program(&reimported.validator), program(&imported.validator));
+        assert_eq!(support_files(&reimported), support_files(&imported));
+        assert_eq!(tests(&reimported), tests(&imported));
+        assert_eq!(subtasks(&reimported), subtasks(&imported));
+    }
+}
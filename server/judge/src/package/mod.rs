@@ -0,0 +1,668 @@
+//! Importing problems from packages: Polygon and Kattis problem packages,
+//! or a plain directory of tests, as a directory or a zip archive. Problems
+//! are exported as Kattis packages.
+//!
+//! A package is read into a [`Package`] first and only then stored, so a
+//! malformed package leaves nothing behind in the database.
+
+mod export;
+mod kattis;
+mod plain;
+mod polygon;
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use db::orm::{
+    CheckerKind, CheckerProtocol, NewProblem, NewTestCase, Problem, ScoringPolicy, Subtask,
+    TestCase,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, AsyncPgConnection};
+use language_module::{
+    Artifact, CompileError, LanguageModule, LanguageRegistry, Limits, RegisteredLanguage,
+    RunOutcome, Sandbox, Termination,
+};
+
+use crate::judging::Workdir;
+
+pub use export::{export, ExportError};
+
+/// Limits of a problem whose package does not set them.
+const DEFAULT_TIME_LIMIT_MS: i32 = 1000;
+const DEFAULT_MEMORY_LIMIT_BYTES: i64 = 256 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum ImportError {
+    Database(db::diesel::result::Error),
+    Io(std::io::Error),
+    /// The package is not laid out as expected.
+    Invalid(String),
+    /// A generator or solution needed to produce tests failed.
+    Generation(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "Database error: {e}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Invalid(e) => write!(f, "Invalid package: {e}"),
+            Self::Generation(e) => write!(f, "Could not generate tests: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<db::diesel::result::Error> for ImportError {
+    fn from(e: db::diesel::result::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<zip::result::ZipError> for ImportError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => Self::Io(e),
+            e => Self::Invalid(e.to_string()),
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> ImportError {
+    ImportError::Invalid(message.into())
+}
+
+/// Settings that take precedence over the package's own.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    pub title: Option<String>,
+    pub time_limit_ms: Option<i32>,
+    pub memory_limit_bytes: Option<i64>,
+    /// Replaces the package's checker with a built-in one.
+    pub checker: Option<CheckerKind>,
+}
+
+/// A problem as read from a package, before it is stored.
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub title: String,
+    pub statement: String,
+    pub time_limit_ms: Option<i32>,
+    pub memory_limit_bytes: Option<i64>,
+    pub checker: CheckerKind,
+    pub checker_absolute_epsilon: Option<f64>,
+    pub checker_relative_epsilon: Option<f64>,
+    /// Source of a custom checker.
+    pub checker_program: Option<Program>,
+    pub interactor: Option<Program>,
+    pub checker_protocol: CheckerProtocol,
+    pub validator: Option<Program>,
+    /// Files the checker, interactor and validator are built next to, such
+    /// as `testlib.h`.
+    pub support_files: Vec<PathBuf>,
+    /// Tests in the order they are judged.
+    pub tests: Vec<PackageTest>,
+    pub subtasks: Vec<PackageSubtask>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub source: String,
+    pub language: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageTest {
+    pub test_group: i32,
+    pub is_sample: bool,
+    pub input: PathBuf,
+    pub answer: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageSubtask {
+    pub test_group: i32,
+    pub points: i32,
+    pub scoring: ScoringPolicy,
+    pub depends_on: Vec<i32>,
+}
+
+impl Package {
+    /// A package with a built-in token checker and nothing else set.
+    fn new(title: String) -> Self {
+        Self {
+            title,
+            statement: String::new(),
+            time_limit_ms: None,
+            memory_limit_bytes: None,
+            checker: CheckerKind::Tokens,
+            checker_absolute_epsilon: None,
+            checker_relative_epsilon: None,
+            checker_program: None,
+            interactor: None,
+            checker_protocol: CheckerProtocol::Testlib,
+            validator: None,
+            support_files: Vec::new(),
+            tests: Vec::new(),
+            subtasks: Vec::new(),
+        }
+    }
+
+    fn apply(&mut self, options: &ImportOptions) {
+        if let Some(title) = &options.title {
+            self.title = title.clone();
+        }
+        if let Some(time_limit_ms) = options.time_limit_ms {
+            self.time_limit_ms = Some(time_limit_ms);
+        }
+        if let Some(memory_limit_bytes) = options.memory_limit_bytes {
+            self.memory_limit_bytes = Some(memory_limit_bytes);
+        }
+        if let Some(checker) = options.checker {
+            self.checker = checker;
+            if checker != CheckerKind::Custom {
+                self.checker_program = None;
+            }
+        }
+    }
+}
+
+/// Everything needed to read packages whose tests must be generated, and to
+/// store the problems they describe.
+#[derive(Clone)]
+pub struct Importer {
+    pub languages: LanguageRegistry,
+    pub sandbox: Sandbox,
+    /// Directory under which every import gets a scratch directory.
+    pub workdir: PathBuf,
+    /// Directory test files are copied to, one subdirectory per problem.
+    pub data_dir: PathBuf,
+}
+
+impl Importer {
+    /// Imports the package at `path`, a directory or a zip archive, and
+    /// returns the problem created for it.
+    ///
+    /// The format is told by the files at the root of the package, or in its
+    /// only directory: `problem.xml` for Polygon, `problem.yaml` for Kattis.
+    /// Anything else is read as a plain directory of tests, see
+    /// [`plain::read`].
+    pub async fn import(
+        &self,
+        conn: &mut AsyncPgConnection,
+        path: &Path,
+        options: &ImportOptions,
+    ) -> Result<Problem, ImportError> {
+        let scratch = Workdir(
+            self.workdir
+                .join(format!("import-{}", uuid::Uuid::new_v4())),
+        );
+
+        let package = {
+            let importer = self.clone();
+            let path = path.to_path_buf();
+            let scratch = scratch.0.clone();
+            let options = options.clone();
+            tokio::task::spawn_blocking(move || -> Result<Package, ImportError> {
+                let mut package = importer.read(&path, &scratch)?;
+                package.apply(&options);
+                Ok(package)
+            })
+            .await
+            .expect("Blocking import task panicked")?
+        };
+
+        self.store(conn, &package).await
+    }
+
+    fn read(&self, path: &Path, scratch: &Path) -> Result<Package, ImportError> {
+        std::fs::create_dir_all(scratch)?;
+
+        let root = if path.is_file() {
+            let root = scratch.join("package");
+            extract_zip(path, &root)?;
+            root
+        } else if path.is_dir() {
+            path.to_path_buf()
+        } else {
+            return Err(invalid(format!("{} does not exist", path.display())));
+        };
+        let root = package_root(&root)?;
+
+        let package = if root.join("problem.xml").is_file() {
+            polygon::read(self, &root, scratch)?
+        } else if root.join("problem.yaml").is_file() {
+            kattis::read(&self.languages, &root)?
+        } else {
+            plain::read(&root)?
+        };
+
+        self.validate(&package, scratch)?;
+        Ok(package)
+    }
+
+    /// Runs the package's validator on every test input. Testlib validators
+    /// accept with exit code 0, Kattis ones with 42.
+    fn validate(&self, package: &Package, scratch: &Path) -> Result<(), ImportError> {
+        let Some(validator) = &package.validator else {
+            return Ok(());
+        };
+        let built = self.build(
+            "validator",
+            validator,
+            &scratch.join("validator"),
+            &package.support_files,
+        )?;
+        let valid = match package.checker_protocol {
+            CheckerProtocol::Testlib => 0,
+            CheckerProtocol::Kattis => 42,
+        };
+
+        for (number, test) in (1..).zip(&package.tests) {
+            let input = std::fs::read(&test.input)?;
+            let outcome = self.execute(&built, &[], &input)?;
+            if outcome.status != Termination::Exited(valid) {
+                return Err(invalid(format!(
+                    "test {number} is rejected by the validator ({}):\n{}",
+                    outcome.status,
+                    String::from_utf8_lossy(&outcome.stderr).trim()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the problem described by `package` in a single transaction,
+    /// so that a failure leaves no trace of it in the database. Files written
+    /// so far are removed again.
+    pub async fn store(
+        &self,
+        conn: &mut AsyncPgConnection,
+        package: &Package,
+    ) -> Result<Problem, ImportError> {
+        if package.tests.is_empty() {
+            return Err(invalid("the package has no tests"));
+        }
+
+        conn.transaction(|conn| self.store_in(conn, package).scope_boxed())
+            .await
+    }
+
+    async fn store_in(
+        &self,
+        conn: &mut AsyncPgConnection,
+        package: &Package,
+    ) -> Result<Problem, ImportError> {
+        let checker_program = package.checker_program.as_ref();
+        let problem = Problem::create(
+            conn,
+            &NewProblem {
+                title: &package.title,
+                statement: &package.statement,
+                time_limit_ms: package.time_limit_ms.unwrap_or(DEFAULT_TIME_LIMIT_MS),
+                memory_limit_bytes: package
+                    .memory_limit_bytes
+                    .unwrap_or(DEFAULT_MEMORY_LIMIT_BYTES),
+                checker: package.checker,
+                checker_absolute_epsilon: package.checker_absolute_epsilon,
+                checker_relative_epsilon: package.checker_relative_epsilon,
+                checker_source: checker_program.map(|program| program.source.as_str()),
+                checker_language: checker_program.map(|program| program.language.as_str()),
+                interactor_source: package.interactor.as_ref().map(|p| p.source.as_str()),
+                interactor_language: package.interactor.as_ref().map(|p| p.language.as_str()),
+                checker_protocol: package.checker_protocol,
+                validator_source: package.validator.as_ref().map(|p| p.source.as_str()),
+                validator_language: package.validator.as_ref().map(|p| p.language.as_str()),
+            },
+        )
+        .await?;
+        let problem_dir = self.data_dir.join(problem.id.to_string());
+
+        let result = store_contents(conn, &problem, package, &problem_dir).await;
+        if result.is_err() {
+            std::fs::remove_dir_all(&problem_dir).ok();
+        }
+
+        result.map(|()| problem)
+    }
+
+    /// Compiles a program that comes with a package in `workdir`, next to
+    /// copies of `support_files`.
+    fn build(
+        &self,
+        role: &str,
+        program: &Program,
+        workdir: &Path,
+        support_files: &[PathBuf],
+    ) -> Result<Built, ImportError> {
+        let Some(language) = self.languages.get(&program.language) else {
+            return Err(ImportError::Generation(format!(
+                "language {} of the {role} is not available",
+                program.language
+            )));
+        };
+        let module = language.module.clone();
+
+        self.sandbox.prepare_workdir(workdir)?;
+        copy_files(support_files, workdir)?;
+
+        match module.compile(&self.sandbox, &program.source, workdir) {
+            Ok(artifact) => Ok(Built { module, artifact }),
+            Err(CompileError::Io(e)) => Err(e.into()),
+            Err(e) => Err(ImportError::Generation(format!(
+                "the {role} does not compile:\n{}",
+                e.log()
+            ))),
+        }
+    }
+
+    /// Runs a built program with `args` and `stdin`, returning its stdout.
+    fn run(
+        &self,
+        role: &str,
+        built: &Built,
+        args: &[String],
+        stdin: &[u8],
+    ) -> Result<Vec<u8>, ImportError> {
+        let outcome = self.execute(built, args, stdin)?;
+
+        match outcome.status {
+            Termination::Exited(0) => Ok(outcome.stdout),
+            status => Err(ImportError::Generation(format!(
+                "the {role} did not finish properly: {status}\n{}",
+                String::from_utf8_lossy(&outcome.stderr).trim()
+            ))),
+        }
+    }
+
+    fn execute(&self, built: &Built, args: &[String], stdin: &[u8]) -> std::io::Result<RunOutcome> {
+        let invocation = built.module.invocation(&built.artifact).args(args);
+        self.sandbox
+            .execute(&invocation, stdin, &Limits::GENERATOR, None)
+    }
+}
+
+/// A program built while importing.
+struct Built {
+    module: Arc<dyn LanguageModule>,
+    artifact: Artifact,
+}
+
+async fn store_contents(
+    conn: &mut AsyncPgConnection,
+    problem: &Problem,
+    package: &Package,
+    problem_dir: &Path,
+) -> Result<(), ImportError> {
+    std::fs::create_dir_all(problem_dir.join("tests"))?;
+
+    // The judge builds checkers and interactors next to the files in the
+    // directory named after their role.
+    if package.checker_program.is_some() {
+        let dir = problem_dir.join("checker");
+        std::fs::create_dir_all(&dir)?;
+        copy_files(&package.support_files, &dir)?;
+    }
+    if package.interactor.is_some() {
+        let dir = problem_dir.join("interactor");
+        std::fs::create_dir_all(&dir)?;
+        copy_files(&package.support_files, &dir)?;
+    }
+    // Validators are not judged with, but kept for exporting.
+    if package.validator.is_some() {
+        let dir = problem_dir.join("validator");
+        std::fs::create_dir_all(&dir)?;
+        copy_files(&package.support_files, &dir)?;
+    }
+
+    for (ordinal, test) in (1..).zip(&package.tests) {
+        let input_path = format!("tests/{ordinal}.in");
+        let answer_path = format!("tests/{ordinal}.ans");
+        std::fs::copy(&test.input, problem_dir.join(&input_path))?;
+        std::fs::copy(&test.answer, problem_dir.join(&answer_path))?;
+
+        TestCase::create(
+            conn,
+            &NewTestCase {
+                problem_id: problem.id,
+                ordinal,
+                test_group: test.test_group,
+                is_sample: test.is_sample,
+                input: None,
+                input_path: Some(&input_path),
+                answer: None,
+                answer_path: Some(&answer_path),
+            },
+        )
+        .await?;
+    }
+
+    for subtask in &package.subtasks {
+        Subtask::create(
+            conn,
+            &Subtask {
+                problem_id: problem.id,
+                test_group: subtask.test_group,
+                points: subtask.points,
+                scoring: subtask.scoring,
+                depends_on: subtask.depends_on.clone(),
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, to: &Path) -> Result<(), ImportError> {
+    let file = std::fs::File::open(archive)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        // Entries must not escape the directory they are extracted to.
+        let Some(name) = entry.enclosed_name().map(Path::to_path_buf) else {
+            return Err(invalid(format!("unsafe path in archive: {}", entry.name())));
+        };
+        let path = to.join(name);
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)?;
+        } else {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = std::fs::File::create(&path)?;
+            std::io::copy(&mut entry, &mut file)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `dir`, or its only subdirectory if the package was packed inside one.
+fn package_root(dir: &Path) -> Result<PathBuf, ImportError> {
+    let markers = ["problem.xml", "problem.yaml"];
+    if markers.iter().any(|marker| dir.join(marker).is_file()) {
+        return Ok(dir.to_path_buf());
+    }
+
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        entries.push(entry?.path());
+    }
+    match entries.as_slice() {
+        [only] if only.is_dir() => Ok(only.clone()),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
+
+fn copy_files(files: &[PathBuf], to: &Path) -> std::io::Result<()> {
+    for file in files {
+        if let Some(name) = file.file_name() {
+            std::fs::copy(file, to.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Extensions source files go by besides the one of their language, see
+/// [`LanguageModule::extension`].
+const EXTENSION_ALIASES: &[(&str, &str)] = &[("cc", "cpp"), ("cxx", "cpp"), ("c++", "cpp")];
+
+/// Registered languages whose sources have the extension of `path`, in
+/// registration order.
+fn languages_of<'a>(
+    languages: &'a LanguageRegistry,
+    path: &Path,
+) -> impl Iterator<Item = &'a RegisteredLanguage> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| {
+            EXTENSION_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == extension)
+                .map_or(extension, |&(_, extension)| extension)
+                .to_owned()
+        });
+
+    languages
+        .iter()
+        .filter(move |language| extension.as_deref() == Some(language.module.extension()))
+}
+
+/// Id of the language a source file is written in, judging by its
+/// extension: the first registered language with it, which for languages
+/// that come in variants is the default one.
+fn language_of<'a>(languages: &'a LanguageRegistry, path: &Path) -> Option<&'a str> {
+    languages_of(languages, path)
+        .next()
+        .map(|language| language.module.id())
+}
+
+/// Extension of source files written in `language`, if it is registered.
+fn extension_of<'a>(languages: &'a LanguageRegistry, language: &str) -> Option<&'a str> {
+    languages
+        .get(language)
+        .map(|language| language.module.extension())
+}
+
+/// Reads a checker, interactor or other program of a package.
+fn read_program(
+    languages: &LanguageRegistry,
+    path: &Path,
+    language: Option<&str>,
+) -> Result<Program, ImportError> {
+    let language = language
+        .or_else(|| language_of(languages, path))
+        .ok_or_else(|| invalid(format!("unknown language of {}", path.display())))?;
+
+    Ok(Program {
+        source: std::fs::read_to_string(path)?,
+        language: language.to_owned(),
+    })
+}
+
+/// What the tests of the package readers share.
+#[cfg(test)]
+mod testing {
+    use std::path::Path;
+
+    use language_module::{
+        Artifact, CompileError, Invocation, LanguageModule, LanguageRegistry, Sandbox,
+        SeccompProfile,
+    };
+
+    use crate::judging::Workdir;
+
+    /// A language whose sources are read but never built.
+    struct Unbuilt {
+        id: &'static str,
+        name: &'static str,
+        extension: &'static str,
+    }
+
+    impl LanguageModule for Unbuilt {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn extension(&self) -> &str {
+            self.extension
+        }
+
+        fn version(&self) -> std::io::Result<String> {
+            Ok("test".to_owned())
+        }
+
+        fn compile(&self, _: &Sandbox, _: &str, _: &Path) -> Result<Artifact, CompileError> {
+            unimplemented!("{} is not built in tests", self.id)
+        }
+
+        fn invocation(&self, _: &Artifact) -> Invocation {
+            unimplemented!("{} is not run in tests", self.id)
+        }
+
+        fn seccomp_profile(&self) -> SeccompProfile {
+            unimplemented!("{} is not run in tests", self.id)
+        }
+    }
+
+    /// The C++ and Python variants, in the order the launcher registers
+    /// them.
+    pub(super) fn languages() -> LanguageRegistry {
+        let mut languages = LanguageRegistry::default();
+        for (id, name, extension) in [
+            ("cpp", "GNU C++17", "cpp"),
+            ("cpp20", "GNU C++20", "cpp"),
+            ("python", "Python 3 (CPython)", "py"),
+            ("pypy", "Python 3 (PyPy)", "py"),
+        ] {
+            languages.register(Unbuilt {
+                id,
+                name,
+                extension,
+            });
+        }
+        languages
+    }
+
+    /// A package written file by file into a scratch directory.
+    pub(super) struct Fixture(Workdir);
+
+    impl Fixture {
+        pub(super) fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("package-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&root).unwrap();
+            Self(Workdir(root))
+        }
+
+        pub(super) fn root(&self) -> &Path {
+            &self.0 .0
+        }
+
+        /// Writes `contents` to `path`, relative to the root of the package.
+        pub(super) fn write(&self, path: &str, contents: &str) -> &Self {
+            let path = self.root().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+            self
+        }
+    }
+}
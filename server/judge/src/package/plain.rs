@@ -0,0 +1,94 @@
+//! A plain directory of tests.
+
+use std::path::Path;
+
+use super::{invalid, ImportError, Package, PackageTest};
+
+/// Names of statement files looked for, by priority.
+const STATEMENT_FILES: &[&str] = &["statement.md", "statement.txt", "statement.html"];
+/// Answer file extensions, by priority.
+const ANSWER_EXTENSIONS: &[&str] = &["ans", "out", "a"];
+
+/// Reads the problem in the directory at `path`.
+///
+/// The directory holds an optional `statement.md` and pairs of `<name>.in`
+/// and `<name>.ans` (or `.out`) files, either directly or in a `tests`
+/// subdirectory. Tests whose names start with `sample` or `example` are
+/// samples. The title is the name of the directory.
+pub(super) fn read(path: &Path) -> Result<Package, ImportError> {
+    let title = path
+        .canonicalize()?
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let statement = STATEMENT_FILES
+        .iter()
+        .map(|name| path.join(name))
+        .find(|path| path.is_file())
+        .map(std::fs::read_to_string)
+        .transpose()?
+        .unwrap_or_default();
+
+    let tests_dir = match path.join("tests") {
+        dir if dir.is_dir() => dir,
+        _ => path.to_path_buf(),
+    };
+    let tests = read_tests(&tests_dir)?;
+    if tests.is_empty() {
+        return Err(invalid(format!(
+            "no tests found in {}",
+            tests_dir.display()
+        )));
+    }
+
+    Ok(Package {
+        statement,
+        tests,
+        ..Package::new(title)
+    })
+}
+
+fn read_tests(dir: &Path) -> Result<Vec<PackageTest>, ImportError> {
+    let mut inputs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|extension| extension == "in") {
+            inputs.push(path);
+        }
+    }
+    inputs.sort_by_cached_key(|path| test_order(path));
+
+    inputs
+        .into_iter()
+        .map(|input| {
+            let answer = ANSWER_EXTENSIONS
+                .iter()
+                .map(|extension| input.with_extension(extension))
+                .find(|path| path.is_file())
+                .ok_or_else(|| invalid(format!("{} has no answer file", input.display())))?;
+            let name = file_stem(&input);
+
+            Ok(PackageTest {
+                test_group: 0,
+                is_sample: name.starts_with("sample") || name.starts_with("example"),
+                input,
+                answer,
+            })
+        })
+        .collect()
+}
+
+/// Orders samples first, then numbered tests by number, then the rest by
+/// name.
+fn test_order(path: &Path) -> (bool, u64, String) {
+    let name = file_stem(path);
+    let is_sample = name.starts_with("sample") || name.starts_with("example");
+    (!is_sample, name.parse().unwrap_or(u64::MAX), name)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,714 @@
+//! Polygon packages, described by `problem.xml`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use db::orm::{CheckerKind, ScoringPolicy};
+use language_module::LanguageRegistry;
+use roxmltree::{Document, Node};
+
+use super::{
+    invalid, languages_of, read_program, Built, ImportError, Importer, Package, PackageSubtask,
+    PackageTest, Program,
+};
+
+/// Statement languages, by preference.
+const LANGUAGES: &[&str] = &["russian", "english"];
+
+pub(super) fn read(
+    importer: &Importer,
+    root: &Path,
+    scratch: &Path,
+) -> Result<Package, ImportError> {
+    let xml = std::fs::read_to_string(root.join("problem.xml"))?;
+    let document = Document::parse(&xml).map_err(|e| invalid(format!("problem.xml: {e}")))?;
+    let problem = document.root_element();
+
+    let title = preferred(child(problem, "names"), "name")
+        .and_then(|name| name.attribute("value"))
+        .or_else(|| problem.attribute("short-name"))
+        .unwrap_or_default()
+        .to_owned();
+    let mut package = Package::new(title);
+
+    if let Some(statement) = preferred(child(problem, "statements"), "statement")
+        .filter(|statement| statement.attribute("type") == Some("application/x-tex"))
+        .and_then(|statement| statement.attribute("path"))
+    {
+        package.statement = std::fs::read_to_string(root.join(statement))?;
+    }
+
+    package.support_files = child(problem, "files")
+        .and_then(|files| child(files, "resources"))
+        .map(|resources| children(resources, "file"))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|file| file.attribute("path"))
+        .map(|path| root.join(path))
+        .filter(|path| path.is_file())
+        .collect();
+    let mut tests = Tests {
+        importer,
+        root,
+        scratch,
+        problem,
+        support_files: package.support_files.clone(),
+        generators: HashMap::new(),
+        solution: None,
+    };
+
+    let assets = child(problem, "assets");
+    if let Some(checker) = assets.and_then(|assets| child(assets, "checker")) {
+        read_checker(&importer.languages, root, checker, &mut package)?;
+    }
+    package.interactor = assets
+        .and_then(|assets| child(assets, "interactor"))
+        .map(|interactor| source(&importer.languages, root, interactor))
+        .transpose()?;
+    package.validator = assets
+        .and_then(|assets| child(assets, "validators"))
+        .and_then(|validators| child(validators, "validator"))
+        .map(|validator| source(&importer.languages, root, validator))
+        .transpose()?;
+
+    let testset = child(problem, "judging")
+        .map(|judging| children(judging, "testset"))
+        .unwrap_or_default()
+        .into_iter()
+        .find(|testset| testset.attribute("name") == Some("tests"))
+        .ok_or_else(|| invalid("problem.xml has no testset named tests"))?;
+
+    package.time_limit_ms = child_text(testset, "time-limit")
+        .map(|limit| limit.parse())
+        .transpose()
+        .map_err(|_| invalid("the time limit is not a number"))?;
+    package.memory_limit_bytes = child_text(testset, "memory-limit")
+        .map(|limit| limit.parse())
+        .transpose()
+        .map_err(|_| invalid("the memory limit is not a number"))?;
+
+    let groups = Groups::new(testset);
+    let mut points: HashMap<i32, i32> = HashMap::new();
+
+    let input_pattern = child_text(testset, "input-path-pattern")
+        .ok_or_else(|| invalid("the testset has no input path pattern"))?;
+    let answer_pattern = child_text(testset, "answer-path-pattern")
+        .ok_or_else(|| invalid("the testset has no answer path pattern"))?;
+    let test_nodes = child(testset, "tests")
+        .map(|tests| children(tests, "test"))
+        .unwrap_or_default();
+
+    for (number, test) in (1..).zip(test_nodes) {
+        let input = root.join(format_pattern(input_pattern, number));
+        let input = if input.is_file() {
+            input
+        } else if let Some(cmd) = test.attribute("cmd") {
+            tests.generate(cmd, number)?
+        } else {
+            return Err(invalid(format!("test {number} has no input file")));
+        };
+
+        let answer = root.join(format_pattern(answer_pattern, number));
+        let answer = if answer.is_file() {
+            answer
+        } else {
+            tests.solve(&input, number)?
+        };
+
+        let test_group = test
+            .attribute("group")
+            .map(|group| groups.id(group))
+            .unwrap_or(0);
+        if let Some(test_points) = test.attribute("points") {
+            // Polygon allows fractional points; subtasks only whole ones.
+            let test_points: f64 = test_points
+                .parse()
+                .map_err(|_| invalid(format!("points of test {number} are not a number")))?;
+            *points.entry(test_group).or_default() += test_points.round() as i32;
+        }
+
+        package.tests.push(PackageTest {
+            test_group,
+            is_sample: test.attribute("sample") == Some("true"),
+            input,
+            answer,
+        });
+    }
+
+    package.subtasks = groups.subtasks(testset, &points)?;
+
+    Ok(package)
+}
+
+/// Builds what is missing from a package that ships without generated
+/// tests or answers.
+struct Tests<'a, 'input> {
+    importer: &'a Importer,
+    root: &'a Path,
+    scratch: &'a Path,
+    problem: Node<'a, 'input>,
+    /// Resources such as `testlib.h`, built along with every program.
+    support_files: Vec<PathBuf>,
+    /// Generators built so far, by name.
+    generators: HashMap<String, Built>,
+    /// The main solution, once built.
+    solution: Option<Built>,
+}
+
+impl Tests<'_, '_> {
+    /// Runs a generator command line such as `gen 10 20`, where `gen` is an
+    /// executable of the package.
+    fn generate(&mut self, cmd: &str, number: usize) -> Result<PathBuf, ImportError> {
+        let mut words = cmd.split_whitespace();
+        let name = words
+            .next()
+            .ok_or_else(|| invalid(format!("test {number} has an empty generator command")))?;
+        let args: Vec<String> = words.map(str::to_owned).collect();
+
+        if !self.generators.contains_key(name) {
+            let program = self.executable(name)?;
+            let built = self.importer.build(
+                "generator",
+                &program,
+                &self.scratch.join(format!("generator-{name}")),
+                &self.support_files,
+            )?;
+            self.generators.insert(name.to_owned(), built);
+        }
+
+        let output = self
+            .importer
+            .run("generator", &self.generators[name], &args, &[])?;
+        self.write(&format!("{number}.in"), &output)
+    }
+
+    /// Runs the main solution on `input` to get the answer.
+    fn solve(&mut self, input: &Path, number: usize) -> Result<PathBuf, ImportError> {
+        if self.solution.is_none() {
+            let solution = child(self.problem, "assets")
+                .and_then(|assets| child(assets, "solutions"))
+                .map(|solutions| children(solutions, "solution"))
+                .unwrap_or_default()
+                .into_iter()
+                .find(|solution| solution.attribute("tag") == Some("main"))
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "test {number} has no answer file and there is no main solution"
+                    ))
+                })?;
+            let program = source(&self.importer.languages, self.root, solution)?;
+            let built = self.importer.build(
+                "main solution",
+                &program,
+                &self.scratch.join("solution"),
+                &self.support_files,
+            )?;
+            self.solution = Some(built);
+        }
+
+        let input = std::fs::read(input)?;
+        let built = self.solution.as_ref().expect("The solution is built above");
+        let output = self.importer.run("main solution", built, &[], &input)?;
+        self.write(&format!("{number}.ans"), &output)
+    }
+
+    /// Source of the executable named `name`, without an extension.
+    fn executable(&self, name: &str) -> Result<Program, ImportError> {
+        child(self.problem, "files")
+            .and_then(|files| child(files, "executables"))
+            .map(|executables| children(executables, "executable"))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|executable| child(executable, "source"))
+            .find(|source| {
+                source
+                    .attribute("path")
+                    .and_then(|path| Path::new(path).file_stem())
+                    .is_some_and(|stem| stem == name)
+            })
+            .map(|source| program(&self.importer.languages, self.root, source))
+            .transpose()?
+            .ok_or_else(|| invalid(format!("no executable named {name}")))
+    }
+
+    fn write(&self, name: &str, data: &[u8]) -> Result<PathBuf, ImportError> {
+        let dir = self.scratch.join("tests");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(name);
+        std::fs::write(&path, data)?;
+        Ok(path)
+    }
+}
+
+/// Polygon names groups freely; subtasks are numbered. Numeric names keep
+/// their number, the rest are numbered after the largest of them.
+struct Groups {
+    ids: HashMap<String, i32>,
+}
+
+impl Groups {
+    fn new(testset: Node) -> Self {
+        let mut names: Vec<&str> = child(testset, "groups")
+            .map(|groups| children(groups, "group"))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|group| group.attribute("name"))
+            .collect();
+        names.extend(
+            child(testset, "tests")
+                .map(|tests| children(tests, "test"))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|test| test.attribute("group")),
+        );
+
+        let mut ids: HashMap<String, i32> = names
+            .iter()
+            .filter_map(|name| Some((name.to_string(), name.parse().ok()?)))
+            .collect();
+        let mut next = ids.values().max().map_or(1, |max| max + 1);
+        for name in names {
+            if !ids.contains_key(name) {
+                ids.insert(name.to_owned(), next);
+                next += 1;
+            }
+        }
+
+        Self { ids }
+    }
+
+    fn id(&self, name: &str) -> i32 {
+        self.ids[name]
+    }
+
+    /// A subtask for every group: `complete-group` groups earn their points
+    /// only when every test passes, `each-test` groups earn the sum of their
+    /// tests' points, split evenly. Without groups, tests with points make
+    /// up a subtask of their own group.
+    fn subtasks(
+        &self,
+        testset: Node,
+        points: &HashMap<i32, i32>,
+    ) -> Result<Vec<PackageSubtask>, ImportError> {
+        let groups = child(testset, "groups")
+            .map(|groups| children(groups, "group"))
+            .unwrap_or_default();
+
+        let mut subtasks = if groups.is_empty() {
+            points
+                .iter()
+                .map(|(&test_group, &points)| PackageSubtask {
+                    test_group,
+                    points,
+                    scoring: ScoringPolicy::Sum,
+                    depends_on: Vec::new(),
+                })
+                .collect()
+        } else {
+            groups
+                .into_iter()
+                .map(|group| self.subtask(group, points))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        subtasks.sort_by_key(|subtask| subtask.test_group);
+
+        Ok(subtasks)
+    }
+
+    fn subtask(
+        &self,
+        group: Node,
+        points: &HashMap<i32, i32>,
+    ) -> Result<PackageSubtask, ImportError> {
+        let name = group
+            .attribute("name")
+            .ok_or_else(|| invalid("a group has no name"))?;
+        let test_group = self.id(name);
+        let tests_points = points.get(&test_group).copied().unwrap_or(0);
+
+        let (scoring, points) = match group.attribute("points-policy") {
+            Some("complete-group") => {
+                let points = match group.attribute("points") {
+                    Some(points) => points
+                        .parse::<f64>()
+                        .map_err(|_| invalid(format!("points of group {name} are not a number")))?
+                        .round() as i32,
+                    None => tests_points,
+                };
+                (ScoringPolicy::AllOrNothing, points)
+            }
+            _ => (ScoringPolicy::Sum, tests_points),
+        };
+
+        let depends_on = child(group, "dependencies")
+            .map(|dependencies| children(dependencies, "dependency"))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|dependency| dependency.attribute("group"))
+            .filter_map(|group| self.ids.get(group).copied())
+            .collect();
+
+        Ok(PackageSubtask {
+            test_group,
+            points,
+            scoring,
+            depends_on,
+        })
+    }
+}
+
+/// Maps the standard testlib checkers onto the built-in ones, and takes
+/// anything else as a custom checker.
+fn read_checker(
+    languages: &LanguageRegistry,
+    root: &Path,
+    checker: Node,
+    package: &mut Package,
+) -> Result<(), ImportError> {
+    let standard = checker
+        .attribute("name")
+        .and_then(|name| name.strip_prefix("std::"))
+        .map(|name| name.trim_end_matches(".cpp"));
+
+    let epsilon = match standard {
+        Some("wcmp" | "ncmp" | "lcmp" | "hcmp" | "icmp" | "uncmp") => {
+            package.checker = CheckerKind::Tokens;
+            return Ok(());
+        }
+        Some("fcmp") => {
+            package.checker = CheckerKind::Exact;
+            return Ok(());
+        }
+        Some("yesno" | "nyesno") => {
+            package.checker = CheckerKind::CaseInsensitive;
+            return Ok(());
+        }
+        Some("rcmp") => 1.5e-6,
+        Some("rcmp4" | "dcmp") => 1e-4,
+        Some("rcmp6") => 1e-6,
+        Some("rcmp9") => 1e-9,
+        _ => {
+            package.checker = CheckerKind::Custom;
+            package.checker_program = Some(source(languages, root, checker)?);
+            return Ok(());
+        }
+    };
+
+    package.checker = CheckerKind::Float;
+    package.checker_absolute_epsilon = Some(epsilon);
+    package.checker_relative_epsilon = Some(epsilon);
+    Ok(())
+}
+
+/// The program whose `<source>` is a child of `node`.
+fn source(languages: &LanguageRegistry, root: &Path, node: Node) -> Result<Program, ImportError> {
+    let source = child(node, "source")
+        .ok_or_else(|| invalid(format!("<{}> has no source", node.tag_name().name())))?;
+    program(languages, root, source)
+}
+
+/// Reads the program a `<source path=".." type="cpp.g++17">` points to.
+fn program(
+    languages: &LanguageRegistry,
+    root: &Path,
+    source: Node,
+) -> Result<Program, ImportError> {
+    let path = source
+        .attribute("path")
+        .ok_or_else(|| invalid("a source has no path"))?;
+    let path = root.join(path);
+    let language = source
+        .attribute("type")
+        .and_then(|kind| variant(languages, &path, kind));
+
+    read_program(languages, &path, language)
+}
+
+/// The registered variant a Polygon type like `cpp.g++20`,
+/// `cpp.gcc13-64-winlibs-g++20` or `python.pypy3` asks for, matched by the
+/// name of its module. Without one the default language of the extension is
+/// used.
+fn variant<'a>(languages: &'a LanguageRegistry, path: &Path, kind: &str) -> Option<&'a str> {
+    let name = if let Some((_, standard)) = kind.rsplit_once("++") {
+        format!("C++{standard}")
+    } else if kind.contains("pypy") {
+        "PyPy".to_owned()
+    } else {
+        return None;
+    };
+
+    languages_of(languages, path)
+        .find(|language| language.module.name().contains(&name))
+        .map(|language| language.module.id())
+}
+
+/// Expands the `%d`-style placeholder in a test path pattern.
+fn format_pattern(pattern: &str, number: usize) -> String {
+    let Some(start) = pattern.find('%') else {
+        return pattern.to_owned();
+    };
+    let Some(length) = pattern[start..].find('d') else {
+        return pattern.to_owned();
+    };
+
+    let spec = &pattern[start + 1..start + length];
+    let width = spec.trim_start_matches('0').parse().unwrap_or(0);
+    let number = if spec.starts_with('0') {
+        format!("{number:0width$}")
+    } else {
+        format!("{number:width$}")
+    };
+
+    format!(
+        "{}{number}{}",
+        &pattern[..start],
+        &pattern[start + length + 1..]
+    )
+}
+
+/// The `tag` child with the most preferred `language` attribute.
+fn preferred<'a, 'input>(parent: Option<Node<'a, 'input>>, tag: &str) -> Option<Node<'a, 'input>> {
+    let nodes = parent
+        .map(|parent| children(parent, tag))
+        .unwrap_or_default();
+    LANGUAGES
+        .iter()
+        .find_map(|&language| {
+            nodes
+                .iter()
+                .find(|node| node.attribute("language") == Some(language))
+        })
+        .or_else(|| nodes.first())
+        .copied()
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+fn children<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Vec<Node<'a, 'input>> {
+    node.children()
+        .filter(|child| child.has_tag_name(tag))
+        .collect()
+}
+
+fn child_text<'a>(node: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(node, tag)
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use language_module::Sandbox;
+
+    use super::*;
+    use crate::package::testing::{languages, Fixture};
+
+    /// Reads the Polygon package written to `fixture`, with the tests of
+    /// `testset` and the assets in `assets`.
+    fn read_package(fixture: &Fixture, testset: &str, assets: &str) -> Package {
+        fixture.write(
+            "problem.xml",
+            &format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<problem short-name="sum">
+  <names>
+    <name language="english" value="Sum"/>
+    <name language="russian" value="Сумма"/>
+  </names>
+  <judging>
+    <testset name="tests">
+      <time-limit>1500</time-limit>
+      <memory-limit>268435456</memory-limit>
+      <input-path-pattern>tests/%02d</input-path-pattern>
+      <answer-path-pattern>tests/%02d.a</answer-path-pattern>
+      {testset}
+    </testset>
+  </judging>
+  <files>
+    <resources><file path="files/testlib.h" type="h.g++"/></resources>
+  </files>
+  <assets>{assets}</assets>
+</problem>"#
+            ),
+        );
+        let importer = Importer {
+            languages: languages(),
+            sandbox: Sandbox::default(),
+            workdir: fixture.root().join("scratch"),
+            data_dir: fixture.root().join("data"),
+        };
+
+        read(&importer, fixture.root(), &fixture.root().join("scratch")).unwrap()
+    }
+
+    /// A package with `tests` tests, each input holding its number.
+    fn fixture(tests: usize) -> Fixture {
+        let fixture = Fixture::new();
+        fixture.write("files/testlib.h", "// testlib.h\n");
+        for number in 1..=tests {
+            fixture
+                .write(&format!("tests/{number:02}"), &format!("{number}\n"))
+                .write(&format!("tests/{number:02}.a"), &format!("{number}\n"));
+        }
+        fixture
+    }
+
+    #[test]
+    fn reads_limits_and_tests() {
+        let fixture = fixture(2);
+        let package = read_package(
+            &fixture,
+            r#"<tests><test method="manual" sample="true"/><test method="manual"/></tests>"#,
+            "",
+        );
+
+        assert_eq!(package.title, "Сумма");
+        assert_eq!(package.time_limit_ms, Some(1500));
+        assert_eq!(package.memory_limit_bytes, Some(256 * 1024 * 1024));
+        assert_eq!(
+            package.support_files,
+            [fixture.root().join("files/testlib.h")]
+        );
+        assert!(package.subtasks.is_empty());
+
+        let tests: Vec<_> = package
+            .tests
+            .iter()
+            .map(|test| (test.test_group, test.is_sample, test.input.clone()))
+            .collect();
+        assert_eq!(
+            tests,
+            [
+                (0, true, fixture.root().join("tests/01")),
+                (0, false, fixture.root().join("tests/02")),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_groups_and_scores_subtasks() {
+        let fixture = fixture(5);
+        let package = read_package(
+            &fixture,
+            r#"<tests>
+                 <test method="manual" sample="true" group="0"/>
+                 <test method="manual" group="first" points="10"/>
+                 <test method="manual" group="first" points="10"/>
+                 <test method="manual" group="second" points="2.4"/>
+                 <test method="manual" group="second" points="2.4"/>
+               </tests>
+               <groups>
+                 <group name="0" points-policy="each-test"/>
+                 <group name="first" points="30" points-policy="complete-group">
+                   <dependencies><dependency group="0"/></dependencies>
+                 </group>
+                 <group name="second" points-policy="each-test">
+                   <dependencies><dependency group="first"/></dependencies>
+                 </group>
+               </groups>"#,
+            "",
+        );
+
+        let groups: Vec<_> = package.tests.iter().map(|test| test.test_group).collect();
+        assert_eq!(groups, [0, 1, 1, 2, 2]);
+
+        let subtasks: Vec<_> = package
+            .subtasks
+            .iter()
+            .map(|subtask| {
+                (
+                    subtask.test_group,
+                    subtask.points,
+                    subtask.scoring,
+                    subtask.depends_on.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            subtasks,
+            [
+                (0, 0, ScoringPolicy::Sum, vec![]),
+                (1, 30, ScoringPolicy::AllOrNothing, vec![0]),
+                (2, 4, ScoringPolicy::Sum, vec![1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_standard_checkers() {
+        for (name, checker, epsilon) in [
+            ("std::wcmp.cpp", CheckerKind::Tokens, None),
+            ("std::fcmp.cpp", CheckerKind::Exact, None),
+            ("std::yesno.cpp", CheckerKind::CaseInsensitive, None),
+            ("std::rcmp6.cpp", CheckerKind::Float, Some(1e-6)),
+            ("std::rcmp9.cpp", CheckerKind::Float, Some(1e-9)),
+        ] {
+            let fixture = fixture(1);
+            let package = read_package(
+                &fixture,
+                r#"<tests><test method="manual"/></tests>"#,
+                &format!(r#"<checker name="{name}" type="testlib"/>"#),
+            );
+
+            assert_eq!(package.checker, checker, "{name}");
+            assert_eq!(package.checker_absolute_epsilon, epsilon, "{name}");
+            assert_eq!(package.checker_relative_epsilon, epsilon, "{name}");
+            assert!(package.checker_program.is_none(), "{name}");
+        }
+    }
+
+    #[test]
+    fn picks_registered_variants_of_programs() {
+        let fixture = fixture(1);
+        fixture
+            .write("files/check.cpp", "// checker\n")
+            .write("files/interactor.cpp", "// interactor\n")
+            .write("files/val.py", "# validator\n");
+        let package = read_package(
+            &fixture,
+            r#"<tests><test method="manual"/></tests>"#,
+            r#"<checker type="testlib">
+                 <source path="files/check.cpp" type="cpp.gcc13-64-winlibs-g++20"/>
+               </checker>
+               <interactor><source path="files/interactor.cpp" type="cpp.g++17"/></interactor>
+               <validators>
+                 <validator><source path="files/val.py" type="python.pypy3"/></validator>
+               </validators>"#,
+        );
+
+        let language = |program: &Option<Program>| program.as_ref().unwrap().language.clone();
+        assert_eq!(package.checker, CheckerKind::Custom);
+        assert_eq!(
+            package.checker_program.as_ref().unwrap().source,
+            "// checker\n"
+        );
+        assert_eq!(language(&package.checker_program), "cpp20");
+        assert_eq!(language(&package.interactor), "cpp");
+        assert_eq!(language(&package.validator), "pypy");
+    }
+
+    #[test]
+    fn falls_back_to_the_default_variant() {
+        let fixture = fixture(1);
+        fixture.write("files/check.py", "# checker\n");
+        let package = read_package(
+            &fixture,
+            r#"<tests><test method="manual"/></tests>"#,
+            r#"<checker type="testlib"><source path="files/check.py" type="python.3"/></checker>"#,
+        );
+
+        assert_eq!(package.checker_program.unwrap().language, "python");
+    }
+
+    #[test]
+    fn formats_test_paths() {
+        assert_eq!(format_pattern("tests/%02d", 7), "tests/07");
+        assert_eq!(format_pattern("tests/%02d.a", 12), "tests/12.a");
+        assert_eq!(format_pattern("tests/%d", 3), "tests/3");
+        assert_eq!(format_pattern("tests/input", 3), "tests/input");
+    }
+}
@@ -0,0 +1,94 @@
+use db::orm::{Run, RunOutput, SubmissionStatus, Verdict};
+use language_module::{CompileError, Termination};
+
+use crate::{
+    judging::{blocking, millis, verdict_of, Workdir},
+    JudgeError, JudgeState,
+};
+
+/// Output kept of each stream of a run; the rest is cut off.
+const MAX_KEPT_OUTPUT: usize = 64 * 1024;
+
+/// Compiles a claimed run and executes it once on its input under the
+/// default limits of its language.
+pub async fn run(state: &JudgeState, run: &Run) -> Result<(), JudgeError> {
+    let Some(language) = state.languages.get(&run.language) else {
+        let output = RunOutput {
+            compile_log: Some(format!("Language {} is not available", run.language)),
+            verdict: Some(Verdict::CompilationError),
+            ..RunOutput::default()
+        };
+        let mut conn = state.db.get().await?;
+        Run::finish(&mut conn, run.id, &output).await?;
+        return Ok(());
+    };
+    let module = language.module.clone();
+
+    let workdir = Workdir(state.config.workdir.join(format!("run-{}", run.id)));
+    let compiled = {
+        let language = language.clone();
+        let cache = state.config.compile_cache.clone();
+        let sandbox = state.sandbox.clone();
+        let source = run.source.clone();
+        let workdir = workdir.0.clone();
+        blocking(move || {
+            sandbox.prepare_workdir(&workdir)?;
+            Ok(cache.compile(&language, &sandbox, &source, &workdir))
+        })
+        .await?
+    };
+
+    let (artifact, compiled_from_cache) = match compiled {
+        Ok(compiled) => (compiled.artifact, compiled.cached),
+        Err(CompileError::Io(e)) => return Err(e.into()),
+        Err(e) => {
+            let output = RunOutput {
+                compile_log: Some(e.log()),
+                verdict: Some(Verdict::CompilationError),
+                ..RunOutput::default()
+            };
+            let mut conn = state.db.get().await?;
+            Run::finish(&mut conn, run.id, &output).await?;
+            return Ok(());
+        }
+    };
+
+    let mut conn = state.db.get().await?;
+    Run::set_status(&mut conn, run.id, SubmissionStatus::Running).await?;
+    drop(conn);
+
+    let outcome = {
+        let sandbox = state.sandbox.clone();
+        let stdin = run.stdin.clone();
+        blocking(move || {
+            let defaults = module.default_limits();
+            let limits = module.limits(defaults.cpu_time, defaults.memory);
+            module.run(&sandbox, &artifact, stdin.as_bytes(), &limits)
+        })
+        .await?
+    };
+
+    let (verdict, exit_code) = match outcome.status {
+        Termination::Exited(code) => (None, Some(code)),
+        status => (Some(verdict_of(status)), None),
+    };
+    let output = RunOutput {
+        compile_log: None,
+        verdict,
+        exit_code,
+        stdout: Some(kept_output(&outcome.stdout)),
+        stderr: Some(kept_output(&outcome.stderr)),
+        time_ms: Some(millis(&outcome)),
+        memory_bytes: Some(outcome.peak_memory as i64),
+        compiled_from_cache: Some(compiled_from_cache),
+    };
+
+    let mut conn = state.db.get().await?;
+    Run::finish(&mut conn, run.id, &output).await?;
+
+    Ok(())
+}
+
+fn kept_output(output: &[u8]) -> String {
+    String::from_utf8_lossy(&output[..output.len().min(MAX_KEPT_OUTPUT)]).into_owned()
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+
+use db::orm::{ScoringPolicy, Subtask, TestCase};
+
+/// Collects test scores group by group and turns them into subtask points.
+pub struct Scoring<'a> {
+    subtasks: &'a [Subtask],
+    /// Tests in every group, judged or not.
+    tests: BTreeMap<i32, usize>,
+    /// Scores of the judged tests in every group.
+    scores: BTreeMap<i32, Vec<f64>>,
+}
+
+impl<'a> Scoring<'a> {
+    pub fn new(subtasks: &'a [Subtask], tests: &[TestCase]) -> Self {
+        let mut counts = BTreeMap::new();
+        for test in tests {
+            *counts.entry(test.test_group).or_default() += 1;
+        }
+
+        Self {
+            subtasks,
+            tests: counts,
+            scores: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, group: i32, score: f64) {
+        self.scores.entry(group).or_default().push(score);
+    }
+
+    /// Whether `group` can still earn points, judging by its dependencies on
+    /// earlier groups, which are judged first.
+    pub fn may_score(&self, group: i32) -> bool {
+        let Some(subtask) = self.subtask(group) else {
+            return true;
+        };
+
+        subtask
+            .depends_on
+            .iter()
+            .filter(|&&dependency| dependency < group)
+            .all(|&dependency| self.full(dependency, self.subtasks.len()))
+    }
+
+    /// Points earned on every subtask, in group order.
+    pub fn points(&self) -> Vec<(i32, f64)> {
+        self.subtasks
+            .iter()
+            .map(|subtask| {
+                let met = subtask
+                    .depends_on
+                    .iter()
+                    .all(|&dependency| self.full(dependency, self.subtasks.len()));
+                let points = if met {
+                    self.ratio(subtask) * subtask.points as f64
+                } else {
+                    0.0
+                };
+                (subtask.test_group, points)
+            })
+            .collect()
+    }
+
+    /// Whether `group` earned full points, dependencies included. `depth`
+    /// bounds the walk in case dependencies form a cycle.
+    fn full(&self, group: i32, depth: usize) -> bool {
+        let Some(subtask) = self.subtask(group) else {
+            return false;
+        };
+
+        depth > 0
+            && self.ratio(subtask) >= 1.0
+            && subtask
+                .depends_on
+                .iter()
+                .all(|&dependency| self.full(dependency, depth - 1))
+    }
+
+    /// Share of its points `subtask` earned on its own tests.
+    fn ratio(&self, subtask: &Subtask) -> f64 {
+        let tests = self.tests.get(&subtask.test_group).copied().unwrap_or(0);
+        let scores = self
+            .scores
+            .get(&subtask.test_group)
+            .map_or(&[][..], Vec::as_slice);
+
+        // Tests that did not run earn nothing, and a subtask needs tests
+        // to earn anything.
+        if tests == 0 || (scores.len() < tests && subtask.scoring != ScoringPolicy::Sum) {
+            return 0.0;
+        }
+
+        match subtask.scoring {
+            ScoringPolicy::AllOrNothing => {
+                if scores.iter().all(|&score| score >= 1.0) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            ScoringPolicy::Min => scores.iter().copied().fold(1.0, f64::min),
+            ScoringPolicy::Sum => scores.iter().sum::<f64>() / tests as f64,
+        }
+    }
+
+    fn subtask(&self, group: i32) -> Option<&Subtask> {
+        self.subtasks
+            .iter()
+            .find(|subtask| subtask.test_group == group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn subtask(
+        test_group: i32,
+        points: i32,
+        scoring: ScoringPolicy,
+        depends_on: &[i32],
+    ) -> Subtask {
+        Subtask {
+            problem_id: Uuid::nil(),
+            test_group,
+            points,
+            scoring,
+            depends_on: depends_on.to_vec(),
+        }
+    }
+
+    /// Tests of the given groups, one for every entry.
+    fn tests(groups: &[i32]) -> Vec<TestCase> {
+        (1..)
+            .zip(groups)
+            .map(|(ordinal, &test_group)| TestCase {
+                id: Uuid::new_v4(),
+                problem_id: Uuid::nil(),
+                ordinal,
+                test_group,
+                is_sample: false,
+                input: None,
+                input_path: None,
+                answer: None,
+                answer_path: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sum_splits_points_between_tests() {
+        let subtasks = [subtask(1, 10, ScoringPolicy::Sum, &[])];
+        let tests = tests(&[1, 1, 1, 1]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+        scoring.record(1, 1.0);
+        scoring.record(1, 0.5);
+        scoring.record(1, 0.0);
+        // The fourth test did not run and earns nothing.
+
+        assert_eq!(scoring.points(), [(1, 3.75)]);
+    }
+
+    #[test]
+    fn all_or_nothing_needs_every_test() {
+        let subtasks = [
+            subtask(1, 10, ScoringPolicy::AllOrNothing, &[]),
+            subtask(2, 20, ScoringPolicy::AllOrNothing, &[]),
+            subtask(3, 30, ScoringPolicy::AllOrNothing, &[]),
+        ];
+        let tests = tests(&[1, 1, 2, 2, 3, 3]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+        scoring.record(1, 1.0);
+        scoring.record(1, 1.0);
+        scoring.record(2, 1.0);
+        scoring.record(2, 0.5);
+        scoring.record(3, 1.0);
+
+        assert_eq!(scoring.points(), [(1, 10.0), (2, 0.0), (3, 0.0)]);
+    }
+
+    #[test]
+    fn min_takes_the_lowest_score() {
+        let subtasks = [
+            subtask(1, 10, ScoringPolicy::Min, &[]),
+            subtask(2, 10, ScoringPolicy::Min, &[]),
+        ];
+        let tests = tests(&[1, 1, 2, 2]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+        scoring.record(1, 0.8);
+        scoring.record(1, 0.5);
+        scoring.record(2, 1.0);
+
+        assert_eq!(scoring.points(), [(1, 5.0), (2, 0.0)]);
+    }
+
+    #[test]
+    fn subtask_without_tests_earns_nothing() {
+        let subtasks = [subtask(1, 10, ScoringPolicy::Sum, &[])];
+        let scoring = Scoring::new(&subtasks, &[]);
+
+        assert_eq!(scoring.points(), [(1, 0.0)]);
+    }
+
+    #[test]
+    fn dependencies_need_full_points() {
+        let subtasks = [
+            subtask(0, 0, ScoringPolicy::AllOrNothing, &[]),
+            subtask(1, 10, ScoringPolicy::Sum, &[0]),
+            subtask(2, 20, ScoringPolicy::Sum, &[1]),
+        ];
+        let tests = tests(&[0, 1, 1, 2]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+        scoring.record(0, 1.0);
+        assert!(scoring.may_score(1));
+        scoring.record(1, 1.0);
+        scoring.record(1, 0.0);
+        assert!(!scoring.may_score(2));
+        scoring.record(2, 1.0);
+
+        assert_eq!(scoring.points(), [(0, 0.0), (1, 5.0), (2, 0.0)]);
+    }
+
+    #[test]
+    fn dependencies_are_transitive() {
+        let subtasks = [
+            subtask(1, 10, ScoringPolicy::AllOrNothing, &[]),
+            subtask(2, 10, ScoringPolicy::AllOrNothing, &[1]),
+            subtask(3, 10, ScoringPolicy::AllOrNothing, &[2]),
+        ];
+        let tests = tests(&[1, 2, 3]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+        scoring.record(1, 0.0);
+        scoring.record(2, 1.0);
+        scoring.record(3, 1.0);
+
+        assert!(!scoring.may_score(3));
+        assert_eq!(scoring.points(), [(1, 0.0), (2, 0.0), (3, 0.0)]);
+    }
+
+    #[test]
+    fn later_dependencies_do_not_stop_judging() {
+        let subtasks = [
+            subtask(1, 10, ScoringPolicy::Sum, &[2]),
+            subtask(2, 10, ScoringPolicy::Sum, &[]),
+        ];
+        let tests = tests(&[1, 2]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+
+        assert!(scoring.may_score(1));
+        scoring.record(1, 1.0);
+        scoring.record(2, 0.0);
+        assert_eq!(scoring.points(), [(1, 0.0), (2, 0.0)]);
+    }
+
+    #[test]
+    fn missing_dependency_is_never_met() {
+        let subtasks = [subtask(2, 10, ScoringPolicy::Sum, &[1])];
+        let tests = tests(&[1, 2]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+        scoring.record(1, 1.0);
+        scoring.record(2, 1.0);
+
+        assert!(!scoring.may_score(2));
+        assert_eq!(scoring.points(), [(2, 0.0)]);
+    }
+
+    #[test]
+    fn cyclic_dependencies_terminate() {
+        let subtasks = [
+            subtask(1, 10, ScoringPolicy::Sum, &[2]),
+            subtask(2, 10, ScoringPolicy::Sum, &[1]),
+        ];
+        let tests = tests(&[1, 2]);
+        let mut scoring = Scoring::new(&subtasks, &tests);
+        scoring.record(1, 1.0);
+        scoring.record(2, 1.0);
+
+        assert_eq!(scoring.points(), [(1, 0.0), (2, 0.0)]);
+    }
+
+    #[test]
+    fn groups_without_subtasks_may_score() {
+        let scoring = Scoring::new(&[], &[]);
+
+        assert!(scoring.may_score(0));
+        assert!(scoring.points().is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use db::orm::{Run, RunOutput, Submission, Verdict};
+
+use crate::{judging, running, JudgeError, JudgeState};
+
+/// Something a worker took off the queue.
+enum Claimed {
+    Submission(Submission),
+    /// Only taken while no submission is waiting.
+    Run(Run),
+}
+
+pub async fn run(state: Arc<JudgeState>, worker: usize) {
+    loop {
+        match claim(&state).await {
+            Ok(Some(Claimed::Submission(submission))) => {
+                judge_claimed(&state, worker, submission).await
+            }
+            Ok(Some(Claimed::Run(run))) => run_claimed(&state, worker, run).await,
+            Ok(None) => tokio::time::sleep(state.config.poll_interval).await,
+            Err(e) => {
+                eprintln!("Judge worker {worker} failed to claim a submission: {e}");
+                tokio::time::sleep(state.config.poll_interval).await;
+            }
+        }
+    }
+}
+
+async fn claim(state: &JudgeState) -> Result<Option<Claimed>, JudgeError> {
+    let mut conn = state.db.get().await?;
+
+    if let Some(submission) = Submission::claim_next(&mut conn, state.config.stale_after).await? {
+        return Ok(Some(Claimed::Submission(submission)));
+    }
+    let run = Run::claim_next(&mut conn, state.config.stale_after).await?;
+    Ok(run.map(Claimed::Run))
+}
+
+async fn judge_claimed(state: &JudgeState, worker: usize, submission: Submission) {
+    println!("Judge worker {worker} judging submission {}", submission.id);
+
+    let claim_id = submission
+        .claim_id
+        .expect("claimed submissions have a claim id");
+    // Judging stops as soon as the heartbeat finds the claim gone.
+    let result = tokio::select! {
+        result = judging::judge(state, &submission, claim_id) => result,
+        () = heartbeat(state, Heartbeat::Submission(submission.id, claim_id)) => {
+            Err(JudgeError::ClaimLost)
+        }
+    };
+
+    match result {
+        Ok(()) => {}
+        Err(JudgeError::ClaimLost) => {
+            eprintln!("Judge worker {worker} lost submission {}", submission.id);
+        }
+        Err(e) => {
+            eprintln!("Failed to judge submission {}: {e}", submission.id);
+
+            // If even this fails the heartbeat has stopped, so another worker
+            // picks the submission up once it goes stale.
+            if let Err(e) = fail(state, &submission, claim_id).await {
+                eprintln!("Failed to mark submission {} as failed: {e}", submission.id);
+            }
+        }
+    }
+}
+
+async fn run_claimed(state: &JudgeState, worker: usize, run: Run) {
+    println!("Judge worker {worker} executing run {}", run.id);
+
+    let result = tokio::select! {
+        result = running::run(state, &run) => result,
+        () = heartbeat(state, Heartbeat::Run(run.id)) => Err(JudgeError::ClaimLost),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to execute run {}: {e}", run.id);
+
+        if let Err(e) = fail_run(state, &run).await {
+            eprintln!("Failed to mark run {} as failed: {e}", run.id);
+        }
+    }
+}
+
+async fn fail(
+    state: &JudgeState,
+    submission: &Submission,
+    claim_id: uuid::Uuid,
+) -> Result<(), JudgeError> {
+    let mut conn = state.db.get().await?;
+    Submission::finish(
+        &mut conn,
+        submission.id,
+        claim_id,
+        Verdict::JudgementFailed,
+        None,
+        None,
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+async fn fail_run(state: &JudgeState, run: &Run) -> Result<(), JudgeError> {
+    let output = RunOutput {
+        verdict: Some(Verdict::JudgementFailed),
+        ..RunOutput::default()
+    };
+
+    let mut conn = state.db.get().await?;
+    Run::finish(&mut conn, run.id, &output).await?;
+    Ok(())
+}
+
+/// What a worker is busy with: a submission under its claim id, or a run.
+#[derive(Debug, Clone, Copy)]
+enum Heartbeat {
+    Submission(uuid::Uuid, uuid::Uuid),
+    Run(uuid::Uuid),
+}
+
+/// Keeps telling other workers that `of` is being worked on. Returns once
+/// the worker no longer holds it.
+async fn heartbeat(state: &JudgeState, of: Heartbeat) {
+    let mut interval = tokio::time::interval(state.config.stale_after / 4);
+
+    loop {
+        interval.tick().await;
+
+        let result = match state.db.get().await {
+            Ok(mut conn) => match of {
+                Heartbeat::Submission(id, claim_id) => {
+                    Submission::heartbeat(&mut conn, id, claim_id).await
+                }
+                Heartbeat::Run(id) => Run::heartbeat(&mut conn, id).await,
+            }
+            .map_err(JudgeError::from),
+            Err(e) => Err(e.into()),
+        };
+
+        match result {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to send heartbeat for {of:?}: {e}"),
+        }
+    }
+}
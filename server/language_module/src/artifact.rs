@@ -0,0 +1,62 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::{Limits, SeccompProfile};
+
+/// The result of a successful compilation.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    /// Directory the artifact lives in; programs are started from here.
+    pub workdir: PathBuf,
+    /// Executable or script produced by the compile step.
+    pub path: PathBuf,
+}
+
+/// A fully specified process to start.
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    pub workdir: PathBuf,
+    /// Environment variables set on top of a minimal `PATH`, `LANG` and
+    /// `HOME`; none of the judge's own are passed on.
+    pub env: Vec<(OsString, OsString)>,
+}
+
+impl Invocation {
+    pub fn new(program: impl Into<OsString>, workdir: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            workdir: workdir.into(),
+            env: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// A process together with everything that confines it in the sandbox.
+#[derive(Debug, Clone)]
+pub struct Execution {
+    pub invocation: Invocation,
+    pub limits: Limits,
+    /// System calls the process may make; `None` leaves them unfiltered.
+    pub profile: Option<SeccompProfile>,
+}
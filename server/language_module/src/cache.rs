@@ -0,0 +1,206 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Artifact, CompileError, RegisteredLanguage, Sandbox};
+
+/// Distinguishes the staging directories of workers in one process.
+static STAGING_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Compiled artifacts kept on disk, so that the same source is not compiled
+/// twice by the same toolchain.
+///
+/// Every artifact lives in a directory named after the hash of its source,
+/// language, toolchain version and [`cache_key`](crate::LanguageModule::cache_key).
+/// Directories are written under a temporary name and renamed into place,
+/// so any number of workers, even in different processes, may share one
+/// cache. Once it outgrows `max_bytes`, the artifacts used least recently
+/// are removed.
+#[derive(Debug, Clone)]
+pub struct CompileCache {
+    pub dir: PathBuf,
+    /// Zero turns the cache off.
+    pub max_bytes: u64,
+}
+
+/// An artifact fresh from the toolchain or from the [`CompileCache`].
+#[derive(Debug, Clone)]
+pub struct Compiled {
+    pub artifact: Artifact,
+    pub cached: bool,
+}
+
+impl CompileCache {
+    /// Compiles `source` inside `workdir` like
+    /// [`LanguageModule::compile`](crate::LanguageModule::compile), unless
+    /// the artifact is cached, in which case it is copied to `workdir`.
+    ///
+    /// Only successful compilations are cached. Failing to use the cache
+    /// is not an error, the source is compiled instead.
+    pub fn compile(
+        &self,
+        language: &RegisteredLanguage,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Compiled, CompileError> {
+        let key = match language.module.cache_key() {
+            Some(settings) if self.max_bytes > 0 => Some(key(language, &settings, source)),
+            _ => None,
+        };
+
+        if let Some(key) = &key {
+            match self.restore(key, workdir) {
+                Ok(Some(artifact)) => {
+                    return Ok(Compiled {
+                        artifact,
+                        cached: true,
+                    })
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Failed to take artifact {key} from the cache: {e}"),
+            }
+        }
+
+        let artifact = language.module.compile(sandbox, source, workdir)?;
+
+        if let Some(key) = &key {
+            if let Err(e) = self.store(key, &artifact) {
+                eprintln!("Failed to cache artifact {key}: {e}");
+            }
+        }
+
+        Ok(Compiled {
+            artifact,
+            cached: false,
+        })
+    }
+
+    /// Copies the artifact stored under `key` to `workdir`, if there is one.
+    fn restore(&self, key: &str, workdir: &Path) -> io::Result<Option<Artifact>> {
+        let entry = self.dir.join(key);
+
+        let file = match fs::read_dir(&entry) {
+            Ok(mut files) => match files.next() {
+                Some(file) => file?,
+                None => return Ok(None),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let path = workdir.join(file.file_name());
+        match fs::copy(file.path(), &path) {
+            Ok(_) => {}
+            // Removed by another worker in the meantime.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        // Marks the artifact as recently used.
+        fs::File::open(&entry)?.set_modified(SystemTime::now())?;
+
+        Ok(Some(Artifact {
+            workdir: workdir.to_owned(),
+            path,
+        }))
+    }
+
+    fn store(&self, key: &str, artifact: &Artifact) -> io::Result<()> {
+        let Some(name) = artifact.path.file_name() else {
+            return Ok(());
+        };
+
+        fs::create_dir_all(&self.dir)?;
+
+        let staging = self.dir.join(format!(
+            ".{key}.{}.{}",
+            std::process::id(),
+            STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&staging)?;
+
+        let stored = fs::copy(&artifact.path, staging.join(name))
+            .and_then(|_| fs::rename(&staging, self.dir.join(key)));
+        if stored.is_err() {
+            fs::remove_dir_all(&staging)?;
+            // Another worker was faster.
+            if self.dir.join(key).is_dir() {
+                return Ok(());
+            }
+        }
+        stored?;
+
+        self.evict()
+    }
+
+    /// Removes the least recently used artifacts until the cache fits in
+    /// `max_bytes`.
+    fn evict(&self) -> io::Result<()> {
+        let mut entries = Vec::new();
+        let mut total = 0;
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            // Still being written.
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            let (Ok(used_at), Ok(size)) = (
+                entry.metadata().and_then(|metadata| metadata.modified()),
+                dir_size(&entry.path()),
+            ) else {
+                // Removed by another worker in the meantime.
+                continue;
+            };
+
+            total += size;
+            entries.push((used_at, size, entry.path()));
+        }
+
+        entries.sort();
+        for (_, size, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+
+            match fs::remove_dir_all(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            total -= size;
+        }
+
+        Ok(())
+    }
+}
+
+fn key(language: &RegisteredLanguage, settings: &[String], source: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [language.module.id(), &language.version]
+        .into_iter()
+        .chain(settings.iter().map(String::as_str))
+    {
+        hasher.update(part.as_bytes());
+        // Keeps `["ab", "c"]` and `["a", "bc"]` apart.
+        hasher.update([0]);
+    }
+    hasher.update(source.as_bytes());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn dir_size(dir: &Path) -> io::Result<u64> {
+    fs::read_dir(dir)?.try_fold(0, |total, entry| Ok(total + entry?.metadata()?.len()))
+}
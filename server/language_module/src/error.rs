@@ -0,0 +1,42 @@
+use std::fmt;
+
+use crate::RunOutcome;
+
+#[derive(Debug)]
+pub enum CompileError {
+    /// The toolchain could not be started or the workdir was unusable.
+    Io(std::io::Error),
+    /// The toolchain ran and rejected the source.
+    Rejected(RunOutcome),
+}
+
+impl CompileError {
+    /// Compiler diagnostics suitable for showing to the author of the source.
+    pub fn log(&self) -> String {
+        match self {
+            Self::Io(e) => e.to_string(),
+            Self::Rejected(outcome) => {
+                let mut log = String::from_utf8_lossy(&outcome.stderr).into_owned();
+                log.push_str(&String::from_utf8_lossy(&outcome.stdout));
+                log
+            }
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to run compiler: {e}"),
+            Self::Rejected(outcome) => write!(f, "compilation failed ({})", outcome.status),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+impl From<std::io::Error> for CompileError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
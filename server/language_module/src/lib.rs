@@ -1 +1,118 @@
-pub trait LanguageModule {}
+pub mod artifact;
+pub mod cache;
+pub mod error;
+pub mod limits;
+pub mod outcome;
+pub mod registry;
+pub mod sandbox;
+pub mod toolchain;
+
+use std::{path::Path, time::Duration};
+
+pub use artifact::*;
+pub use cache::*;
+pub use error::*;
+pub use limits::*;
+pub use outcome::*;
+pub use registry::*;
+pub use sandbox::{Sandbox, SeccompProfile};
+
+/// A language the judge knows how to build and execute.
+///
+/// Every supported language plugs into the judge through this trait: the
+/// module turns a source file into an [`Artifact`] and describes how that
+/// artifact is started. Running it inside the [`Sandbox`] is shared by all
+/// modules.
+pub trait LanguageModule: Send + Sync {
+    /// Stable identifier, stored with submissions.
+    fn id(&self) -> &str;
+
+    /// Human readable name.
+    fn name(&self) -> &str;
+
+    /// Extension of source files, without the dot.
+    fn extension(&self) -> &str;
+
+    /// Version of the installed toolchain; fails if it is missing.
+    fn version(&self) -> std::io::Result<String>;
+
+    /// Limits used when a problem does not set its own.
+    fn default_limits(&self) -> Limits {
+        Limits::default()
+    }
+
+    /// How many times more CPU time than native code this language gets,
+    /// for runtimes that are slow to start or to run.
+    fn time_multiplier(&self) -> f64 {
+        1.0
+    }
+
+    /// Memory the runtime itself takes, such as the JVM's metaspace and
+    /// code cache, given on top of what a problem allows.
+    fn memory_overhead(&self) -> u64 {
+        0
+    }
+
+    /// Limits for a program of this language on a problem that gives native
+    /// programs `cpu_time` and `memory`.
+    fn limits(&self, cpu_time: Duration, memory: u64) -> Limits {
+        let cpu_time = cpu_time.mul_f64(self.time_multiplier());
+
+        Limits {
+            cpu_time,
+            wall_time: cpu_time * 2 + Duration::from_secs(1),
+            memory: memory.saturating_add(self.memory_overhead()),
+            ..self.default_limits()
+        }
+    }
+
+    /// Builds `source` inside `workdir` and returns the resulting artifact.
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError>;
+
+    /// What besides the source and the toolchain's version decides the
+    /// artifact [`compile`](Self::compile) builds, such as compiler flags.
+    ///
+    /// Only given by modules whose artifact is the single file at
+    /// [`Artifact::path`], which lets [`CompileCache`] keep it.
+    fn cache_key(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Describes the process that runs a compiled `artifact`.
+    fn invocation(&self, artifact: &Artifact) -> Invocation;
+
+    /// System calls and files a running artifact needs.
+    fn seccomp_profile(&self) -> SeccompProfile;
+
+    /// Describes how a compiled `artifact` is started and confined under
+    /// `limits`.
+    fn execution(&self, artifact: &Artifact, limits: &Limits) -> Execution {
+        Execution {
+            invocation: self.invocation(artifact),
+            limits: *limits,
+            profile: Some(self.seccomp_profile()),
+        }
+    }
+
+    /// Runs a compiled `artifact` under `limits`, feeding it `stdin`.
+    fn run(
+        &self,
+        sandbox: &Sandbox,
+        artifact: &Artifact,
+        stdin: &[u8],
+        limits: &Limits,
+    ) -> std::io::Result<RunOutcome> {
+        let execution = self.execution(artifact, limits);
+        sandbox.execute(
+            &execution.invocation,
+            stdin,
+            &execution.limits,
+            execution.profile.as_ref(),
+        )
+    }
+}
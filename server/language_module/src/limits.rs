@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Resource limits enforced on a single sandboxed run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// CPU time consumed by the program.
+    pub cpu_time: Duration,
+    /// Real time since the program was started.
+    pub wall_time: Duration,
+    /// Memory, in bytes.
+    pub memory: u64,
+    /// Bytes the program may write to stdout.
+    pub output: u64,
+    /// Processes and threads the program may have alive at once.
+    pub processes: u64,
+    /// File descriptors the program may have open at once.
+    pub open_files: u64,
+    /// Whether the program sets aside far more address space than it uses,
+    /// as threaded runtimes and sanitizers do. Without a cgroup, only the
+    /// address space of other programs is capped.
+    pub sparse_address_space: bool,
+}
+
+impl Limits {
+    /// Limits for toolchains building contestant code.
+    pub const COMPILATION: Self = Self {
+        cpu_time: Duration::from_secs(30),
+        wall_time: Duration::from_secs(60),
+        memory: 2048 * MIB,
+        output: 16 * MIB,
+        processes: 32,
+        open_files: 512,
+        sparse_address_space: true,
+    };
+
+    /// Limits for checkers and other programs supplied with a problem.
+    pub const CHECKER: Self = Self {
+        cpu_time: Duration::from_secs(10),
+        wall_time: Duration::from_secs(20),
+        memory: 1024 * MIB,
+        output: 16 * MIB,
+        processes: 1,
+        open_files: 64,
+        sparse_address_space: false,
+    };
+
+    /// Limits for generators and reference solutions producing test data.
+    pub const GENERATOR: Self = Self {
+        cpu_time: Duration::from_secs(60),
+        wall_time: Duration::from_secs(120),
+        memory: 2048 * MIB,
+        output: 1024 * MIB,
+        processes: 1,
+        open_files: 64,
+        sparse_address_space: false,
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            cpu_time: Duration::from_secs(1),
+            wall_time: Duration::from_secs(3),
+            memory: 256 * MIB,
+            output: 64 * MIB,
+            processes: 1,
+            open_files: 64,
+            sparse_address_space: false,
+        }
+    }
+}
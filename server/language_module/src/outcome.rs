@@ -0,0 +1,72 @@
+use std::{fmt, time::Duration};
+
+/// How a sandboxed run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The program exited on its own with the given code.
+    Exited(i32),
+    /// The program was killed by a signal it did not get from the sandbox.
+    Signaled(i32),
+    TimeLimitExceeded,
+    WallTimeLimitExceeded,
+    MemoryLimitExceeded,
+    OutputLimitExceeded,
+    /// The program and its interactor were both waiting on each other.
+    IdlenessLimitExceeded,
+    /// The program made a system call its seccomp profile forbids.
+    SecurityViolation(&'static str),
+}
+
+impl Termination {
+    pub fn success(&self) -> bool {
+        matches!(self, Self::Exited(0))
+    }
+
+    /// Whether the run was stopped for breaching one of its [`Limits`](crate::Limits).
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(
+            self,
+            Self::TimeLimitExceeded
+                | Self::WallTimeLimitExceeded
+                | Self::MemoryLimitExceeded
+                | Self::OutputLimitExceeded
+                | Self::IdlenessLimitExceeded
+        )
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exited(code) => write!(f, "exit code {code}"),
+            Self::Signaled(signal) => write!(f, "signal {signal}"),
+            Self::TimeLimitExceeded => f.write_str("time limit exceeded"),
+            Self::WallTimeLimitExceeded => f.write_str("wall time limit exceeded"),
+            Self::MemoryLimitExceeded => f.write_str("memory limit exceeded"),
+            Self::OutputLimitExceeded => f.write_str("output limit exceeded"),
+            Self::IdlenessLimitExceeded => f.write_str("idleness limit exceeded"),
+            Self::SecurityViolation(syscall) => write!(f, "security violation ({syscall})"),
+        }
+    }
+}
+
+/// Everything observed about a single program run.
+#[derive(Debug, Clone)]
+pub struct RunOutcome {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub status: Termination,
+    pub wall_time: Duration,
+    pub cpu_time: Duration,
+    /// Peak memory usage, in bytes.
+    pub peak_memory: u64,
+}
+
+/// Both sides of an interactive run.
+#[derive(Debug, Clone)]
+pub struct Interaction {
+    /// The contestant's program; its stdout went to the interactor.
+    pub program: RunOutcome,
+    /// The interactor; its stdout went to the program.
+    pub interactor: RunOutcome,
+}
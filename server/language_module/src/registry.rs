@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::LanguageModule;
+
+/// A language module whose toolchain was found on this host.
+#[derive(Clone)]
+pub struct RegisteredLanguage {
+    pub module: Arc<dyn LanguageModule>,
+    /// Version reported by the toolchain when the module was registered.
+    pub version: String,
+}
+
+/// Every language available on this host, in registration order.
+#[derive(Clone, Default)]
+pub struct LanguageRegistry {
+    languages: Vec<RegisteredLanguage>,
+}
+
+impl LanguageRegistry {
+    /// Adds `module` if its toolchain is installed, warning otherwise.
+    pub fn register(&mut self, module: impl LanguageModule + 'static) {
+        if self.get(module.id()).is_some() {
+            eprintln!("Language {} is already registered, skipping", module.id());
+            return;
+        }
+
+        match module.version() {
+            Ok(version) => {
+                println!("Registered language {}: {}", module.id(), version);
+                self.languages.push(RegisteredLanguage {
+                    module: Arc::new(module),
+                    version,
+                });
+            }
+            Err(e) => eprintln!(
+                "Skipping language {}, its toolchain is unavailable: {}",
+                module.id(),
+                e
+            ),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&RegisteredLanguage> {
+        self.languages
+            .iter()
+            .find(|language| language.module.id() == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredLanguage> {
+        self.languages.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty()
+    }
+}
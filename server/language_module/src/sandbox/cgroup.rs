@@ -0,0 +1,105 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::Limits;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Prepares `root` to hold per-run cgroups.
+///
+/// Fails if cgroup v2 is not mounted or the controllers the sandbox relies on
+/// cannot be delegated to `root`.
+pub fn prepare_root(root: &Path) -> io::Result<()> {
+    let mount = Path::new("/sys/fs/cgroup");
+    if !mount.join("cgroup.controllers").exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "cgroup v2 is not mounted at /sys/fs/cgroup",
+        ));
+    }
+
+    fs::create_dir_all(root)?;
+    fs::write(root.join("cgroup.subtree_control"), "+memory +pids +cpu")
+}
+
+/// A cgroup holding exactly one sandboxed run.
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    pub fn create(root: &Path, limits: &Limits) -> io::Result<Self> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = root.join(format!("run-{}-{id}", std::process::id()));
+        fs::create_dir(&path)?;
+
+        let cgroup = Self { path };
+        cgroup.write("memory.max", &limits.memory.to_string())?;
+        cgroup.write("pids.max", &limits.processes.to_string())?;
+        // Not every kernel is built with swap accounting.
+        let _ = cgroup.write("memory.swap.max", "0");
+
+        Ok(cgroup)
+    }
+
+    /// Opens `cgroup.procs` so that a freshly forked child can move itself in.
+    pub fn procs(&self) -> io::Result<File> {
+        OpenOptions::new()
+            .write(true)
+            .open(self.path.join("cgroup.procs"))
+    }
+
+    pub fn oom_killed(&self) -> bool {
+        self.read("memory.events")
+            .and_then(|events| stat_field(&events, "oom_kill"))
+            .is_some_and(|kills| kills > 0)
+    }
+
+    pub fn peak_memory(&self) -> Option<u64> {
+        self.read("memory.peak")?.trim().parse().ok()
+    }
+
+    pub fn cpu_time(&self) -> Option<Duration> {
+        self.read("cpu.stat")
+            .and_then(|stat| stat_field(&stat, "usage_usec"))
+            .map(Duration::from_micros)
+    }
+
+    pub fn kill(&self) {
+        let _ = self.write("cgroup.kill", "1");
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        fs::read_to_string(self.path.join(file)).ok()
+    }
+
+    fn write(&self, file: &str, value: &str) -> io::Result<()> {
+        fs::write(self.path.join(file), value)
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        self.kill();
+        // The kernel removes killed tasks asynchronously.
+        for _ in 0..100 {
+            if fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        eprintln!("Failed to remove cgroup {}", self.path.display());
+    }
+}
+
+fn stat_field(stat: &str, key: &str) -> Option<u64> {
+    stat.lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(name, _)| *name == key)
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
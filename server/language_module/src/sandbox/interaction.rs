@@ -0,0 +1,121 @@
+use std::{
+    io,
+    os::fd::{FromRawFd, OwnedFd},
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{check, Peer, Sandbox, Streams, POLL_INTERVAL};
+use crate::{Execution, Interaction};
+
+impl Sandbox {
+    /// Runs `program` and `interactor` side by side, the stdout of each
+    /// feeding the stdin of the other.
+    ///
+    /// Each side is held to its own limits. If neither uses any CPU time for
+    /// as long as the program's CPU time limit, they are taken to be waiting
+    /// on each other and both end with
+    /// [`Termination::IdlenessLimitExceeded`](crate::Termination::IdlenessLimitExceeded).
+    pub fn interact(&self, program: &Execution, interactor: &Execution) -> io::Result<Interaction> {
+        let (program_stdin, interactor_stdout) = pipe()?;
+        let (interactor_stdin, program_stdout) = pipe()?;
+
+        let pids = [AtomicI32::new(0), AtomicI32::new(0)];
+        let idle = AtomicBool::new(false);
+        let finished = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            scope.spawn(|| watch_idleness(&pids, program.limits.cpu_time, &idle, &finished));
+
+            let interactor = scope.spawn(|| {
+                self.run(
+                    &interactor.invocation,
+                    Streams::Connected {
+                        stdin: interactor_stdin,
+                        stdout: interactor_stdout,
+                    },
+                    &interactor.limits,
+                    interactor.profile.as_ref(),
+                    Some(Peer {
+                        pid: &pids[1],
+                        idle: &idle,
+                    }),
+                )
+            });
+            let program = self.run(
+                &program.invocation,
+                Streams::Connected {
+                    stdin: program_stdin,
+                    stdout: program_stdout,
+                },
+                &program.limits,
+                program.profile.as_ref(),
+                Some(Peer {
+                    pid: &pids[0],
+                    idle: &idle,
+                }),
+            );
+            let interactor = interactor.join().unwrap();
+            finished.store(true, Ordering::SeqCst);
+
+            Ok(Interaction {
+                program: program?,
+                interactor: interactor?,
+            })
+        })
+    }
+}
+
+/// Sets `idle` once the processes in `pids` have both been alive without
+/// using CPU time for `timeout`.
+fn watch_idleness(
+    pids: &[AtomicI32; 2],
+    timeout: Duration,
+    idle: &AtomicBool,
+    finished: &AtomicBool,
+) {
+    let mut last_usage = None;
+    let mut last_progress = Instant::now();
+
+    while !finished.load(Ordering::SeqCst) {
+        thread::sleep(POLL_INTERVAL);
+
+        // Once either side is gone, the other one reads end of file or gets
+        // SIGPIPE instead of blocking forever.
+        let usage = pids
+            .iter()
+            .map(|pid| match pid.load(Ordering::SeqCst) {
+                0 => None,
+                pid => cpu_ticks(pid),
+            })
+            .sum::<Option<u64>>();
+
+        if usage.is_none() || usage != last_usage {
+            last_usage = usage;
+            last_progress = Instant::now();
+        } else if last_progress.elapsed() > timeout {
+            idle.store(true, Ordering::SeqCst);
+            return;
+        }
+    }
+}
+
+/// CPU time used by a live process so far, in clock ticks.
+fn cpu_ticks(pid: libc::pid_t) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The command name may contain anything, fields are counted after it.
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+    let user = fields.next()?.parse::<u64>().ok()?;
+    let system = fields.next()?.parse::<u64>().ok()?;
+    Some(user + system)
+}
+
+/// Creates a pipe, returning its read and write ends.
+fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` is a live local with room for both descriptors.
+    check(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) })?;
+    // SAFETY: both descriptors were just created and are owned by nobody else.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
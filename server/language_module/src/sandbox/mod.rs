@@ -0,0 +1,587 @@
+mod cgroup;
+mod interaction;
+mod rlimit;
+mod seccomp;
+
+use std::{
+    io::{self, Read, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::process::CommandExt,
+    },
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicI64, AtomicU8, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{toolchain, Invocation, Limits, RunOutcome, Termination};
+use cgroup::Cgroup;
+use rlimit::Rlimit;
+pub use seccomp::{syscall_name, SeccompProfile};
+
+const NAMESPACES: libc::c_int = libc::CLONE_NEWNET | libc::CLONE_NEWIPC | libc::CLONE_NEWUTS;
+const STDERR_CAPACITY: u64 = 64 * 1024;
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// `PATH` programs get, whatever the judge's own is.
+const PROGRAM_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+const NOT_KILLED: u8 = 0;
+const KILLED_WALL_TIME: u8 = 1;
+const KILLED_OUTPUT: u8 = 2;
+const KILLED_MEMORY: u8 = 3;
+const KILLED_SECURITY: u8 = 4;
+const KILLED_IDLENESS: u8 = 5;
+
+/// Host-wide settings for running untrusted programs.
+#[derive(Debug, Clone, Default)]
+pub struct Sandbox {
+    /// cgroup v2 directory under which every run gets its own group. Without
+    /// one, memory is watched through `/proc` and process counts through
+    /// rlimits.
+    pub cgroup_root: Option<PathBuf>,
+    /// Detach programs from the host network, IPC and hostname.
+    pub namespaces: bool,
+    /// User and group programs run as, instead of the judge's own.
+    pub user: Option<(u32, u32)>,
+    /// Apply the [`SeccompProfile`] passed to [`Sandbox::execute`].
+    pub seccomp: bool,
+}
+
+impl Sandbox {
+    pub const DEFAULT_CGROUP_ROOT: &'static str = "/sys/fs/cgroup/solve";
+
+    /// Enables every isolation feature the host supports.
+    pub fn detect() -> Self {
+        Self::detect_at(Path::new(Self::DEFAULT_CGROUP_ROOT))
+    }
+
+    /// Like [`Sandbox::detect`], with per-run cgroups under `cgroup_root`.
+    pub fn detect_at(cgroup_root: &Path) -> Self {
+        let cgroup_root = cgroup_root.to_path_buf();
+        let cgroup_root = match cgroup::prepare_root(&cgroup_root) {
+            Ok(()) => Some(cgroup_root),
+            Err(e) => {
+                eprintln!("cgroups are unavailable, falling back to rlimits: {e}");
+                None
+            }
+        };
+
+        let namespaces = namespaces_supported();
+        if !namespaces {
+            eprintln!("namespaces are unavailable, sandboxed programs share the host network");
+        }
+
+        let seccomp = seccomp::supported();
+        if !seccomp {
+            eprintln!("seccomp notifications are unavailable, system calls are not filtered");
+        }
+
+        Self {
+            cgroup_root,
+            namespaces,
+            user: None,
+            seccomp,
+        }
+    }
+
+    /// Creates `workdir` so that sandboxed programs may write to it.
+    pub fn prepare_workdir(&self, workdir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(workdir)?;
+        if let Some((uid, gid)) = self.user {
+            std::os::unix::fs::chown(workdir, Some(uid), Some(gid))?;
+        }
+        Ok(())
+    }
+
+    /// Runs `invocation` under `limits`, feeding it `stdin`. With a `profile`,
+    /// the program is also confined to the system calls it allows.
+    ///
+    /// Breaching a limit or the profile is not an error: it is reported
+    /// through [`RunOutcome::status`].
+    pub fn execute(
+        &self,
+        invocation: &Invocation,
+        stdin: &[u8],
+        limits: &Limits,
+        profile: Option<&SeccompProfile>,
+    ) -> io::Result<RunOutcome> {
+        self.run(invocation, Streams::Buffered(stdin), limits, profile, None)
+    }
+
+    fn run(
+        &self,
+        invocation: &Invocation,
+        streams: Streams,
+        limits: &Limits,
+        profile: Option<&SeccompProfile>,
+        peer: Option<Peer>,
+    ) -> io::Result<RunOutcome> {
+        let cgroup = self
+            .cgroup_root
+            .as_deref()
+            .map(|root| Cgroup::create(root, limits))
+            .transpose()?;
+        let procs = cgroup.as_ref().map(Cgroup::procs).transpose()?;
+
+        // The supervisor end stays with the judge, the program end is only
+        // used between fork and exec.
+        let (supervisor_socket, program_socket) = match profile {
+            Some(_) if self.seccomp => {
+                let (supervisor, program) = socket_pair()?;
+                (Some(supervisor), Some(program))
+            }
+            _ => (None, None),
+        };
+        let filter = match (profile, &program_socket) {
+            (Some(profile), Some(socket)) => Some(profile.filter(socket.as_raw_fd())?),
+            _ => None,
+        };
+
+        let procs_fd = procs.as_ref().map(AsRawFd::as_raw_fd);
+        let mut seccomp = program_socket.as_ref().map(AsRawFd::as_raw_fd).zip(filter);
+        let rlimits = rlimit::rlimits(limits, cgroup.is_some(), self.user.is_some());
+        let namespaces = self.namespaces;
+        let user = self.user;
+
+        let (stdin, stdout, input) = match streams {
+            Streams::Buffered(input) => (Stdio::piped(), Stdio::piped(), Some(input)),
+            Streams::Connected { stdin, stdout } => (stdin.into(), stdout.into(), None),
+        };
+
+        // The judge's environment holds its secrets, so the program starts
+        // from a clean one, and is looked up before it is left behind.
+        let program = Path::new(&invocation.program);
+        let mut command = Command::new(toolchain::find(program).unwrap_or_else(|| program.into()));
+        command
+            .args(&invocation.args)
+            .env_clear()
+            .env("PATH", PROGRAM_PATH)
+            .env("LANG", "C.UTF-8")
+            .env("HOME", &invocation.workdir)
+            .envs(invocation.env.iter().map(|(key, value)| (key, value)))
+            .current_dir(&invocation.workdir)
+            .stdin(stdin)
+            .stdout(stdout)
+            .stderr(Stdio::piped())
+            .process_group(0);
+        // SAFETY: the hook only performs system calls on data prepared
+        // before the fork.
+        unsafe {
+            command
+                .pre_exec(move || confine(procs_fd, namespaces, &rlimits, user, seccomp.as_mut()));
+        }
+
+        let pid = AtomicI32::new(0);
+        let killed = AtomicU8::new(NOT_KILLED);
+        let violation = AtomicI64::new(-1);
+        let finished = AtomicBool::new(false);
+        let kill = |reason| {
+            if killed
+                .compare_exchange(NOT_KILLED, reason, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                // Before the pid is known, it is the spawn below that kills.
+                match pid.load(Ordering::SeqCst) {
+                    0 => {}
+                    child => kill_group(child),
+                }
+            }
+        };
+
+        let (pid, wall_time, stdout, stderr) = thread::scope(|scope| {
+            // The program's first `execve` waits for the supervisor, which
+            // `spawn` in turn waits for, so it has to be running already.
+            let supervisor = supervisor_socket.map(|socket| {
+                scope.spawn(|| {
+                    seccomp::supervise(
+                        socket,
+                        profile.expect("a socket is only created for a profile"),
+                        &invocation.workdir,
+                        &finished,
+                        |syscall| {
+                            let _ = violation.compare_exchange(
+                                -1,
+                                syscall,
+                                Ordering::SeqCst,
+                                Ordering::SeqCst,
+                            );
+                            kill(KILLED_SECURITY);
+                        },
+                    )
+                })
+            });
+
+            let started = Instant::now();
+            let spawned = command.spawn();
+            // The command holds on to connected streams, which would keep the
+            // other end of the pipes from ever seeing them close.
+            drop(command);
+            drop(procs);
+            drop(program_socket);
+            let mut child = match spawned {
+                Ok(child) => child,
+                Err(e) => {
+                    finished.store(true, Ordering::SeqCst);
+                    return Err(e);
+                }
+            };
+
+            let child_pid = child.id() as libc::pid_t;
+            pid.store(child_pid, Ordering::SeqCst);
+            if killed.load(Ordering::SeqCst) != NOT_KILLED {
+                kill_group(child_pid);
+            }
+            if let Some(peer) = &peer {
+                peer.pid.store(child_pid, Ordering::SeqCst);
+            }
+
+            let child_stdin = child.stdin.take();
+            let child_stdout = child.stdout.take();
+            let mut child_stderr = child.stderr.take().expect("stderr is piped");
+            let idle = peer.as_ref().map(|peer| peer.idle);
+            let (done_sender, done) = mpsc::channel();
+
+            if let (Some(mut child_stdin), Some(input)) = (child_stdin, input) {
+                scope.spawn(move || {
+                    // The program may exit without reading all of its input.
+                    let _ = child_stdin.write_all(input);
+                });
+            }
+            let stdout = child_stdout.map(|mut child_stdout| {
+                scope.spawn(move || {
+                    read_capped(&mut child_stdout, limits.output, || kill(KILLED_OUTPUT))
+                })
+            });
+            let stderr =
+                scope.spawn(move || read_capped(&mut child_stderr, STDERR_CAPACITY, || {}));
+            let watch_memory = cgroup.is_none();
+            scope.spawn(move || {
+                watchdog(child_pid, limits, started, watch_memory, idle, done, &kill)
+            });
+
+            let exited = wait_exited(child_pid);
+            let wall_time = started.elapsed();
+            if let Some(peer) = &peer {
+                peer.pid.store(0, Ordering::SeqCst);
+            }
+            let _ = done_sender.send(());
+            finished.store(true, Ordering::SeqCst);
+
+            // Take down anything the program left behind, so the pipes close.
+            kill_group(child_pid);
+            if let Some(cgroup) = &cgroup {
+                cgroup.kill();
+            }
+
+            let stdout = stdout.map_or(Ok(Vec::new()), |stdout| stdout.join().unwrap());
+            let stderr = stderr.join().unwrap();
+            if let Some(supervisor) = supervisor {
+                supervisor.join().unwrap()?;
+            }
+            exited.map(|()| (child_pid, wall_time, stdout, stderr))
+        })?;
+
+        let (raw_status, usage) = wait4(pid)?;
+
+        let cpu_time = cgroup
+            .as_ref()
+            .and_then(Cgroup::cpu_time)
+            .unwrap_or_else(|| timeval(usage.ru_utime) + timeval(usage.ru_stime));
+        let peak_memory = cgroup
+            .as_ref()
+            .and_then(Cgroup::peak_memory)
+            .unwrap_or(usage.ru_maxrss as u64 * 1024);
+        let oom_killed = cgroup.as_ref().is_some_and(Cgroup::oom_killed);
+
+        let status = match killed.load(Ordering::SeqCst) {
+            KILLED_SECURITY => {
+                Termination::SecurityViolation(syscall_name(violation.load(Ordering::SeqCst)))
+            }
+            KILLED_OUTPUT => Termination::OutputLimitExceeded,
+            KILLED_MEMORY => Termination::MemoryLimitExceeded,
+            KILLED_IDLENESS => Termination::IdlenessLimitExceeded,
+            _ if oom_killed || peak_memory > limits.memory => Termination::MemoryLimitExceeded,
+            _ if cpu_time > limits.cpu_time
+                || raw_status == Termination::Signaled(libc::SIGXCPU) =>
+            {
+                Termination::TimeLimitExceeded
+            }
+            KILLED_WALL_TIME => Termination::WallTimeLimitExceeded,
+            _ if raw_status == Termination::Signaled(libc::SIGXFSZ) => {
+                Termination::OutputLimitExceeded
+            }
+            _ => raw_status,
+        };
+
+        Ok(RunOutcome {
+            stdout: stdout?,
+            stderr: stderr?,
+            status,
+            wall_time,
+            cpu_time,
+            peak_memory,
+        })
+    }
+}
+
+/// Where the standard streams of a run lead.
+enum Streams<'a> {
+    /// stdin is fed from memory and stdout is collected.
+    Buffered(&'a [u8]),
+    /// Both are ends of pipes to another process; stdout is not collected.
+    Connected { stdin: OwnedFd, stdout: OwnedFd },
+}
+
+/// Ties a run to another one it communicates with.
+struct Peer<'a> {
+    /// Set to the process id while the program is alive, 0 otherwise.
+    pid: &'a AtomicI32,
+    /// Set once the runs are found waiting on each other.
+    idle: &'a AtomicBool,
+}
+
+/// Restricts the freshly forked child before it executes the program.
+fn confine(
+    procs_fd: Option<RawFd>,
+    namespaces: bool,
+    rlimits: &[Rlimit],
+    user: Option<(u32, u32)>,
+    seccomp: Option<&mut (RawFd, Vec<libc::sock_filter>)>,
+) -> io::Result<()> {
+    if let Some(fd) = procs_fd {
+        // Writing "0" moves the writing process itself.
+        // SAFETY: the buffer outlives the call.
+        if unsafe { libc::write(fd, b"0".as_ptr().cast(), 1) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    if namespaces {
+        // SAFETY: plain system call.
+        check(unsafe { libc::unshare(NAMESPACES) })?;
+    }
+
+    for rlimit in rlimits {
+        rlimit.apply()?;
+    }
+
+    if let Some((uid, gid)) = user {
+        // SAFETY: plain system calls.
+        unsafe {
+            check(libc::setgroups(0, std::ptr::null()))?;
+            check(libc::setgid(gid))?;
+            check(libc::setuid(uid))?;
+        }
+    }
+
+    // Installed last, so that the calls above need not be allowed.
+    if let Some((socket, filter)) = seccomp {
+        seccomp::install(filter, *socket)?;
+    }
+
+    Ok(())
+}
+
+/// Reads `reader` to the end, keeping at most `capacity` bytes.
+///
+/// `on_overflow` is called once as soon as the capacity is exceeded; the rest
+/// is drained so that the writer never blocks on a full pipe.
+fn read_capped(
+    reader: &mut impl Read,
+    capacity: u64,
+    on_overflow: impl FnOnce(),
+) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    reader.take(capacity).read_to_end(&mut buffer)?;
+
+    if reader.read(&mut [0])? > 0 {
+        on_overflow();
+        io::copy(reader, &mut io::sink())?;
+    }
+
+    Ok(buffer)
+}
+
+fn watchdog(
+    pid: libc::pid_t,
+    limits: &Limits,
+    started: Instant,
+    watch_memory: bool,
+    idle: Option<&AtomicBool>,
+    done: mpsc::Receiver<()>,
+    kill: &(dyn Fn(u8) + Sync),
+) {
+    while let Err(RecvTimeoutError::Timeout) = done.recv_timeout(POLL_INTERVAL) {
+        if idle.is_some_and(|idle| idle.load(Ordering::SeqCst)) {
+            return kill(KILLED_IDLENESS);
+        }
+        if started.elapsed() > limits.wall_time {
+            return kill(KILLED_WALL_TIME);
+        }
+        if watch_memory && resident_peak(pid).is_some_and(|peak| peak > limits.memory) {
+            return kill(KILLED_MEMORY);
+        }
+    }
+}
+
+/// Peak resident set size of a live process, in bytes.
+fn resident_peak(pid: libc::pid_t) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn kill_group(pid: libc::pid_t) {
+    // SAFETY: plain system call; the group leader is not reaped yet, so the
+    // group id cannot have been reused.
+    unsafe { libc::kill(-pid, libc::SIGKILL) };
+}
+
+/// Waits for `pid` to exit without reaping it.
+fn wait_exited(pid: libc::pid_t) -> io::Result<()> {
+    loop {
+        // SAFETY: siginfo_t is plain old data, zeroed is a valid value.
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        // SAFETY: `info` is a live local.
+        let result = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if result == 0 {
+            return Ok(());
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// Reaps `pid`, returning how it ended and what it consumed.
+fn wait4(pid: libc::pid_t) -> io::Result<(Termination, libc::rusage)> {
+    let mut status = 0;
+    // SAFETY: rusage is plain old data, zeroed is a valid value.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    loop {
+        // SAFETY: both pointers refer to live locals.
+        let result = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+        if result == pid {
+            break;
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+
+    let status = if libc::WIFSIGNALED(status) {
+        Termination::Signaled(libc::WTERMSIG(status))
+    } else {
+        Termination::Exited(libc::WEXITSTATUS(status))
+    };
+
+    Ok((status, usage))
+}
+
+fn namespaces_supported() -> bool {
+    // SAFETY: the child only makes system calls before exiting.
+    match unsafe { libc::fork() } {
+        -1 => false,
+        0 => unsafe { libc::_exit(if libc::unshare(NAMESPACES) == 0 { 0 } else { 1 }) },
+        pid => wait4(pid).is_ok_and(|(status, _)| status.success()),
+    }
+}
+
+fn socket_pair() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` is a live local with room for both descriptors.
+    check(unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+            0,
+            fds.as_mut_ptr(),
+        )
+    })?;
+    // SAFETY: both descriptors were just created and are owned by nobody else.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+fn check(result: libc::c_int) -> io::Result<()> {
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn timeval(tv: libc::timeval) -> Duration {
+    Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn programs_do_not_inherit_the_judge_environment() {
+        std::env::set_var("SOLVE_SANDBOX_TEST_SECRET", "secret");
+        let workdir = std::env::temp_dir();
+        let invocation = Invocation::new("sh", &workdir)
+            .args([
+                "-c",
+                "echo \"$SOLVE_SANDBOX_TEST_SECRET|$GIVEN|$HOME|$PATH\"",
+            ])
+            .env("GIVEN", "given");
+
+        let outcome = Sandbox::default()
+            .execute(&invocation, &[], &Limits::default(), None)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(outcome.stdout).unwrap(),
+            format!("|given|{}|{PROGRAM_PATH}\n", workdir.display())
+        );
+    }
+
+    #[test]
+    fn only_compilers_start_processes() {
+        if !seccomp::supported() {
+            return;
+        }
+        let workdir = std::env::temp_dir();
+        let invocation = Invocation::new("sh", &workdir).args(["-c", "(exit 3); echo $?"]);
+        let sandbox = Sandbox {
+            seccomp: true,
+            ..Sandbox::default()
+        };
+        let run = |profile: SeccompProfile| {
+            sandbox
+                .execute(&invocation, &[], &Limits::COMPILATION, Some(&profile))
+                .unwrap()
+        };
+
+        let threaded = run(SeccompProfile::native().threaded().allow(&["getppid"]));
+        assert_eq!(threaded.status, Termination::SecurityViolation("clone"));
+
+        let compiler = run(SeccompProfile::compilation());
+        assert_eq!(compiler.status, Termination::Exited(0));
+        assert_eq!(compiler.stdout, b"3\n");
+    }
+}
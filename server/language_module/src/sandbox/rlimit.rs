@@ -0,0 +1,86 @@
+use std::io;
+
+use crate::Limits;
+
+const ADDRESS_SPACE_SLACK: u64 = 64 * 1024 * 1024;
+
+#[cfg(target_env = "gnu")]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(target_env = "gnu"))]
+type Resource = libc::c_int;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rlimit {
+    resource: Resource,
+    soft: libc::rlim_t,
+    hard: libc::rlim_t,
+}
+
+impl Rlimit {
+    fn new(resource: Resource, value: u64) -> Self {
+        Self {
+            resource,
+            soft: value,
+            hard: value,
+        }
+    }
+
+    /// Applies the limit to the calling process.
+    ///
+    /// Only performs a system call, so it is safe to use between `fork` and
+    /// `exec`.
+    pub fn apply(&self) -> io::Result<()> {
+        let limit = libc::rlimit {
+            rlim_cur: self.soft,
+            rlim_max: self.hard,
+        };
+        // SAFETY: `limit` is a valid rlimit for the duration of the call.
+        if unsafe { libc::setrlimit(self.resource, &limit) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// Translates `limits` into rlimits.
+///
+/// Memory and process counts are better enforced by a cgroup; the rlimit
+/// fallbacks are only used when `cgroup` is false. Without a cgroup the exact
+/// memory limit is watched through `/proc`, and the address space is only
+/// capped well above it to protect the host, unless the program is known to
+/// reserve much more than it uses. `RLIMIT_NPROC` is counted per user, so it
+/// is only meaningful when the program runs as a dedicated user.
+pub fn rlimits(limits: &Limits, cgroup: bool, dedicated_user: bool) -> Vec<Rlimit> {
+    // The kernel only checks CPU time once a second; the sandbox compares
+    // the exact figure afterwards, this is just the hard stop.
+    let cpu_seconds = limits.cpu_time.as_secs() + 1;
+
+    let mut rlimits = vec![
+        Rlimit {
+            resource: libc::RLIMIT_CPU,
+            soft: cpu_seconds,
+            hard: cpu_seconds + 1,
+        },
+        Rlimit::new(libc::RLIMIT_STACK, limits.memory),
+        Rlimit::new(libc::RLIMIT_FSIZE, limits.output),
+        Rlimit::new(libc::RLIMIT_NOFILE, limits.open_files),
+        Rlimit::new(libc::RLIMIT_CORE, 0),
+    ];
+
+    // Some programs reserve far more address space than they use: threads
+    // get stacks as large as `RLIMIT_STACK`, the JVM and V8 set their heaps
+    // aside up front and ASan its shadow memory. Only the watcher holds them
+    // to the limit.
+    if !cgroup && !limits.sparse_address_space {
+        let address_space = limits
+            .memory
+            .saturating_mul(2)
+            .saturating_add(ADDRESS_SPACE_SLACK);
+        rlimits.push(Rlimit::new(libc::RLIMIT_AS, address_space));
+    }
+    if !cgroup && dedicated_user {
+        rlimits.push(Rlimit::new(libc::RLIMIT_NPROC, limits.processes));
+    }
+
+    rlimits
+}
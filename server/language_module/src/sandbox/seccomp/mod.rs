@@ -0,0 +1,456 @@
+mod supervisor;
+#[cfg(target_arch = "x86_64")]
+mod syscalls_x86_64;
+
+use std::{io, os::fd::RawFd, path::PathBuf};
+
+use libc::{sock_filter, sock_fprog};
+
+pub use supervisor::supervise;
+#[cfg(target_arch = "x86_64")]
+use syscalls_x86_64::SYSCALLS;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+
+// Other architectures have no system call table yet, so seccomp is reported
+// as unsupported there.
+#[cfg(not(target_arch = "x86_64"))]
+const SYSCALLS: &[(i64, &str)] = &[];
+#[cfg(not(target_arch = "x86_64"))]
+const AUDIT_ARCH: u32 = 0;
+
+const SECCOMP_SET_MODE_FILTER: libc::c_uint = 1;
+const SECCOMP_FILTER_FLAG_NEW_LISTENER: libc::c_ulong = 1 << 3;
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+// Offsets into `struct seccomp_data`.
+const DATA_NR: u32 = 0;
+const DATA_ARCH: u32 = 4;
+const DATA_ARG0: u32 = 16;
+
+const LOAD: u16 = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+const JUMP_EQ: u16 = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+const JUMP_GE: u16 = (libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K) as u16;
+const JUMP_SET: u16 = (libc::BPF_JMP | libc::BPF_JSET | libc::BPF_K) as u16;
+const RETURN: u16 = (libc::BPF_RET | libc::BPF_K) as u16;
+
+/// Stands for the pid of the program in a filter, which is only known once
+/// it is installed.
+const OWN_PID: u32 = u32::MAX;
+
+/// System calls every dynamically linked native program makes. Starting the
+/// program is let through by the supervisor; `prlimit64` and `tgkill` only
+/// reach the program itself.
+const NATIVE_SYSCALLS: &[&str] = &[
+    "read",
+    "write",
+    "readv",
+    "writev",
+    "pread64",
+    "pwrite64",
+    "lseek",
+    "close",
+    "fstat",
+    "newfstatat",
+    "stat",
+    "lstat",
+    "statx",
+    "access",
+    "faccessat",
+    "faccessat2",
+    "readlink",
+    "readlinkat",
+    "ioctl",
+    "fcntl",
+    "dup",
+    "dup2",
+    "dup3",
+    "getcwd",
+    "brk",
+    "mmap",
+    "munmap",
+    "mremap",
+    "mprotect",
+    "madvise",
+    "arch_prctl",
+    "set_tid_address",
+    "set_robust_list",
+    "rseq",
+    "prlimit64",
+    "getrlimit",
+    "getrandom",
+    "futex",
+    "rt_sigaction",
+    "rt_sigprocmask",
+    "rt_sigreturn",
+    "sigaltstack",
+    "tgkill",
+    "clock_gettime",
+    "clock_getres",
+    "clock_nanosleep",
+    "nanosleep",
+    "gettimeofday",
+    "time",
+    "getpid",
+    "gettid",
+    "getuid",
+    "geteuid",
+    "getgid",
+    "getegid",
+    "uname",
+    "sysinfo",
+    "getrusage",
+    "times",
+    "sched_yield",
+    "sched_getaffinity",
+    "exit",
+    "exit_group",
+];
+
+/// System calls runtimes with threads of their own, such as the JVM, Go and
+/// Node.js, make on top of a native program: starting threads and waiting
+/// for them. `clone` only starts threads unless the profile allows
+/// [`processes`](SeccompProfile::processes), since without a cgroup or a
+/// dedicated user nothing else bounds how many there are; `clone3` fails
+/// with `ENOSYS`, which makes the C library fall back to `clone`.
+///
+/// Another thread may rewrite a path while the supervisor checks it, which
+/// is safe only because the supervisor then opens or changes the file it
+/// checked itself, rather than letting the call go on.
+const THREADED_SYSCALLS: &[&str] = &[
+    "clone",
+    "clone3",
+    "sched_setaffinity",
+    "membarrier",
+    "prctl",
+    "epoll_create1",
+    "epoll_ctl",
+    "epoll_wait",
+    "epoll_pwait",
+    "eventfd2",
+    "pipe2",
+    "poll",
+    "ppoll",
+];
+
+/// System calls compilers make on top of a threaded program: starting the
+/// tools they drive, and talking to them and to a jobserver. They also
+/// clean up after them, through [`FILE_CHANGING_SYSCALLS`].
+const COMPILATION_SYSCALLS: &[&str] = &[
+    "execve",
+    "vfork",
+    "wait4",
+    "waitid",
+    "umask",
+    "getdents64",
+    "ftruncate",
+    "fchmod",
+    "socketpair",
+    "recvfrom",
+    "getppid",
+    "getpgrp",
+];
+
+/// System calls changing files by path, which are never let through: the
+/// supervisor makes them for the program, on the same paths it may open for
+/// writing.
+const FILE_CHANGING_SYSCALLS: &[&str] = &[
+    "unlink",
+    "unlinkat",
+    "rmdir",
+    "mkdir",
+    "mkdirat",
+    "rename",
+    "renameat",
+    "renameat2",
+    "chmod",
+    "fchmodat",
+];
+
+/// Directories and files every dynamically linked native program reads.
+const NATIVE_READABLE_PATHS: &[&str] = &[
+    "/lib",
+    "/lib64",
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/local/lib",
+    "/etc/ld.so.cache",
+    "/etc/localtime",
+    "/usr/share/zoneinfo",
+    "/dev/null",
+    "/dev/urandom",
+];
+
+/// Headers compilers search on top of what a native program reads; their
+/// own tools live under `/usr/lib` or `/usr/libexec`.
+const COMPILATION_READABLE_PATHS: &[&str] = &["/usr/include", "/usr/local/include", "/usr/libexec"];
+
+/// What a sandboxed program is allowed to do once it is running.
+///
+/// System calls outside of [`syscalls`](Self::syscalls) end the run with a
+/// security violation, except for [`denied`](Self::denied) ones, which fail
+/// with `EACCES`. Opening files is checked separately: the program may
+/// open anything inside its workdir and [`writable_paths`](Self::writable_paths)
+/// and read anything under [`readable_paths`](Self::readable_paths); other
+/// opens fail with `EACCES`. Allowed system calls removing, renaming or
+/// creating directories and changing modes only work on writable paths.
+#[derive(Debug, Clone)]
+pub struct SeccompProfile {
+    pub syscalls: Vec<&'static str>,
+    /// System calls runtimes try at startup but do fine without, such as
+    /// looking up the user through a socket.
+    pub denied: Vec<&'static str>,
+    pub readable_paths: Vec<PathBuf>,
+    /// Directories besides the workdir the program may write to, such as a
+    /// build cache shared between compilations.
+    pub writable_paths: Vec<PathBuf>,
+    /// Whether `clone` may start processes rather than only threads, for
+    /// compilers driving their own tools.
+    pub processes: bool,
+}
+
+impl SeccompProfile {
+    /// The profile of a single-threaded, dynamically linked native program.
+    pub fn native() -> Self {
+        Self {
+            syscalls: NATIVE_SYSCALLS.to_vec(),
+            denied: Vec::new(),
+            readable_paths: NATIVE_READABLE_PATHS.iter().map(PathBuf::from).collect(),
+            writable_paths: Vec::new(),
+            processes: false,
+        }
+    }
+
+    /// The profile of a compiler driving its own tools, such as `g++`
+    /// running `cc1plus`, `as` and `ld`. They write their temporary files to
+    /// `TMPDIR`, which has to be the workdir.
+    pub fn compilation() -> Self {
+        let profile = Self {
+            processes: true,
+            ..Self::native()
+                .threaded()
+                .allow(COMPILATION_SYSCALLS)
+                .allow(FILE_CHANGING_SYSCALLS)
+        };
+        COMPILATION_READABLE_PATHS
+            .iter()
+            .fold(profile, |profile, path| profile.allow_reading(path))
+    }
+
+    /// Also lets the program start threads, for multi-threaded runtimes.
+    pub fn threaded(self) -> Self {
+        self.allow(THREADED_SYSCALLS)
+    }
+
+    pub fn allow(mut self, syscalls: &[&'static str]) -> Self {
+        self.syscalls.extend_from_slice(syscalls);
+        self
+    }
+
+    pub fn deny(mut self, syscalls: &[&'static str]) -> Self {
+        self.denied.extend_from_slice(syscalls);
+        self
+    }
+
+    pub fn allow_reading(mut self, path: impl Into<PathBuf>) -> Self {
+        self.readable_paths.push(path.into());
+        self
+    }
+
+    pub fn allow_writing(mut self, path: impl Into<PathBuf>) -> Self {
+        self.writable_paths.push(path.into());
+        self
+    }
+
+    /// Compiles the profile into a BPF program.
+    ///
+    /// Everything not allowed outright is forwarded to the supervisor,
+    /// including allowed system calls that change files by path. The
+    /// only exception is `sendmsg` on `socket`, which the sandbox uses to
+    /// hand the supervisor its listener before the program starts.
+    pub(crate) fn filter(&self, socket: RawFd) -> io::Result<Vec<sock_filter>> {
+        let mut program = vec![
+            statement(LOAD, DATA_ARCH),
+            jump(JUMP_EQ, AUDIT_ARCH, 1, 0),
+            statement(RETURN, SECCOMP_RET_KILL_PROCESS),
+            statement(LOAD, DATA_NR),
+            jump(JUMP_GE, X32_SYSCALL_BIT, 0, 1),
+            statement(RETURN, SECCOMP_RET_KILL_PROCESS),
+        ];
+
+        let number = |name: &str| {
+            syscall_number(name)
+                .map(|number| number as u32)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("unknown system call {name}"),
+                    )
+                })
+        };
+        for name in &self.syscalls {
+            if !FILE_CHANGING_SYSCALLS.contains(name) {
+                program.extend(allow(name, number(name)?, self.processes));
+            }
+        }
+        for name in &self.denied {
+            program.extend([
+                jump(JUMP_EQ, number(name)?, 0, 1),
+                statement(RETURN, SECCOMP_RET_ERRNO | libc::EACCES as u32),
+            ]);
+        }
+
+        program.extend([
+            jump(JUMP_EQ, libc::SYS_sendmsg as u32, 0, 3),
+            statement(LOAD, DATA_ARG0),
+            jump(JUMP_EQ, socket as u32, 0, 1),
+            statement(RETURN, SECCOMP_RET_ALLOW),
+            statement(RETURN, SECCOMP_RET_USER_NOTIF),
+        ]);
+
+        Ok(program)
+    }
+}
+
+/// Lets the system call `name` through, as long as it only reaches the
+/// program itself, and only starts threads unless `processes` are allowed.
+fn allow(name: &str, number: u32, processes: bool) -> Vec<sock_filter> {
+    // Fails the call unless its first argument is `value`.
+    let only_with_first = |value| {
+        vec![
+            jump(JUMP_EQ, number, 0, 4),
+            statement(LOAD, DATA_ARG0),
+            jump(JUMP_EQ, value, 0, 1),
+            statement(RETURN, SECCOMP_RET_ALLOW),
+            statement(RETURN, SECCOMP_RET_ERRNO | libc::EPERM as u32),
+        ]
+    };
+
+    match name {
+        // The program runs as the judge's user, so both could otherwise
+        // reach the judge: pid 0 is the caller itself.
+        "prlimit64" => only_with_first(0),
+        "tgkill" => only_with_first(OWN_PID),
+        // Anything but a thread is forwarded, and so a violation.
+        "clone" if !processes => vec![
+            jump(JUMP_EQ, number, 0, 4),
+            statement(LOAD, DATA_ARG0),
+            jump(JUMP_SET, libc::CLONE_THREAD as u32, 0, 1),
+            statement(RETURN, SECCOMP_RET_ALLOW),
+            statement(RETURN, SECCOMP_RET_USER_NOTIF),
+        ],
+        // Its flags live in memory, out of the filter's reach.
+        "clone3" if !processes => vec![
+            jump(JUMP_EQ, number, 0, 1),
+            statement(RETURN, SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+        ],
+        _ => vec![
+            jump(JUMP_EQ, number, 0, 1),
+            statement(RETURN, SECCOMP_RET_ALLOW),
+        ],
+    }
+}
+
+/// Name of the system call with the given number on this architecture.
+pub fn syscall_name(number: i64) -> &'static str {
+    SYSCALLS
+        .binary_search_by_key(&number, |(number, _)| *number)
+        .map(|index| SYSCALLS[index].1)
+        .unwrap_or("unknown")
+}
+
+fn syscall_number(name: &str) -> Option<i64> {
+    SYSCALLS
+        .iter()
+        .find(|(_, candidate)| *candidate == name)
+        .map(|(number, _)| *number)
+}
+
+/// Installs `program` on the calling process and sends the notification
+/// listener over `socket`.
+///
+/// Only performs system calls, so it is safe to use between `fork` and
+/// `exec`.
+pub(crate) fn install(program: &mut [sock_filter], socket: RawFd) -> io::Result<()> {
+    // SAFETY: plain system call.
+    let pid = unsafe { libc::getpid() } as u32;
+    for instruction in program.iter_mut() {
+        if instruction.code == JUMP_EQ && instruction.k == OWN_PID {
+            instruction.k = pid;
+        }
+    }
+
+    let program = sock_fprog {
+        len: program.len() as libc::c_ushort,
+        filter: program.as_ptr() as *mut sock_filter,
+    };
+
+    // SAFETY: plain system calls; `program` outlives them.
+    let listener = unsafe {
+        super::check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+        libc::syscall(
+            libc::SYS_seccomp,
+            SECCOMP_SET_MODE_FILTER,
+            SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &program,
+        )
+    };
+    if listener < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    supervisor::send_listener(socket, listener as RawFd)
+}
+
+/// Checks whether the kernel supports notifying a supervisor and letting it
+/// answer with a descriptor, which Linux does since 5.14.
+pub(crate) fn supported() -> bool {
+    if SYSCALLS.is_empty() {
+        return false;
+    }
+
+    let program = [statement(RETURN, SECCOMP_RET_ALLOW)];
+    let program = sock_fprog {
+        len: 1,
+        filter: program.as_ptr() as *mut sock_filter,
+    };
+
+    // SAFETY: the child only makes system calls before exiting.
+    match unsafe { libc::fork() } {
+        -1 => false,
+        0 => unsafe {
+            let installed = libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == 0;
+            let listener = if installed {
+                libc::syscall(
+                    libc::SYS_seccomp,
+                    SECCOMP_SET_MODE_FILTER,
+                    SECCOMP_FILTER_FLAG_NEW_LISTENER,
+                    &program,
+                )
+            } else {
+                -1
+            };
+            let supported = listener >= 0 && supervisor::can_send_fd(listener as RawFd);
+            libc::_exit(if supported { 0 } else { 1 })
+        },
+        pid => super::wait4(pid).is_ok_and(|(status, _)| status.success()),
+    }
+}
+
+fn statement(code: u16, k: u32) -> sock_filter {
+    sock_filter {
+        code,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+fn jump(code: u16, k: u32, jt: u8, jf: u8) -> sock_filter {
+    sock_filter { code, jt, jf, k }
+}
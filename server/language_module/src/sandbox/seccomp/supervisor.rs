@@ -0,0 +1,542 @@
+use std::{
+    ffi::{CString, OsString},
+    fs::File,
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::{
+            ffi::{OsStrExt, OsStringExt},
+            fs::{FileExt, PermissionsExt},
+        },
+    },
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::{SeccompProfile, FILE_CHANGING_SYSCALLS};
+use crate::sandbox::check;
+
+const SECCOMP_IOCTL_NOTIF_RECV: u64 = 0xc050_2100;
+const SECCOMP_IOCTL_NOTIF_SEND: u64 = 0xc018_2101;
+const SECCOMP_IOCTL_NOTIF_ADDFD: u64 = 0x4018_2103;
+const SECCOMP_ADDFD_FLAG_SEND: u32 = 1 << 1;
+const SECCOMP_USER_NOTIF_FLAG_CONTINUE: u32 = 1;
+
+const RESOLVE_NO_MAGICLINKS: u64 = 0x02;
+const RESOLVE_NO_SYMLINKS: u64 = 0x04;
+
+const POLL_TIMEOUT_MS: libc::c_int = 10;
+/// Paths are read in chunks that never cross this boundary, since the next
+/// page may not be mapped.
+const PAGE_SIZE: u64 = 4096;
+
+/// `struct seccomp_notif`
+#[repr(C)]
+struct Notification {
+    id: u64,
+    pid: u32,
+    flags: u32,
+    data: libc::seccomp_data,
+}
+
+/// `struct seccomp_notif_resp`
+#[repr(C)]
+struct Response {
+    id: u64,
+    val: i64,
+    error: i32,
+    flags: u32,
+}
+
+/// `struct seccomp_notif_addfd`
+#[repr(C)]
+struct AddFd {
+    id: u64,
+    flags: u32,
+    srcfd: u32,
+    newfd: u32,
+    newfd_flags: u32,
+}
+
+/// `struct open_how`
+#[repr(C)]
+struct OpenHow {
+    flags: u64,
+    mode: u64,
+    resolve: u64,
+}
+
+/// Answers the system calls a sandboxed program's filter forwards.
+///
+/// A permitted file is opened by the supervisor and its descriptor handed to
+/// the program as the result of its call; any other open fails with
+/// `EACCES`. Allowed changes to files by path are made by the supervisor
+/// too. The first `execve`, which starts the program, goes on; every other
+/// forwarded system call is a violation: its number is
+/// passed to `on_violation`, which is expected to kill the program.
+pub fn supervise(
+    socket: OwnedFd,
+    profile: &SeccompProfile,
+    workdir: &Path,
+    finished: &AtomicBool,
+    on_violation: impl Fn(i64),
+) -> io::Result<()> {
+    let Some(listener) = receive_listener(&socket)? else {
+        // The program never got as far as installing its filter.
+        return Ok(());
+    };
+
+    let paths = AllowedPaths::new(profile, workdir);
+    let changes: Vec<i64> = profile
+        .syscalls
+        .iter()
+        .filter(|name| FILE_CHANGING_SYSCALLS.contains(name))
+        .filter_map(|name| super::syscall_number(name))
+        .collect();
+    let mut started = false;
+
+    while !finished.load(Ordering::SeqCst) {
+        let mut poll = libc::pollfd {
+            fd: listener.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `poll` is a live local.
+        if unsafe { libc::poll(&mut poll, 1, POLL_TIMEOUT_MS) } < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+
+        if poll.revents & libc::POLLIN != 0 {
+            // The program may have died before the notification was read.
+            if let Ok(notification) = receive(&listener) {
+                respond(
+                    &listener,
+                    &notification,
+                    &paths,
+                    &changes,
+                    &mut started,
+                    &on_violation,
+                );
+            }
+        } else if poll.revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn respond(
+    listener: &OwnedFd,
+    notification: &Notification,
+    paths: &AllowedPaths,
+    changes: &[i64],
+    started: &mut bool,
+    on_violation: &impl Fn(i64),
+) {
+    let number = i64::from(notification.data.nr);
+    let args = notification.data.args;
+
+    let open = match number {
+        libc::SYS_open => Some((libc::AT_FDCWD as u64, args[0], args[1], args[2])),
+        libc::SYS_openat => Some((args[0], args[1], args[2], args[3])),
+        _ => None,
+    };
+
+    let mut response = Response {
+        id: notification.id,
+        val: 0,
+        error: -libc::EPERM,
+        flags: 0,
+    };
+
+    match open {
+        Some((dirfd, path, flags, mode)) => {
+            let flags = flags as libc::c_int;
+            let path = read_path(notification.pid, path)
+                .and_then(|path| resolve(notification.pid, dirfd as i32, &path))
+                .filter(|path| paths.permit(path, flags));
+
+            // The program can change the path in its memory once it has been
+            // read, so the file checked is the one opened, here, and not by
+            // letting the call go on.
+            match path.map(|path| open_resolved(&path, flags, mode)) {
+                Some(Ok(file)) => {
+                    // This answers the call. Failing means the program is
+                    // gone and there is nobody left to answer.
+                    let _ = send_fd(listener, notification.id, &file, flags);
+                    return;
+                }
+                Some(Err(e)) => response.error = -e.raw_os_error().unwrap_or(libc::EACCES),
+                None => response.error = -libc::EACCES,
+            }
+        }
+        None if changes.contains(&number) => {
+            response.error = match change(notification.pid, number, &args, paths) {
+                Ok(()) => 0,
+                Err(e) => -e.raw_os_error().unwrap_or(libc::EACCES),
+            };
+        }
+        // The sandbox starts the program itself, before any of its code
+        // runs; whatever it executes after that is its own doing.
+        None if number == libc::SYS_execve && !*started => {
+            *started = true;
+            response.error = 0;
+            response.flags = SECCOMP_USER_NOTIF_FLAG_CONTINUE;
+        }
+        None => on_violation(number),
+    }
+
+    // SAFETY: `response` is a live local of the layout the kernel expects.
+    unsafe {
+        libc::ioctl(
+            listener.as_raw_fd(),
+            SECCOMP_IOCTL_NOTIF_SEND as _,
+            &mut response,
+        )
+    };
+}
+
+/// Opens `path` on behalf of the program. It is resolved already, so any
+/// symlink found on the way was put there since and is refused.
+fn open_resolved(path: &Path, flags: libc::c_int, mode: u64) -> io::Result<OwnedFd> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let creates = flags & libc::O_CREAT != 0 || flags & libc::O_TMPFILE == libc::O_TMPFILE;
+    let how = OpenHow {
+        flags: (flags | libc::O_CLOEXEC) as u32 as u64,
+        mode: if creates { mode & 0o7777 } else { 0 },
+        resolve: RESOLVE_NO_SYMLINKS | RESOLVE_NO_MAGICLINKS,
+    };
+
+    // SAFETY: `path` and `how` are live locals of the layout the kernel
+    // expects.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_openat2,
+            libc::AT_FDCWD,
+            path.as_ptr(),
+            &how,
+            std::mem::size_of::<OpenHow>(),
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the descriptor was just opened and is owned by nobody else.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+/// Makes a change to files the program asked for, on the paths it names as
+/// resolved when checked.
+fn change(pid: u32, number: i64, args: &[u64; 6], paths: &AllowedPaths) -> io::Result<()> {
+    let path = |dirfd: u64, address: u64| {
+        read_path(pid, address)
+            .and_then(|path| resolve(pid, dirfd as RawFd, &path))
+            .filter(|path| paths.permit_change(path))
+            .ok_or_else(|| io::Error::from_raw_os_error(libc::EACCES))
+    };
+    let cwd = libc::AT_FDCWD as u64;
+
+    match number {
+        libc::SYS_unlink => unlink(&path(cwd, args[0])?, 0),
+        libc::SYS_unlinkat => unlink(&path(args[0], args[1])?, args[2] as libc::c_int),
+        libc::SYS_rmdir => unlink(&path(cwd, args[0])?, libc::AT_REMOVEDIR),
+        libc::SYS_mkdir => mkdir(&path(cwd, args[0])?, args[1]),
+        libc::SYS_mkdirat => mkdir(&path(args[0], args[1])?, args[2]),
+        libc::SYS_rename => rename(&path(cwd, args[0])?, &path(cwd, args[1])?, 0),
+        libc::SYS_renameat => rename(&path(args[0], args[1])?, &path(args[2], args[3])?, 0),
+        libc::SYS_renameat2 => rename(
+            &path(args[0], args[1])?,
+            &path(args[2], args[3])?,
+            args[4] as libc::c_uint,
+        ),
+        libc::SYS_chmod => chmod(&path(cwd, args[0])?, args[1]),
+        libc::SYS_fchmodat => chmod(&path(args[0], args[1])?, args[2]),
+        _ => Err(io::Error::from_raw_os_error(libc::EACCES)),
+    }
+}
+
+/// The directory `path` is in, opened without following symlinks, and the
+/// name of `path` in it.
+fn parent(path: &Path) -> io::Result<(OwnedFd, CString)> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(io::Error::from_raw_os_error(libc::EINVAL));
+    };
+    let dir = open_resolved(dir, libc::O_PATH | libc::O_DIRECTORY, 0)?;
+    Ok((dir, CString::new(name.as_bytes())?))
+}
+
+fn unlink(path: &Path, flags: libc::c_int) -> io::Result<()> {
+    let (dir, name) = parent(path)?;
+    // SAFETY: `name` is a live local.
+    check(unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), flags & libc::AT_REMOVEDIR) })
+}
+
+fn mkdir(path: &Path, mode: u64) -> io::Result<()> {
+    let (dir, name) = parent(path)?;
+    // SAFETY: `name` is a live local.
+    check(unsafe { libc::mkdirat(dir.as_raw_fd(), name.as_ptr(), (mode & 0o7777) as _) })
+}
+
+fn rename(from: &Path, to: &Path, flags: libc::c_uint) -> io::Result<()> {
+    let (from_dir, from_name) = parent(from)?;
+    let (to_dir, to_name) = parent(to)?;
+    // SAFETY: the names are live locals.
+    check(unsafe {
+        libc::syscall(
+            libc::SYS_renameat2,
+            from_dir.as_raw_fd(),
+            from_name.as_ptr(),
+            to_dir.as_raw_fd(),
+            to_name.as_ptr(),
+            flags,
+        ) as libc::c_int
+    })
+}
+
+fn chmod(path: &Path, mode: u64) -> io::Result<()> {
+    let file = open_resolved(path, libc::O_PATH, 0)?;
+    // Files opened only as a path can still be changed through /proc.
+    std::fs::set_permissions(
+        format!("/proc/self/fd/{}", file.as_raw_fd()),
+        std::fs::Permissions::from_mode((mode & 0o7777) as u32),
+    )
+}
+
+/// Installs a copy of `file` in the program and answers the call with it.
+/// `flags` are those of the call, of which only `O_CLOEXEC` matters here.
+fn send_fd(listener: &OwnedFd, id: u64, file: &OwnedFd, flags: libc::c_int) -> io::Result<()> {
+    let addfd = AddFd {
+        id,
+        flags: SECCOMP_ADDFD_FLAG_SEND,
+        srcfd: file.as_raw_fd() as u32,
+        newfd: 0,
+        newfd_flags: (flags & libc::O_CLOEXEC) as u32,
+    };
+
+    // SAFETY: `addfd` is a live local of the layout the kernel expects.
+    if unsafe { libc::ioctl(listener.as_raw_fd(), SECCOMP_IOCTL_NOTIF_ADDFD as _, &addfd) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Whether the kernel can answer a call with a descriptor, which it tells
+/// apart from an unknown notification only if it knows how.
+///
+/// Only makes system calls, so it is safe to use between `fork` and `exit`.
+pub(super) fn can_send_fd(listener: RawFd) -> bool {
+    let addfd = AddFd {
+        id: 0,
+        flags: SECCOMP_ADDFD_FLAG_SEND,
+        srcfd: listener as u32,
+        newfd: 0,
+        newfd_flags: 0,
+    };
+
+    // SAFETY: `addfd` is a live local; the error is read right after.
+    unsafe {
+        libc::ioctl(listener, SECCOMP_IOCTL_NOTIF_ADDFD as _, &addfd) < 0
+            && *libc::__errno_location() == libc::ENOENT
+    }
+}
+
+struct AllowedPaths {
+    writable: Vec<PathBuf>,
+    readable: Vec<PathBuf>,
+}
+
+impl AllowedPaths {
+    fn new(profile: &SeccompProfile, workdir: &Path) -> Self {
+        let canonical = |paths: &[PathBuf]| {
+            paths
+                .iter()
+                .filter_map(|path| path.canonicalize().ok())
+                .collect::<Vec<_>>()
+        };
+
+        let mut writable = canonical(&profile.writable_paths);
+        writable.extend(workdir.canonicalize().ok());
+        Self {
+            writable,
+            readable: canonical(&profile.readable_paths),
+        }
+    }
+
+    fn permit(&self, path: &Path, flags: libc::c_int) -> bool {
+        let writes = flags & libc::O_ACCMODE != libc::O_RDONLY
+            || flags & (libc::O_CREAT | libc::O_TRUNC) != 0;
+
+        self.writable
+            .iter()
+            .any(|writable| path.starts_with(writable))
+            || (!writes
+                && self
+                    .readable
+                    .iter()
+                    .any(|readable| path.starts_with(readable)))
+    }
+
+    /// Whether `path` may be removed, renamed, created as a directory or
+    /// have its mode changed: it has to be inside a writable directory.
+    fn permit_change(&self, path: &Path) -> bool {
+        self.writable
+            .iter()
+            .any(|writable| path.starts_with(writable) && path != writable)
+    }
+}
+
+/// Reads a NUL-terminated path from the memory of `pid`.
+fn read_path(pid: u32, mut address: u64) -> Option<PathBuf> {
+    let memory = File::open(format!("/proc/{pid}/mem")).ok()?;
+    let mut path = Vec::new();
+    let mut chunk = [0; 256];
+
+    while path.len() < libc::PATH_MAX as usize {
+        let len = chunk.len().min((PAGE_SIZE - address % PAGE_SIZE) as usize);
+        let read = memory.read_at(&mut chunk[..len], address).ok()?;
+        if read == 0 {
+            return None;
+        }
+
+        if let Some(end) = chunk[..read].iter().position(|&byte| byte == 0) {
+            path.extend_from_slice(&chunk[..end]);
+            return Some(PathBuf::from(OsString::from_vec(path)));
+        }
+
+        path.extend_from_slice(&chunk[..read]);
+        address += read as u64;
+    }
+
+    None
+}
+
+/// Turns a path as seen by `pid` into an absolute path without symlinks.
+fn resolve(pid: u32, dirfd: RawFd, path: &Path) -> Option<PathBuf> {
+    let path = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        let base = if dirfd == libc::AT_FDCWD {
+            format!("/proc/{pid}/cwd")
+        } else {
+            format!("/proc/{pid}/fd/{dirfd}")
+        };
+        std::fs::read_link(base).ok()?.join(path)
+    };
+
+    // Files being created do not exist yet, and neither may the directories
+    // a search path goes through. Plain names are all that may follow the
+    // part that exists, so the rest cannot lead outside of it.
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return Some(
+                missing
+                    .iter()
+                    .rev()
+                    .fold(resolved, |path, name| path.join(name)),
+            );
+        }
+        match existing.components().next_back()? {
+            Component::Normal(name) => missing.push(name),
+            _ => return None,
+        }
+        existing = existing.parent()?;
+    }
+}
+
+fn receive(listener: &OwnedFd) -> io::Result<Notification> {
+    // SAFETY: the notification is plain old data, zeroed is a valid value.
+    let mut notification: Notification = unsafe { std::mem::zeroed() };
+    // SAFETY: `notification` is a live local of the layout the kernel expects.
+    let result = unsafe {
+        libc::ioctl(
+            listener.as_raw_fd(),
+            SECCOMP_IOCTL_NOTIF_RECV as _,
+            &mut notification,
+        )
+    };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(notification)
+}
+
+/// Sends `listener` over `socket` as `SCM_RIGHTS`.
+///
+/// Runs between `fork` and `exec`, so it must not allocate.
+pub fn send_listener(socket: RawFd, listener: RawFd) -> io::Result<()> {
+    let mut control = ControlBuffer([0; CONTROL_LEN]);
+    let mut byte = [0u8];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: 1,
+    };
+
+    // SAFETY: msghdr is plain old data, zeroed is a valid value, and every
+    // pointer stored in it refers to a live local.
+    unsafe {
+        let mut message: libc::msghdr = std::mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.0.as_mut_ptr().cast();
+        message.msg_controllen = libc::CMSG_SPACE(FD_LEN) as _;
+
+        let header = libc::CMSG_FIRSTHDR(&message);
+        (*header).cmsg_level = libc::SOL_SOCKET;
+        (*header).cmsg_type = libc::SCM_RIGHTS;
+        (*header).cmsg_len = libc::CMSG_LEN(FD_LEN) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(header).cast::<RawFd>(), listener);
+
+        if libc::sendmsg(socket, &message, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Receives the listener sent by [`send_listener`], or `None` if the other
+/// end was closed first.
+fn receive_listener(socket: &OwnedFd) -> io::Result<Option<OwnedFd>> {
+    let mut control = ControlBuffer([0; CONTROL_LEN]);
+    let mut byte = [0u8];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: 1,
+    };
+
+    // SAFETY: as in `send_listener`; the received descriptor is owned by
+    // nobody else.
+    unsafe {
+        let mut message: libc::msghdr = std::mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.0.as_mut_ptr().cast();
+        message.msg_controllen = CONTROL_LEN as _;
+
+        let received = libc::recvmsg(socket.as_raw_fd(), &mut message, libc::MSG_CMSG_CLOEXEC);
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let header = libc::CMSG_FIRSTHDR(&message);
+        if received == 0 || header.is_null() || (*header).cmsg_type != libc::SCM_RIGHTS {
+            return Ok(None);
+        }
+
+        let listener = std::ptr::read_unaligned(libc::CMSG_DATA(header).cast::<RawFd>());
+        Ok(Some(OwnedFd::from_raw_fd(listener)))
+    }
+}
+
+const FD_LEN: u32 = std::mem::size_of::<RawFd>() as u32;
+const CONTROL_LEN: usize = 64;
+
+#[repr(C, align(8))]
+struct ControlBuffer([u8; CONTROL_LEN]);
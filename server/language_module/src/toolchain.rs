@@ -0,0 +1,51 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// First line a toolchain prints when asked for its version.
+pub fn version(program: &str, args: &[&str]) -> io::Result<String> {
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{program} exited with {}",
+            output.status
+        )));
+    }
+
+    // Some interpreters print their version to stderr.
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+
+    Ok(String::from_utf8_lossy(&text)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_owned())
+}
+
+/// Where `program` really lives, looking it up on `PATH` unless it is a path
+/// already, with symlinks resolved.
+///
+/// Runtimes use this to find the directories they read at startup.
+pub fn locate(program: &str) -> Option<PathBuf> {
+    find(Path::new(program))?.canonicalize().ok()
+}
+
+/// `program` as found on the judge's `PATH` unless it is a path already,
+/// keeping the name it was found by: tools such as rustup's proxies tell
+/// what to run by it.
+pub fn find(program: &Path) -> Option<PathBuf> {
+    if program.components().count() > 1 {
+        return Some(program.to_owned());
+    }
+
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
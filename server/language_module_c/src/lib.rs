@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use language_module::{
+    toolchain, Artifact, CompileError, Invocation, LanguageModule, Limits, Sandbox, SeccompProfile,
+};
+
+const SOURCE_FILE: &str = "main.c";
+const EXECUTABLE_FILE: &str = "main";
+
+pub struct LanguageModuleC {
+    pub compiler: String,
+    pub flags: Vec<String>,
+}
+
+impl Default for LanguageModuleC {
+    fn default() -> Self {
+        Self {
+            compiler: "gcc".to_owned(),
+            flags: ["-O2", "-std=c11", "-DONLINE_JUDGE"]
+                .map(str::to_owned)
+                .to_vec(),
+        }
+    }
+}
+
+impl LanguageModule for LanguageModuleC {
+    fn id(&self) -> &str {
+        "c"
+    }
+
+    fn name(&self) -> &str {
+        "C"
+    }
+
+    fn extension(&self) -> &str {
+        "c"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        toolchain::version(&self.compiler, &["--version"])
+    }
+
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        std::fs::write(workdir.join(SOURCE_FILE), source)?;
+
+        // The math library has to follow the source to be linked.
+        let compiler = Invocation::new(&self.compiler, workdir)
+            .args(&self.flags)
+            .args(["-o", EXECUTABLE_FILE, SOURCE_FILE, "-lm"])
+            .env("TMPDIR", workdir);
+        let outcome = sandbox.execute(
+            &compiler,
+            &[],
+            &Limits::COMPILATION,
+            Some(&SeccompProfile::compilation()),
+        )?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(EXECUTABLE_FILE),
+        })
+    }
+
+    fn cache_key(&self) -> Option<Vec<String>> {
+        let mut key = vec![self.compiler.clone()];
+        key.extend(self.flags.iter().cloned());
+        Some(key)
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        Invocation::new(&artifact.path, &artifact.workdir)
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        SeccompProfile::native()
+    }
+}
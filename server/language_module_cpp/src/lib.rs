@@ -1,5 +1,207 @@
-use language_module::LanguageModule;
+use std::path::Path;
 
-pub struct LanguageModuleCpp {}
+use language_module::{
+    toolchain, Artifact, CompileError, Invocation, LanguageModule, Limits, Sandbox, SeccompProfile,
+};
 
-impl LanguageModule for LanguageModuleCpp {}
+const SOURCE_FILE: &str = "main.cpp";
+const EXECUTABLE_FILE: &str = "main";
+
+const MIB: u64 = 1024 * 1024;
+
+/// What the sanitizer runtimes need on top of a native program.
+const SANITIZER_SYSCALLS: &[&str] = &["pipe2"];
+
+const SANITIZER_OPTIONS_FILE: &str = "sanitizer_options.cpp";
+/// Options for AddressSanitizer, built into the program: the runtime reads
+/// `ASAN_OPTIONS` from `/proc/self/environ`, which the sandbox keeps closed.
+/// Leak checking stops the program with `ptrace`, which the sandbox does not
+/// allow either; leaks do not fail a submission anyway.
+const SANITIZER_OPTIONS: &str = r#"extern "C" const char *__asan_default_options() {
+    return "detect_leaks=0";
+}
+"#;
+
+/// One way of building C++: a compiler with its flags. Each variant is a
+/// language of its own, so that a submission records exactly how it was
+/// built.
+pub struct LanguageModuleCpp {
+    pub id: String,
+    pub name: String,
+    pub compiler: String,
+    pub flags: Vec<String>,
+    pub time_multiplier: f64,
+    pub memory_overhead: u64,
+    /// Whether `flags` build with AddressSanitizer and
+    /// UndefinedBehaviorSanitizer, whose runtimes need more of the sandbox.
+    pub sanitizers: bool,
+}
+
+impl Default for LanguageModuleCpp {
+    fn default() -> Self {
+        Self::gnu(17)
+    }
+}
+
+impl LanguageModuleCpp {
+    /// g++ with `-std=c++<standard>`. C++17 keeps the plain `cpp` id it had
+    /// before there were variants.
+    pub fn gnu(standard: u32) -> Self {
+        let id = match standard {
+            17 => "cpp".to_owned(),
+            _ => format!("cpp{standard}"),
+        };
+
+        Self::optimized(id, format!("GNU C++{standard}"), "g++", standard)
+    }
+
+    pub fn clang(standard: u32) -> Self {
+        Self::optimized(
+            format!("cpp{standard}-clang"),
+            format!("Clang C++{standard}"),
+            "clang++",
+            standard,
+        )
+    }
+
+    /// g++ with AddressSanitizer and UndefinedBehaviorSanitizer, which turn
+    /// out-of-bounds accesses and overflows into runtime errors with a
+    /// report on stderr. The program is several times slower.
+    pub fn sanitized(standard: u32) -> Self {
+        Self {
+            id: format!("cpp{standard}-sanitized"),
+            name: format!("GNU C++{standard} (ASan, UBSan)"),
+            compiler: "g++".to_owned(),
+            flags: vec![
+                "-O1".to_owned(),
+                "-g".to_owned(),
+                format!("-std=c++{standard}"),
+                "-DONLINE_JUDGE".to_owned(),
+                "-fsanitize=address,undefined".to_owned(),
+                "-fno-sanitize-recover=all".to_owned(),
+                "-fno-omit-frame-pointer".to_owned(),
+            ],
+            time_multiplier: 3.0,
+            memory_overhead: 256 * MIB,
+            sanitizers: true,
+        }
+    }
+
+    /// Every variant the judge offers.
+    pub fn variants() -> Vec<Self> {
+        vec![
+            Self::gnu(17),
+            Self::gnu(20),
+            Self::gnu(23),
+            Self::clang(17),
+            Self::clang(20),
+            Self::sanitized(17),
+        ]
+    }
+
+    fn optimized(id: String, name: String, compiler: &str, standard: u32) -> Self {
+        Self {
+            id,
+            name,
+            compiler: compiler.to_owned(),
+            flags: vec![
+                "-O2".to_owned(),
+                format!("-std=c++{standard}"),
+                "-DONLINE_JUDGE".to_owned(),
+            ],
+            time_multiplier: 1.0,
+            memory_overhead: 0,
+            sanitizers: false,
+        }
+    }
+}
+
+impl LanguageModule for LanguageModuleCpp {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extension(&self) -> &str {
+        "cpp"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        toolchain::version(&self.compiler, &["--version"])
+    }
+
+    fn default_limits(&self) -> Limits {
+        Limits {
+            // ASan maps terabytes of shadow memory up front.
+            sparse_address_space: self.sanitizers,
+            ..Limits::default()
+        }
+    }
+
+    fn time_multiplier(&self) -> f64 {
+        self.time_multiplier
+    }
+
+    fn memory_overhead(&self) -> u64 {
+        self.memory_overhead
+    }
+
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        std::fs::write(workdir.join(SOURCE_FILE), source)?;
+
+        let mut compiler = Invocation::new(&self.compiler, workdir)
+            .args(&self.flags)
+            .args(["-o", EXECUTABLE_FILE, SOURCE_FILE])
+            .env("TMPDIR", workdir);
+        if self.sanitizers {
+            std::fs::write(workdir.join(SANITIZER_OPTIONS_FILE), SANITIZER_OPTIONS)?;
+            compiler = compiler.arg(SANITIZER_OPTIONS_FILE);
+        }
+        let outcome = sandbox.execute(
+            &compiler,
+            &[],
+            &Limits::COMPILATION,
+            Some(&SeccompProfile::compilation()),
+        )?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(EXECUTABLE_FILE),
+        })
+    }
+
+    fn cache_key(&self) -> Option<Vec<String>> {
+        let mut key = vec![self.compiler.clone()];
+        key.extend(self.flags.iter().cloned());
+        if self.sanitizers {
+            key.push(SANITIZER_OPTIONS.to_owned());
+        }
+        Some(key)
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        Invocation::new(&artifact.path, &artifact.workdir)
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        let profile = SeccompProfile::native();
+
+        if self.sanitizers {
+            profile.allow(SANITIZER_SYSCALLS)
+        } else {
+            profile
+        }
+    }
+}
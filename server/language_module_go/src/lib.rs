@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use language_module::{
+    toolchain, Artifact, CompileError, Invocation, LanguageModule, Limits, Sandbox, SeccompProfile,
+};
+
+const SOURCE_FILE: &str = "main.go";
+const EXECUTABLE_FILE: &str = "main";
+
+/// What `go build` needs on top of other compilers: locking and syncing
+/// the build cache, and waiting for its tools through pidfds.
+const GO_BUILD_SYSCALLS: &[&str] = &[
+    "flock",
+    "fsync",
+    "statfs",
+    "pidfd_open",
+    "pidfd_send_signal",
+];
+
+/// What `go build` tries and does fine without: marking cache entries as
+/// used.
+const GO_BUILD_DENIED_SYSCALLS: &[&str] = &["utimensat"];
+
+pub struct LanguageModuleGo {
+    pub compiler: String,
+    pub flags: Vec<String>,
+    /// Build cache shared by all compilations, so that the standard library
+    /// is only compiled once.
+    pub build_cache: PathBuf,
+    /// Threads running Go code at once. The runtime starts a few more
+    /// threads of its own.
+    pub max_procs: u32,
+}
+
+impl Default for LanguageModuleGo {
+    fn default() -> Self {
+        Self {
+            compiler: "go".to_owned(),
+            flags: ["-trimpath", "-tags", "online_judge"]
+                .map(str::to_owned)
+                .to_vec(),
+            build_cache: std::env::temp_dir().join("solve-go-build-cache"),
+            max_procs: 1,
+        }
+    }
+}
+
+impl LanguageModuleGo {
+    /// Compiling also reads the standard library from GOROOT and fills the
+    /// shared build cache.
+    fn compilation_profile(&self) -> SeccompProfile {
+        let profile = SeccompProfile::compilation()
+            .allow(GO_BUILD_SYSCALLS)
+            .deny(GO_BUILD_DENIED_SYSCALLS)
+            .allow_writing(&self.build_cache);
+
+        // <goroot>/bin/go
+        match toolchain::locate(&self.compiler)
+            .as_deref()
+            .and_then(Path::parent)
+            .and_then(Path::parent)
+        {
+            Some(goroot) => profile.allow_reading(goroot),
+            None => profile,
+        }
+    }
+}
+
+impl LanguageModule for LanguageModuleGo {
+    fn id(&self) -> &str {
+        "go"
+    }
+
+    fn name(&self) -> &str {
+        "Go"
+    }
+
+    fn extension(&self) -> &str {
+        "go"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        toolchain::version(&self.compiler, &["version"])
+    }
+
+    fn default_limits(&self) -> Limits {
+        Limits {
+            processes: 16,
+            sparse_address_space: true,
+            ..Limits::default()
+        }
+    }
+
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        std::fs::write(workdir.join(SOURCE_FILE), source)?;
+        std::fs::create_dir_all(&self.build_cache)?;
+
+        // A static executable needs nothing from the host at run time; the
+        // build runs outside of any module, straight from the file.
+        let compiler = Invocation::new(&self.compiler, workdir)
+            .arg("build")
+            .args(&self.flags)
+            .args(["-o", EXECUTABLE_FILE, SOURCE_FILE])
+            .env("GOCACHE", &self.build_cache)
+            .env("GOPATH", workdir.join(".gopath"))
+            .env("GO111MODULE", "off")
+            .env("GOFLAGS", "")
+            .env("GOTOOLCHAIN", "local")
+            .env("CGO_ENABLED", "0")
+            .env("TMPDIR", workdir);
+        let outcome = sandbox.execute(
+            &compiler,
+            &[],
+            &Limits::COMPILATION,
+            Some(&self.compilation_profile()),
+        )?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(EXECUTABLE_FILE),
+        })
+    }
+
+    fn cache_key(&self) -> Option<Vec<String>> {
+        let mut key = vec![self.compiler.clone()];
+        key.extend(self.flags.iter().cloned());
+        Some(key)
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        Invocation::new(&artifact.path, &artifact.workdir)
+            .env("GOMAXPROCS", self.max_procs.to_string())
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        SeccompProfile::native().threaded()
+    }
+}
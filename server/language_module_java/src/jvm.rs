@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use language_module::{toolchain, Invocation, Limits, SeccompProfile};
+
+const MIB: u64 = 1024 * 1024;
+
+/// What the JVM tries at startup and does fine without: looking up the name
+/// of the user, which may go through a socket to a name service.
+const JVM_DENIED_SYSCALLS: &[&str] = &["socket"];
+
+/// The Java virtual machine, shared by the languages running on it.
+#[derive(Debug, Clone)]
+pub struct Jvm {
+    pub java: String,
+    /// Options passed before the heap size and the program.
+    pub flags: Vec<String>,
+    /// Memory the JVM takes besides the heap: metaspace, code cache and
+    /// thread stacks. The heap gets the rest of the memory limit.
+    pub memory_overhead: u64,
+    pub time_multiplier: f64,
+}
+
+impl Default for Jvm {
+    fn default() -> Self {
+        Self {
+            java: "java".to_owned(),
+            // A single collector thread and fewer compiler threads keep the
+            // time the JVM spends on itself low; the smaller code cache and
+            // class space keep it within `memory_overhead`.
+            flags: [
+                "-XX:+UseSerialGC",
+                "-XX:ActiveProcessorCount=1",
+                "-XX:-UsePerfData",
+                "-XX:ReservedCodeCacheSize=64m",
+                "-XX:CompressedClassSpaceSize=64m",
+                "-XX:MaxMetaspaceSize=128m",
+                "-Xss64m",
+                "-Dfile.encoding=UTF-8",
+                "-DONLINE_JUDGE=true",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            memory_overhead: 96 * MIB,
+            time_multiplier: 1.5,
+        }
+    }
+}
+
+impl Jvm {
+    pub fn version(&self) -> std::io::Result<String> {
+        toolchain::version(&self.java, &["-version"])
+    }
+
+    /// Starts the JVM in `workdir` with a heap of what `limits` leave after
+    /// [`memory_overhead`](Self::memory_overhead), followed by `args`.
+    pub fn invocation<I, S>(&self, workdir: &Path, limits: &Limits, args: I) -> Invocation
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        let heap = limits
+            .memory
+            .saturating_sub(self.memory_overhead)
+            .max(16 * MIB);
+
+        Invocation::new(&self.java, workdir)
+            .args(&self.flags)
+            .arg(format!("-Xmx{}m", heap / MIB))
+            .args(args)
+    }
+
+    /// Runtimes start a dozen threads of their own on top of the program's.
+    pub fn default_limits(&self) -> Limits {
+        Limits {
+            processes: 64,
+            open_files: 256,
+            sparse_address_space: true,
+            ..Limits::default()
+        }
+    }
+
+    pub fn seccomp_profile(&self) -> SeccompProfile {
+        let profile = SeccompProfile::native()
+            .threaded()
+            .deny(JVM_DENIED_SYSCALLS);
+
+        allow_home(profile, home(&self.java))
+    }
+
+    /// The profile of `compiler` running on a JVM, either a tool of the JDK
+    /// such as `javac` or a script starting one such as `kotlinc`.
+    pub fn compiler_profile(&self, compiler: &str) -> SeccompProfile {
+        let profile = SeccompProfile::compilation().deny(JVM_DENIED_SYSCALLS);
+
+        allow_home(allow_home(profile, home(&self.java)), home(compiler))
+    }
+}
+
+/// Directory `program` is installed in, as <home>/bin/<program>.
+fn home(program: &str) -> Option<PathBuf> {
+    let program = toolchain::locate(program)?;
+    Some(program.parent()?.parent()?.to_owned())
+}
+
+fn allow_home(profile: SeccompProfile, home: Option<PathBuf>) -> SeccompProfile {
+    let Some(home) = home else {
+        return profile;
+    };
+
+    // The configuration under <home>/conf is usually linked in from /etc,
+    // as a whole or file by file, which is only readable once allowed by
+    // name.
+    let conf = home.join("conf");
+    link_targets(&conf)
+        .into_iter()
+        .fold(profile.allow_reading(conf), SeccompProfile::allow_reading)
+        .allow_reading(home)
+}
+
+/// Where the symlinks under `dir` lead.
+fn link_targets(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .flat_map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_symlink() => entry.path().canonicalize().into_iter().collect(),
+            Ok(kind) if kind.is_dir() => link_targets(&entry.path()),
+            _ => Vec::new(),
+        })
+        .collect()
+}
@@ -0,0 +1,148 @@
+pub mod jvm;
+
+use std::path::Path;
+
+use language_module::{
+    toolchain, Artifact, CompileError, Execution, Invocation, LanguageModule, Limits, Sandbox,
+    SeccompProfile,
+};
+
+pub use jvm::Jvm;
+
+/// Class started when the source declares no public class.
+const DEFAULT_CLASS: &str = "Main";
+
+pub struct LanguageModuleJava {
+    pub compiler: String,
+    pub flags: Vec<String>,
+    pub jvm: Jvm,
+}
+
+impl Default for LanguageModuleJava {
+    fn default() -> Self {
+        Self {
+            compiler: "javac".to_owned(),
+            // javac runs on a JVM too; without a heap limit it would size
+            // the heap by the host's memory rather than the sandbox's.
+            flags: ["-J-Xmx1g", "-J-XX:+UseSerialGC", "-encoding", "UTF-8"]
+                .map(str::to_owned)
+                .to_vec(),
+            jvm: Jvm::default(),
+        }
+    }
+}
+
+impl LanguageModule for LanguageModuleJava {
+    fn id(&self) -> &str {
+        "java"
+    }
+
+    fn name(&self) -> &str {
+        "Java"
+    }
+
+    fn extension(&self) -> &str {
+        "java"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        // The compiler and the runtime have to be installed both.
+        self.jvm.version()?;
+        toolchain::version(&self.compiler, &["-version"])
+    }
+
+    fn default_limits(&self) -> Limits {
+        self.jvm.default_limits()
+    }
+
+    fn time_multiplier(&self) -> f64 {
+        self.jvm.time_multiplier
+    }
+
+    fn memory_overhead(&self) -> u64 {
+        self.jvm.memory_overhead
+    }
+
+    /// Leaves the classes in `workdir`; the artifact is the one holding
+    /// `main`.
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        let class = public_class(source).unwrap_or(DEFAULT_CLASS);
+        let source_file = format!("{class}.java");
+        std::fs::write(workdir.join(&source_file), source)?;
+
+        let compiler = Invocation::new(&self.compiler, workdir)
+            .args(&self.flags)
+            .args(["-d", "."])
+            .arg(&source_file)
+            .env("TMPDIR", workdir);
+        let outcome = sandbox.execute(
+            &compiler,
+            &[],
+            &Limits::COMPILATION,
+            Some(&self.jvm.compiler_profile(&self.compiler)),
+        )?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(format!("{class}.class")),
+        })
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        self.execution(artifact, &self.default_limits()).invocation
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        self.jvm.seccomp_profile()
+    }
+
+    /// Sizes the heap by the memory limit.
+    fn execution(&self, artifact: &Artifact, limits: &Limits) -> Execution {
+        let class = artifact
+            .path
+            .file_stem()
+            .unwrap_or(DEFAULT_CLASS.as_ref())
+            .to_owned();
+        let invocation =
+            self.jvm
+                .invocation(&artifact.workdir, limits, ["-cp".into(), ".".into(), class]);
+
+        Execution {
+            invocation,
+            limits: *limits,
+            profile: Some(self.seccomp_profile()),
+        }
+    }
+}
+
+/// Name of the public top-level class of `source`, which has to match the
+/// name of the file it is compiled from.
+fn public_class(source: &str) -> Option<&str> {
+    let mut words = source
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|word| !word.is_empty());
+
+    while let Some(word) = words.next() {
+        if word != "public" {
+            continue;
+        }
+        for word in words.by_ref() {
+            match word {
+                "final" | "abstract" | "strictfp" => continue,
+                "class" => return words.next(),
+                _ => break,
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use language_module::{
+    toolchain, Artifact, CompileError, Execution, Invocation, LanguageModule, Limits, Sandbox,
+    SeccompProfile,
+};
+
+const SOURCE_FILE: &str = "main.js";
+
+const MIB: u64 = 1024 * 1024;
+
+/// What Node.js probes for at startup and does fine without: its own
+/// capabilities and memory protection keys.
+const NODE_DENIED_SYSCALLS: &[&str] = &["capget", "pkey_alloc"];
+
+pub struct LanguageModuleJavascript {
+    pub interpreter: String,
+    /// Options passed before the heap size and the script.
+    pub flags: Vec<String>,
+    /// Memory Node.js takes besides the JavaScript heap. The heap gets the
+    /// rest of the memory limit.
+    pub memory_overhead: u64,
+    pub time_multiplier: f64,
+}
+
+impl Default for LanguageModuleJavascript {
+    fn default() -> Self {
+        Self {
+            interpreter: "node".to_owned(),
+            flags: ["--stack-size=65500"].map(str::to_owned).to_vec(),
+            memory_overhead: 64 * MIB,
+            time_multiplier: 1.5,
+        }
+    }
+}
+
+impl LanguageModule for LanguageModuleJavascript {
+    fn id(&self) -> &str {
+        "javascript"
+    }
+
+    fn name(&self) -> &str {
+        "JavaScript (Node.js)"
+    }
+
+    fn extension(&self) -> &str {
+        "js"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        toolchain::version(&self.interpreter, &["--version"])
+    }
+
+    /// Node.js starts a handful of threads for V8 and libuv.
+    fn default_limits(&self) -> Limits {
+        Limits {
+            processes: 16,
+            sparse_address_space: true,
+            ..Limits::default()
+        }
+    }
+
+    fn time_multiplier(&self) -> f64 {
+        self.time_multiplier
+    }
+
+    fn memory_overhead(&self) -> u64 {
+        self.memory_overhead
+    }
+
+    /// Scripts are not built, but checking the syntax catches errors before
+    /// any test is run.
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        std::fs::write(workdir.join(SOURCE_FILE), source)?;
+
+        let checker = Invocation::new(&self.interpreter, workdir).args(["--check", SOURCE_FILE]);
+        let outcome = sandbox.execute(
+            &checker,
+            &[],
+            &Limits::COMPILATION,
+            Some(&self.seccomp_profile()),
+        )?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(SOURCE_FILE),
+        })
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        self.execution(artifact, &self.default_limits()).invocation
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        SeccompProfile::native()
+            .threaded()
+            .deny(NODE_DENIED_SYSCALLS)
+            .allow_reading("/etc/ssl/openssl.cnf")
+    }
+
+    /// Sizes the heap by the memory limit.
+    fn execution(&self, artifact: &Artifact, limits: &Limits) -> Execution {
+        let heap = limits
+            .memory
+            .saturating_sub(self.memory_overhead)
+            .max(16 * MIB);
+        let invocation = Invocation::new(&self.interpreter, &artifact.workdir)
+            .args(&self.flags)
+            .arg(format!("--max-old-space-size={}", heap / MIB))
+            .arg(&artifact.path);
+
+        Execution {
+            invocation,
+            limits: *limits,
+            profile: Some(self.seccomp_profile()),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use language_module::{
+    toolchain, Artifact, CompileError, Execution, Invocation, LanguageModule, Limits, Sandbox,
+    SeccompProfile,
+};
+use language_module_java::Jvm;
+
+const SOURCE_FILE: &str = "main.kt";
+/// The program and the Kotlin runtime, runnable with `java -jar`.
+const JAR_FILE: &str = "main.jar";
+
+pub struct LanguageModuleKotlin {
+    pub compiler: String,
+    pub flags: Vec<String>,
+    pub jvm: Jvm,
+}
+
+impl Default for LanguageModuleKotlin {
+    fn default() -> Self {
+        Self {
+            compiler: "kotlinc".to_owned(),
+            // kotlinc runs on a JVM too; without a heap limit it would size
+            // the heap by the host's memory rather than the sandbox's.
+            flags: ["-J-Xmx1g", "-J-XX:+UseSerialGC", "-nowarn"]
+                .map(str::to_owned)
+                .to_vec(),
+            jvm: Jvm::default(),
+        }
+    }
+}
+
+impl LanguageModule for LanguageModuleKotlin {
+    fn id(&self) -> &str {
+        "kotlin"
+    }
+
+    fn name(&self) -> &str {
+        "Kotlin"
+    }
+
+    fn extension(&self) -> &str {
+        "kt"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        // The compiler and the runtime have to be installed both.
+        self.jvm.version()?;
+        toolchain::version(&self.compiler, &["-version"])
+    }
+
+    fn default_limits(&self) -> Limits {
+        self.jvm.default_limits()
+    }
+
+    fn time_multiplier(&self) -> f64 {
+        self.jvm.time_multiplier
+    }
+
+    fn memory_overhead(&self) -> u64 {
+        self.jvm.memory_overhead
+    }
+
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        std::fs::write(workdir.join(SOURCE_FILE), source)?;
+
+        let compiler = Invocation::new(&self.compiler, workdir)
+            .args(&self.flags)
+            .args([SOURCE_FILE, "-include-runtime", "-d", JAR_FILE])
+            .env("TMPDIR", workdir);
+        let outcome = sandbox.execute(
+            &compiler,
+            &[],
+            &Limits::COMPILATION,
+            Some(&self.jvm.compiler_profile(&self.compiler)),
+        )?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(JAR_FILE),
+        })
+    }
+
+    fn cache_key(&self) -> Option<Vec<String>> {
+        let mut key = vec![self.compiler.clone()];
+        key.extend(self.flags.iter().cloned());
+        Some(key)
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        self.execution(artifact, &self.default_limits()).invocation
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        self.jvm.seccomp_profile()
+    }
+
+    /// Sizes the heap by the memory limit.
+    fn execution(&self, artifact: &Artifact, limits: &Limits) -> Execution {
+        let invocation = self.jvm.invocation(
+            &artifact.workdir,
+            limits,
+            ["-jar".into(), artifact.path.clone().into_os_string()],
+        );
+
+        Execution {
+            invocation,
+            limits: *limits,
+            profile: Some(self.seccomp_profile()),
+        }
+    }
+}
@@ -1,5 +1,152 @@
-use language_module::LanguageModule;
+use std::path::{Path, PathBuf};
 
-pub struct LanguageModulePython {}
+use language_module::{
+    toolchain, Artifact, CompileError, Invocation, LanguageModule, Limits, Sandbox, SeccompProfile,
+};
 
-impl LanguageModule for LanguageModulePython {}
+const SOURCE_FILE: &str = "main.py";
+
+/// What the interpreter needs on top of a native program: it lists the
+/// directories on `sys.path` while importing.
+const PYTHON_SYSCALLS: &[&str] = &["getdents64", "getppid"];
+
+/// What byte-compiling needs on top of running: writing the result to
+/// `__pycache__` next to the script.
+const PY_COMPILE_SYSCALLS: &[&str] = &["mkdir", "rename"];
+
+const MIB: u64 = 1024 * 1024;
+
+/// One Python implementation. Each is a language of its own, so that a
+/// submission records which one ran it.
+pub struct LanguageModulePython {
+    pub id: String,
+    pub name: String,
+    pub interpreter: String,
+    pub time_multiplier: f64,
+    pub memory_overhead: u64,
+}
+
+impl Default for LanguageModulePython {
+    fn default() -> Self {
+        Self::cpython()
+    }
+}
+
+impl LanguageModulePython {
+    /// The reference interpreter, under the plain `python` id it had before
+    /// there were variants. It interprets every instruction, so it gets the
+    /// most time.
+    pub fn cpython() -> Self {
+        Self {
+            id: "python".to_owned(),
+            name: "Python 3 (CPython)".to_owned(),
+            interpreter: "python3".to_owned(),
+            time_multiplier: 3.0,
+            memory_overhead: 0,
+        }
+    }
+
+    /// The JIT compiling interpreter, much faster on loops. Its runtime and
+    /// compiled code take memory of their own.
+    pub fn pypy() -> Self {
+        Self {
+            id: "pypy".to_owned(),
+            name: "Python 3 (PyPy)".to_owned(),
+            interpreter: "pypy3".to_owned(),
+            time_multiplier: 1.5,
+            memory_overhead: 64 * MIB,
+        }
+    }
+
+    /// Every variant the judge offers.
+    pub fn variants() -> Vec<Self> {
+        vec![Self::cpython(), Self::pypy()]
+    }
+
+    /// Directories the configured interpreter loads its modules from.
+    fn library_dirs(&self) -> Vec<PathBuf> {
+        let Some(prefix) = toolchain::locate(&self.interpreter)
+            .as_deref()
+            .and_then(Path::parent)
+            .and_then(Path::parent)
+            .map(Path::to_owned)
+        else {
+            return Vec::new();
+        };
+
+        // <prefix>/bin/python3 keeps its modules under <prefix>/lib. PyPy
+        // may also keep them in lib_pypy and lib-python, and its own library
+        // next to the interpreter.
+        ["lib", "lib_pypy", "lib-python", "bin"]
+            .into_iter()
+            .map(|dir| prefix.join(dir))
+            .filter(|dir| dir.is_dir())
+            .collect()
+    }
+}
+
+impl LanguageModule for LanguageModulePython {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extension(&self) -> &str {
+        "py"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        toolchain::version(&self.interpreter, &["--version"])
+    }
+
+    fn time_multiplier(&self) -> f64 {
+        self.time_multiplier
+    }
+
+    fn memory_overhead(&self) -> u64 {
+        self.memory_overhead
+    }
+
+    /// Python has no build step, but byte-compiling the script catches
+    /// syntax errors before any test is run.
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        std::fs::write(workdir.join(SOURCE_FILE), source)?;
+
+        let checker =
+            Invocation::new(&self.interpreter, workdir).args(["-m", "py_compile", SOURCE_FILE]);
+        let outcome = sandbox.execute(
+            &checker,
+            &[],
+            &Limits::COMPILATION,
+            Some(&self.seccomp_profile().allow(PY_COMPILE_SYSCALLS)),
+        )?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(SOURCE_FILE),
+        })
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        Invocation::new(&self.interpreter, &artifact.workdir).arg(&artifact.path)
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        self.library_dirs().into_iter().fold(
+            SeccompProfile::native().allow(PYTHON_SYSCALLS),
+            |profile, dir| profile.allow_reading(dir),
+        )
+    }
+}
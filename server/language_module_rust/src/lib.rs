@@ -0,0 +1,130 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use language_module::{
+    toolchain, Artifact, CompileError, Invocation, LanguageModule, Limits, Sandbox, SeccompProfile,
+};
+
+const SOURCE_FILE: &str = "main.rs";
+const EXECUTABLE_FILE: &str = "main";
+
+/// What the standard library needs on top of a native program: it polls
+/// the standard streams at startup to check they are open.
+const RUST_SYSCALLS: &[&str] = &["poll"];
+
+pub struct LanguageModuleRust {
+    pub compiler: String,
+    pub flags: Vec<String>,
+}
+
+impl Default for LanguageModuleRust {
+    fn default() -> Self {
+        Self {
+            compiler: "rustc".to_owned(),
+            flags: [
+                "--edition=2021",
+                "-O",
+                "-Cdebuginfo=0",
+                "--cfg",
+                "online_judge",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+        }
+    }
+}
+
+impl LanguageModuleRust {
+    fn sysroot(&self) -> Option<PathBuf> {
+        let output = Command::new(&self.compiler)
+            .args(["--print", "sysroot"])
+            .output()
+            .ok()?;
+
+        output
+            .status
+            .success()
+            .then(|| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+    }
+}
+
+/// Where rustup keeps toolchains, if it keeps the one of `sysroot`:
+/// <rustup home>/toolchains/<toolchain>. Its proxy in place of `rustc` reads
+/// the settings there too.
+fn rustup_home(sysroot: &Path) -> Option<&Path> {
+    sysroot
+        .parent()
+        .filter(|dir| dir.ends_with("toolchains"))
+        .and_then(Path::parent)
+}
+
+impl LanguageModule for LanguageModuleRust {
+    fn id(&self) -> &str {
+        "rust"
+    }
+
+    fn name(&self) -> &str {
+        "Rust"
+    }
+
+    fn extension(&self) -> &str {
+        "rs"
+    }
+
+    fn version(&self) -> std::io::Result<String> {
+        toolchain::version(&self.compiler, &["--version"])
+    }
+
+    fn compile(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        workdir: &Path,
+    ) -> Result<Artifact, CompileError> {
+        std::fs::write(workdir.join(SOURCE_FILE), source)?;
+
+        let mut compiler = Invocation::new(&self.compiler, workdir)
+            .args(&self.flags)
+            .args(["-o", EXECUTABLE_FILE, SOURCE_FILE])
+            .env("TMPDIR", workdir);
+        // Compiling also reads the standard library from the sysroot. The
+        // compiler does not get the judge's `HOME`, which rustup would find
+        // its toolchains through.
+        let mut profile = SeccompProfile::compilation();
+        if let Some(sysroot) = self.sysroot() {
+            match rustup_home(&sysroot) {
+                Some(rustup_home) => {
+                    compiler = compiler.env("RUSTUP_HOME", rustup_home);
+                    profile = profile.allow_reading(rustup_home);
+                }
+                None => profile = profile.allow_reading(sysroot),
+            }
+        }
+        let outcome = sandbox.execute(&compiler, &[], &Limits::COMPILATION, Some(&profile))?;
+
+        if !outcome.status.success() {
+            return Err(CompileError::Rejected(outcome));
+        }
+
+        Ok(Artifact {
+            workdir: workdir.to_owned(),
+            path: workdir.join(EXECUTABLE_FILE),
+        })
+    }
+
+    fn cache_key(&self) -> Option<Vec<String>> {
+        let mut key = vec![self.compiler.clone()];
+        key.extend(self.flags.iter().cloned());
+        Some(key)
+    }
+
+    fn invocation(&self, artifact: &Artifact) -> Invocation {
+        Invocation::new(&artifact.path, &artifact.workdir)
+    }
+
+    fn seccomp_profile(&self) -> SeccompProfile {
+        SeccompProfile::native().allow(RUST_SYSCALLS)
+    }
+}
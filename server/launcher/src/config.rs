@@ -0,0 +1,389 @@
+//! Settings of every part of the server, read once at startup.
+//!
+//! Values come from a TOML file, see `solve.example.toml`, and are
+//! overridden by environment variables:
+//!
+//! | Variable | Setting |
+//! |---|---|
+//! | `API_BIND`, `API_PORT` | `server.bind`, or just its port |
+//! | `API_PUBLIC_URL` | `server.public_url` |
+//! | `API_CORS_ORIGINS` | `server.cors_origins`, comma separated |
+//! | `API_SECURE_COOKIES` | `server.secure_cookies` |
+//! | `API_RUNS_PER_MINUTE` | `server.runs_per_minute` |
+//! | `DATABASE_URL` | `database.url` |
+//! | `API_PG_HOST`, `API_PG_PORT`, `API_PG_USER`, `API_PG_PASSWORD`, `API_PG_DATABASE` | `database.url` if `DATABASE_URL` is not set |
+//! | `API_PG_POOL_SIZE` | `database.pool_size` |
+//! | `JWT_SECRET`, `JWT_EXPIRED_IN`, `JWT_MAXAGE` | `auth.jwt_secret`, `auth.access_token_lifetime`, `auth.refresh_token_lifetime_minutes` |
+//! | `JUDGE_WORKERS`, `JUDGE_WORKDIR`, `JUDGE_DATA_DIR` | `judge.workers`, `judge.workdir`, `judge.data_dir` |
+//! | `JUDGE_COMPILE_CACHE_DIR`, `JUDGE_COMPILE_CACHE_SIZE_MB` | `judge.compile_cache_dir`, `judge.compile_cache_size_mb` |
+//! | `SANDBOX_CGROUP_ROOT` | `sandbox.cgroup_root` |
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use judge::JudgeConfig;
+use language_module::{CompileCache, Sandbox};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use web::{auth::JWTConfig, WebConfig};
+
+/// Read when no file is given explicitly, if it exists.
+pub const DEFAULT_PATH: &str = "solve.toml";
+
+pub struct Config {
+    pub web: WebConfig,
+    /// Only required to serve the web app, so judge nodes can do without.
+    pub jwt: Option<JWTConfig>,
+    pub database_url: String,
+    pub pool_size: usize,
+    pub judge: JudgeConfig,
+    pub cgroup_root: PathBuf,
+    pub sandbox_user: Option<(u32, u32)>,
+}
+
+/// Everything wrong with the configuration, one problem per line.
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            writeln!(f, "  {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct File {
+    server: ServerSection,
+    database: DatabaseSection,
+    auth: AuthSection,
+    judge: JudgeSection,
+    sandbox: SandboxSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerSection {
+    bind: Option<String>,
+    public_url: Option<String>,
+    /// Default to the origin of `public_url`.
+    cors_origins: Option<Vec<String>>,
+    secure_cookies: Option<bool>,
+    session_expiry: Option<String>,
+    runs_per_minute: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DatabaseSection {
+    url: Option<String>,
+    pool_size: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AuthSection {
+    jwt_secret: Option<String>,
+    access_token_lifetime: Option<String>,
+    refresh_token_lifetime_minutes: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct JudgeSection {
+    workers: Option<usize>,
+    workdir: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    compile_cache_dir: Option<PathBuf>,
+    /// Zero turns the cache off.
+    compile_cache_size_mb: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SandboxSection {
+    cgroup_root: Option<PathBuf>,
+    /// User and group sandboxed programs run as.
+    uid: Option<u32>,
+    gid: Option<u32>,
+}
+
+impl Config {
+    /// Reads `path`, or [`DEFAULT_PATH`] if there is one, and applies the
+    /// environment on top.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+
+        let file = match read_file(path) {
+            Ok(file) => file,
+            Err(e) => return Err(ConfigError(vec![e])),
+        };
+
+        let config = Self::build(file, &mut errors);
+        match config {
+            Some(config) if errors.is_empty() => Ok(config),
+            _ => Err(ConfigError(errors)),
+        }
+    }
+
+    fn build(mut file: File, errors: &mut Vec<String>) -> Option<Self> {
+        let defaults = WebConfig::default();
+        let judge_defaults = JudgeConfig::default();
+
+        let server = &mut file.server;
+        let mut bind = match env("API_BIND").or(server.bind.take()) {
+            Some(bind) => parse(errors, "server.bind", &bind),
+            None => Some(defaults.bind),
+        };
+        if let (Some(port), Some(bind)) = (env("API_PORT"), &mut bind) {
+            if let Some(port) = parse(errors, "API_PORT", &port) {
+                bind.set_port(port);
+            }
+        }
+
+        let public_url = env("API_PUBLIC_URL")
+            .or(server.public_url.take())
+            .unwrap_or(defaults.public_url);
+        let public_url = public_url.trim_end_matches('/').to_owned();
+        if !(public_url.starts_with("http://") || public_url.starts_with("https://")) {
+            errors.push(format!(
+                "server.public_url: {public_url:?} must start with http:// or https://"
+            ));
+        }
+
+        let cors_origins = match env("API_CORS_ORIGINS") {
+            Some(origins) => Some(origins.split(',').map(|o| o.trim().to_owned()).collect()),
+            None => server.cors_origins.take(),
+        };
+        let cors_origins = cors_origins
+            .unwrap_or_else(|| vec![origin(&public_url).to_owned()])
+            .iter()
+            .filter_map(|o| parse_with(errors, "server.cors_origins", o, |o| o.parse().ok()))
+            .collect();
+
+        let secure_cookies = match env("API_SECURE_COOKIES") {
+            Some(secure) => parse(errors, "API_SECURE_COOKIES", &secure),
+            None => Some(server.secure_cookies.unwrap_or(defaults.secure_cookies)),
+        };
+
+        let session_expiry = match server.session_expiry.take() {
+            Some(expiry) => parse_with(errors, "server.session_expiry", &expiry, |e| {
+                web::config::parse_duration(e).filter(|d| !d.is_zero())
+            }),
+            None => Some(defaults.session_expiry),
+        };
+
+        let runs_per_minute = match env("API_RUNS_PER_MINUTE") {
+            Some(runs) => parse(errors, "API_RUNS_PER_MINUTE", &runs),
+            None => Some(server.runs_per_minute.unwrap_or(defaults.runs_per_minute)),
+        };
+
+        let database = &mut file.database;
+        let database_url = env("DATABASE_URL")
+            .or_else(database_url_from_parts)
+            .or(database.url.take());
+        if database_url.is_none() {
+            errors.push("database.url: not set, nor are DATABASE_URL or API_PG_HOST".to_owned());
+        }
+        let pool_size = match env("API_PG_POOL_SIZE") {
+            Some(size) => parse(errors, "API_PG_POOL_SIZE", &size),
+            None => Some(database.pool_size.unwrap_or(16)),
+        };
+        if pool_size == Some(0) {
+            errors.push("database.pool_size: must be at least 1".to_owned());
+        }
+
+        let auth = &mut file.auth;
+        let jwt_secret = env("JWT_SECRET").or(auth.jwt_secret.take());
+        let access_token_lifetime = env("JWT_EXPIRED_IN")
+            .or(auth.access_token_lifetime.take())
+            .unwrap_or_else(|| "15m".to_owned());
+        let maxage = match env("JWT_MAXAGE") {
+            Some(maxage) => parse(errors, "JWT_MAXAGE", &maxage),
+            None => Some(auth.refresh_token_lifetime_minutes.unwrap_or(7 * 24 * 60)),
+        };
+        // Lifetimes are checked even without a secret, judge nodes may share
+        // their settings with the web app.
+        let jwt = maxage.and_then(|maxage| {
+            let secret = jwt_secret.clone().unwrap_or_else(|| "unset".to_owned());
+            match JWTConfig::new(secret, &access_token_lifetime, maxage) {
+                Ok(jwt) => jwt_secret.is_some().then_some(jwt),
+                Err(e) => {
+                    errors.push(format!("auth: {e}"));
+                    None
+                }
+            }
+        });
+
+        let judge = &mut file.judge;
+        let workers = match env("JUDGE_WORKERS") {
+            Some(workers) => parse(errors, "JUDGE_WORKERS", &workers),
+            None => Some(judge.workers.unwrap_or(judge_defaults.workers)),
+        };
+        if workers == Some(0) {
+            errors.push("judge.workers: must be at least 1".to_owned());
+        }
+        let compile_cache_size_mb = match env("JUDGE_COMPILE_CACHE_SIZE_MB") {
+            Some(size) => parse(errors, "JUDGE_COMPILE_CACHE_SIZE_MB", &size),
+            None => judge.compile_cache_size_mb,
+        };
+        let compile_cache = CompileCache {
+            dir: env_path("JUDGE_COMPILE_CACHE_DIR")
+                .or(judge.compile_cache_dir.take())
+                .unwrap_or(judge_defaults.compile_cache.dir.clone()),
+            max_bytes: compile_cache_size_mb.map_or(judge_defaults.compile_cache.max_bytes, |mb| {
+                mb.saturating_mul(1024 * 1024)
+            }),
+        };
+        let judge = JudgeConfig {
+            workers: workers.unwrap_or(1),
+            workdir: env_path("JUDGE_WORKDIR")
+                .or(judge.workdir.take())
+                .unwrap_or(judge_defaults.workdir),
+            data_dir: env_path("JUDGE_DATA_DIR")
+                .or(judge.data_dir.take())
+                .unwrap_or(judge_defaults.data_dir),
+            compile_cache,
+            ..judge_defaults
+        };
+
+        let sandbox = &mut file.sandbox;
+        let cgroup_root = env_path("SANDBOX_CGROUP_ROOT")
+            .or(sandbox.cgroup_root.take())
+            .unwrap_or_else(|| PathBuf::from(Sandbox::DEFAULT_CGROUP_ROOT));
+        let sandbox_user = match (sandbox.uid, sandbox.gid) {
+            (Some(uid), Some(gid)) => Some((uid, gid)),
+            (None, None) => None,
+            _ => {
+                errors.push("sandbox: uid and gid must be set together".to_owned());
+                None
+            }
+        };
+
+        Some(Self {
+            web: WebConfig {
+                bind: bind?,
+                public_url,
+                cors_origins,
+                secure_cookies: secure_cookies?,
+                session_expiry: session_expiry?,
+                runs_per_minute: runs_per_minute?,
+            },
+            jwt,
+            database_url: database_url?,
+            pool_size: pool_size?,
+            judge,
+            cgroup_root,
+            sandbox_user,
+        })
+    }
+
+    pub fn require_jwt(&self) -> Result<JWTConfig, ConfigError> {
+        self.jwt.clone().ok_or_else(|| {
+            ConfigError(vec![
+                "auth.jwt_secret: not set, nor is JWT_SECRET".to_owned()
+            ])
+        })
+    }
+
+    /// [`database_url`](Self::database_url) with the password left out, to
+    /// be printed.
+    pub fn redacted_database_url(&self) -> String {
+        let url = &self.database_url;
+        let after_scheme = url.find("://").map_or(0, |index| index + 3);
+        let authority_end = url[after_scheme..]
+            .find('/')
+            .map_or(url.len(), |index| after_scheme + index);
+
+        let Some(at) = url[after_scheme..authority_end].rfind('@') else {
+            return url.clone();
+        };
+        match url[after_scheme..after_scheme + at].find(':') {
+            Some(colon) => format!(
+                "{}***{}",
+                &url[..after_scheme + colon + 1],
+                &url[after_scheme + at..]
+            ),
+            None => url.clone(),
+        }
+    }
+
+    pub fn sandbox(&self) -> Sandbox {
+        Sandbox {
+            user: self.sandbox_user,
+            ..Sandbox::detect_at(&self.cgroup_root)
+        }
+    }
+}
+
+fn read_file(path: Option<&Path>) -> Result<File, String> {
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(DEFAULT_PATH).is_file() => Path::new(DEFAULT_PATH),
+        None => return Ok(File::default()),
+    };
+
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("{}: could not be read: {e}", path.display()))?;
+    toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// The database URL assembled from the `API_PG_*` variables the compose
+/// files set. The user, password and database may hold any character, so
+/// they are percent-encoded.
+fn database_url_from_parts() -> Option<String> {
+    let encode = |part: String| utf8_percent_encode(&part, NON_ALPHANUMERIC).to_string();
+
+    let host = env("API_PG_HOST")?;
+    let port = env("API_PG_PORT").unwrap_or_else(|| "5432".to_owned());
+    let user = encode(env("API_PG_USER").unwrap_or_else(|| "postgres".to_owned()));
+    let database = encode(env("API_PG_DATABASE").unwrap_or_else(|| "postgres".to_owned()));
+
+    Some(match env("API_PG_PASSWORD").map(encode) {
+        Some(password) => format!("postgres://{user}:{password}@{host}:{port}/{database}"),
+        None => format!("postgres://{user}@{host}:{port}/{database}"),
+    })
+}
+
+/// `scheme://host[:port]` of `url`.
+fn origin(url: &str) -> &str {
+    let after_scheme = url.find("://").map_or(0, |index| index + 3);
+    match url[after_scheme..].find('/') {
+        Some(index) => &url[..after_scheme + index],
+        None => url,
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    env(name).map(PathBuf::from)
+}
+
+fn parse<T: FromStr>(errors: &mut Vec<String>, setting: &str, text: &str) -> Option<T> {
+    parse_with(errors, setting, text, |text| text.parse().ok())
+}
+
+fn parse_with<T>(
+    errors: &mut Vec<String>,
+    setting: &str,
+    text: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+    let value = parse(text);
+    if value.is_none() {
+        errors.push(format!("{setting}: {text:?} is not valid"));
+    }
+    value
+}
@@ -1,34 +1,464 @@
-use db::{diesel::Connection, diesel_migrations::MigrationHarness};
-use web::diesel_async::{async_connection_wrapper::AsyncConnectionWrapper, AsyncPgConnection};
+mod config;
+
+use std::{
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use config::Config;
+use db::{
+    diesel::Connection,
+    diesel_migrations::MigrationHarness,
+    orm::{
+        CheckerKind, NewRejudge, PasswordScheme, Rejudge, RejudgeSubmission, Role,
+        SubmissionStatus, User, Verdict,
+    },
+};
+use judge::package::{ImportOptions, Importer};
+use language_module::LanguageRegistry;
+use language_module_c::LanguageModuleC;
+use language_module_cpp::LanguageModuleCpp;
+use language_module_go::LanguageModuleGo;
+use language_module_java::LanguageModuleJava;
+use language_module_javascript::LanguageModuleJavascript;
+use language_module_kotlin::LanguageModuleKotlin;
+use language_module_python::LanguageModulePython;
+use language_module_rust::LanguageModuleRust;
+use uuid::Uuid;
+use web::diesel_async::{
+    async_connection_wrapper::AsyncConnectionWrapper, AsyncConnection, AsyncPgConnection,
+};
+
+#[derive(Parser)]
+#[command(about = "Runs the solve.aleq.dev server and judge")]
+struct Cli {
+    /// TOML configuration file, `solve.toml` by default.
+    #[arg(long, global = true, env = "SOLVE_CONFIG")]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Applies pending migrations, then serves the web app and judges
+    /// submissions. The default.
+    All,
+    /// Serves the web app without judging.
+    Serve,
+    /// Judges submissions without serving the web app.
+    Judge,
+    /// Applies pending migrations.
+    Migrate {
+        /// Reverts the last applied migration instead.
+        #[arg(long, conflicts_with = "status")]
+        revert: bool,
+        /// Lists applied and pending migrations instead.
+        #[arg(long)]
+        status: bool,
+    },
+    /// Creates a user, for instance the first admin.
+    CreateUser {
+        username: String,
+        /// Read from stdin if not given.
+        #[arg(long)]
+        password: Option<String>,
+        /// One of admin, problem_setter, contest_manager or contestant.
+        #[arg(long, default_value = "contestant", value_parser = parse_role)]
+        role: Role,
+    },
+    /// Imports a problem from a Polygon or Kattis package, or a directory
+    /// of tests; either a directory or a zip archive.
+    ImportProblem {
+        path: PathBuf,
+        /// Overrides the package's title.
+        #[arg(long)]
+        title: Option<String>,
+        /// Overrides the package's time limit, 1000 by default.
+        #[arg(long)]
+        time_limit_ms: Option<i32>,
+        /// Overrides the package's memory limit, 256 by default.
+        #[arg(long)]
+        memory_limit_mb: Option<i64>,
+        /// Replaces the package's checker: one of exact, tokens,
+        /// case_insensitive or float.
+        #[arg(long, value_parser = parse_checker)]
+        checker: Option<CheckerKind>,
+    },
+    /// Exports a problem as a Kattis package: a zip archive if the path
+    /// ends with `.zip`, a directory otherwise.
+    ExportProblem { id: Uuid, path: PathBuf },
+    /// Queues finished submissions to be judged again: those matching every
+    /// given filter.
+    #[command(group = clap::ArgGroup::new("filters").required(true).multiple(true))]
+    Rejudge {
+        #[arg(long, group = "filters")]
+        problem: Option<Uuid>,
+        #[arg(long, group = "filters")]
+        contest: Option<Uuid>,
+        /// Such as WA or FAIL.
+        #[arg(long, group = "filters", value_parser = parse_verdict)]
+        verdict: Option<Verdict>,
+        #[arg(long, group = "filters")]
+        submission: Option<Uuid>,
+    },
+    /// Lists the submissions of a rejudge that changed verdict or points.
+    RejudgeReport { id: Uuid },
+}
 
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
 
-    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let cli = Cli::parse();
 
-    println!("Connecting to: {}", database_url);
+    let config = Config::load(cli.config.as_deref()).unwrap_or_else(|e| exit_with(e));
+    let database_url = &config.database_url;
 
-    let database_url_clone = database_url.clone();
+    println!("Connecting to: {}", config.redacted_database_url());
+
+    match cli.command.unwrap_or(Command::All) {
+        Command::All => {
+            let jwt_config = config.require_jwt().unwrap_or_else(|e| exit_with(e));
+            migrate(database_url, MigrationAction::Run).await;
+            upgrade_legacy_passwords(database_url).await;
+            tokio::join!(serve(&config, jwt_config), judge(&config));
+        }
+        Command::Serve => {
+            let jwt_config = config.require_jwt().unwrap_or_else(|e| exit_with(e));
+            serve(&config, jwt_config).await
+        }
+        Command::Judge => judge(&config).await,
+        Command::Migrate { revert, status } => {
+            let action = if revert {
+                MigrationAction::Revert
+            } else if status {
+                MigrationAction::Status
+            } else {
+                MigrationAction::Run
+            };
+            let upgrade = matches!(action, MigrationAction::Run);
+            migrate(database_url, action).await;
+            if upgrade {
+                upgrade_legacy_passwords(database_url).await;
+            }
+        }
+        Command::CreateUser {
+            username,
+            password,
+            role,
+        } => create_user(database_url, &username, password, role).await,
+        Command::ImportProblem {
+            path,
+            title,
+            time_limit_ms,
+            memory_limit_mb,
+            checker,
+        } => {
+            let options = ImportOptions {
+                title,
+                time_limit_ms,
+                memory_limit_bytes: memory_limit_mb.map(|mb| mb * 1024 * 1024),
+                checker,
+            };
+            import_problem(&config, &path, &options).await;
+        }
+        Command::ExportProblem { id, path } => export_problem(&config, id, &path).await,
+        Command::Rejudge {
+            problem,
+            contest,
+            verdict,
+            submission,
+        } => {
+            let new_rejudge = NewRejudge {
+                created_by: None,
+                problem_id: problem,
+                contest_id: contest,
+                verdict,
+                submission_id: submission,
+            };
+            rejudge(database_url, &new_rejudge).await;
+        }
+        Command::RejudgeReport { id } => rejudge_report(database_url, id).await,
+    }
+}
+
+enum MigrationAction {
+    Run,
+    Revert,
+    Status,
+}
+
+async fn migrate(database_url: &str, action: MigrationAction) {
+    let database_url = database_url.to_owned();
     tokio::task::spawn_blocking(move || {
         let mut temporary_connection_wrapper =
-            AsyncConnectionWrapper::<AsyncPgConnection>::establish(&database_url_clone)
+            AsyncConnectionWrapper::<AsyncPgConnection>::establish(&database_url)
                 .expect("Failed to establish temporary connection for migrations");
-        temporary_connection_wrapper
-            .run_pending_migrations(db::MIGRATIONS)
-            .expect("Failed to run migrations");
+
+        match action {
+            MigrationAction::Run => {
+                let applied = temporary_connection_wrapper
+                    .run_pending_migrations(db::MIGRATIONS)
+                    .expect("Failed to run migrations");
+                for version in applied {
+                    println!("Applied migration {version}");
+                }
+            }
+            MigrationAction::Revert => {
+                let version = temporary_connection_wrapper
+                    .revert_last_migration(db::MIGRATIONS)
+                    .expect("Failed to revert migration");
+                println!("Reverted migration {version}");
+            }
+            MigrationAction::Status => {
+                let applied = temporary_connection_wrapper
+                    .applied_migrations()
+                    .expect("Failed to list applied migrations");
+                let pending = temporary_connection_wrapper
+                    .pending_migrations(db::MIGRATIONS)
+                    .expect("Failed to list pending migrations");
+
+                let mut applied: Vec<_> = applied.iter().map(ToString::to_string).collect();
+                applied.sort();
+                for version in applied {
+                    println!("[x] {version}");
+                }
+                for migration in pending {
+                    println!("[ ] {}", migration.name());
+                }
+            }
+        }
     })
     .await
     .expect("Failed to execute migrations");
+}
+
+/// Rehashes the passwords stored before the server hashed them, which are
+/// not accepted at login until then.
+async fn upgrade_legacy_passwords(database_url: &str) {
+    let mut conn = AsyncPgConnection::establish(database_url)
+        .await
+        .expect("Failed to connect to the database");
 
+    let upgraded = web::auth::password::upgrade_legacy(&mut conn)
+        .await
+        .expect("Failed to rehash legacy passwords");
+    if upgraded > 0 {
+        println!("Rehashed {upgraded} legacy passwords");
+    }
+}
+
+async fn serve(config: &Config, jwt_config: web::auth::JWTConfig) {
+    let languages = build_language_registry();
     let state = web::AppState {
-        db: web::build_connection_pool(&database_url),
-        jwt_config: web::auth::JWTConfig::init(),
+        db: web::build_connection_pool(&config.database_url, config.pool_size),
+        config: config.web.clone(),
+        jwt_config,
+        importer: importer(config, &languages),
+        languages,
+    };
+
+    web::serve_web_app(state).await;
+}
+
+async fn judge(config: &Config) {
+    let judge_state = judge::JudgeState {
+        db: web::build_connection_pool(&config.database_url, config.pool_size),
+        languages: build_language_registry(),
+        sandbox: config.sandbox(),
+        config: config.judge.clone(),
+    };
+
+    judge::run_judge(judge_state).await;
+}
+
+async fn create_user(database_url: &str, username: &str, password: Option<String>, role: Role) {
+    let password = password.unwrap_or_else(|| {
+        print!("Password: ");
+        std::io::stdout().flush().ok();
+
+        let mut line = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .expect("Failed to read password");
+        line.trim_end_matches(['\r', '\n']).to_owned()
+    });
+
+    if password.is_empty() {
+        eprintln!("Password must not be empty");
+        std::process::exit(1);
+    }
+
+    // Hash the way the browser does, so the user can log in from it.
+    let (salt, client_hash) = auth::generate_secrets(&password).expect("Failed to hash password");
+    let password_hash = web::auth::password::hash(client_hash.as_bytes());
+
+    let mut conn = AsyncPgConnection::establish(database_url)
+        .await
+        .expect("Failed to connect to the database");
+
+    let user_exists = User::exists_with_username(&mut conn, username)
+        .await
+        .expect("Failed to look up user");
+    if user_exists {
+        eprintln!("User {username} already exists");
+        std::process::exit(1);
+    }
+
+    let user = User::create(
+        &mut conn,
+        username,
+        salt.as_str().as_bytes(),
+        password_hash.as_bytes(),
+        PasswordScheme::Argon2,
+    )
+    .await
+    .expect("Failed to create user");
+    User::set_role(&mut conn, user.id, role)
+        .await
+        .expect("Failed to set role");
+
+    println!("Created {role} {username} with id {}", user.id);
+}
+
+async fn import_problem(config: &Config, path: &Path, options: &ImportOptions) {
+    let mut conn = AsyncPgConnection::establish(&config.database_url)
+        .await
+        .expect("Failed to connect to the database");
+
+    let importer = importer(config, &build_language_registry());
+    match importer.import(&mut conn, path, options).await {
+        Ok(problem) => println!("Imported {} with id {}", problem.title, problem.id),
+        Err(e) => {
+            eprintln!("Failed to import {}: {e}", path.display());
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn export_problem(config: &Config, id: Uuid, path: &Path) {
+    let mut conn = AsyncPgConnection::establish(&config.database_url)
+        .await
+        .expect("Failed to connect to the database");
+
+    let languages = build_language_registry();
+    match judge::package::export(&mut conn, &config.judge.data_dir, &languages, id, path).await {
+        Ok(problem) => println!("Exported {} to {}", problem.title, path.display()),
+        Err(e) => {
+            eprintln!("Failed to export {id}: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn rejudge(database_url: &str, new_rejudge: &NewRejudge) {
+    let mut conn = AsyncPgConnection::establish(database_url)
+        .await
+        .expect("Failed to connect to the database");
+
+    match Rejudge::create(&mut conn, new_rejudge).await {
+        Ok((rejudge, queued)) => println!("Queued {queued} submissions in rejudge {}", rejudge.id),
+        Err(e) => exit_with(format!("Failed to rejudge: {e}")),
+    }
+}
+
+async fn rejudge_report(database_url: &str, id: Uuid) {
+    let mut conn = AsyncPgConnection::establish(database_url)
+        .await
+        .expect("Failed to connect to the database");
+
+    let rejudge = Rejudge::get(&mut conn, id)
+        .await
+        .expect("Failed to look up rejudge");
+    if rejudge.is_none() {
+        exit_with(format!("Rejudge {id} not found"));
+    }
+
+    let submissions = RejudgeSubmission::list_for_rejudge(&mut conn, id)
+        .await
+        .expect("Failed to look up rejudge");
+
+    let outcome_text = |verdict: Option<Verdict>, score: Option<f64>| {
+        let verdict = verdict.map_or("-", |verdict| verdict.as_str());
+        match score {
+            Some(score) => format!("{verdict} ({score})"),
+            None => verdict.to_owned(),
+        }
     };
+    let mut pending = 0;
+    let mut changed = 0;
+    for (before, submission) in &submissions {
+        if submission.status != SubmissionStatus::Finished {
+            pending += 1;
+        } else if before.is_changed(submission) {
+            changed += 1;
+            println!(
+                "{}: {} -> {}",
+                submission.id,
+                outcome_text(before.verdict, before.score),
+                outcome_text(submission.verdict, submission.score),
+            );
+        }
+    }
+
+    println!(
+        "{changed} of {} submissions changed, {pending} still being judged",
+        submissions.len()
+    );
+}
+
+fn exit_with(error: impl std::fmt::Display) -> ! {
+    eprintln!("{error}");
+    std::process::exit(1);
+}
+
+fn parse_role(text: &str) -> Result<Role, String> {
+    text.parse()
+}
+
+fn parse_verdict(text: &str) -> Result<Verdict, String> {
+    text.parse()
+}
+
+fn parse_checker(text: &str) -> Result<CheckerKind, String> {
+    match text.parse()? {
+        CheckerKind::Custom => Err("custom checkers cannot be imported this way".to_owned()),
+        checker => Ok(checker),
+    }
+}
+
+/// Imports share the judge's sandbox and directories.
+fn importer(config: &Config, languages: &LanguageRegistry) -> Importer {
+    Importer {
+        languages: languages.clone(),
+        sandbox: config.sandbox(),
+        workdir: config.judge.workdir.clone(),
+        data_dir: config.judge.data_dir.clone(),
+    }
+}
+
+fn build_language_registry() -> LanguageRegistry {
+    let mut languages = LanguageRegistry::default();
 
-    let web_app_handle = tokio::spawn(web::serve_web_app(state));
+    for variant in LanguageModuleCpp::variants() {
+        languages.register(variant);
+    }
+    for variant in LanguageModulePython::variants() {
+        languages.register(variant);
+    }
+    languages.register(LanguageModuleC::default());
+    languages.register(LanguageModuleRust::default());
+    languages.register(LanguageModuleJava::default());
+    languages.register(LanguageModuleKotlin::default());
+    languages.register(LanguageModuleGo::default());
+    languages.register(LanguageModuleJavascript::default());
 
-    let (web_app_result,) = tokio::join!(web_app_handle);
+    if languages.is_empty() {
+        eprintln!("No language toolchains found, submissions cannot be judged");
+    }
 
-    web_app_result.unwrap();
+    languages
 }
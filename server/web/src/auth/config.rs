@@ -1,19 +1,37 @@
 #[derive(Debug, Clone)]
 pub struct JWTConfig {
     pub secret: String,
-    pub expires_in: String,
+    /// Lifetime of access tokens.
+    pub expires_in: chrono::Duration,
+    /// Lifetime of refresh tokens, that is of a login, in minutes.
     pub maxage: i32,
 }
 
 impl JWTConfig {
-    pub fn init() -> JWTConfig {
-        let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-        let expires_in = std::env::var("JWT_EXPIRED_IN").expect("JWT_EXPIRED_IN must be set");
-        let maxage = std::env::var("JWT_MAXAGE").expect("JWT_MAXAGE must be set");
-        Self {
+    /// Takes `expires_in` as a duration such as `15m`, see
+    /// [`parse_duration`](crate::config::parse_duration).
+    pub fn new(secret: String, expires_in: &str, maxage: i32) -> Result<JWTConfig, String> {
+        if secret.is_empty() {
+            return Err("the JWT secret must not be empty".to_owned());
+        }
+        if maxage <= 0 {
+            return Err(format!("the JWT max age must be positive, not {maxage}"));
+        }
+
+        let expires_in = crate::config::parse_duration(expires_in)
+            .and_then(|duration| chrono::Duration::from_std(duration).ok())
+            .ok_or_else(|| {
+                format!("{expires_in:?} is not a duration such as 30s, 15m, 1h or 7d")
+            })?;
+
+        Ok(Self {
             secret,
             expires_in,
-            maxage: maxage.parse::<i32>().unwrap(),
-        }
+            maxage,
+        })
+    }
+
+    pub fn refresh_expires_in(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.maxage.into())
     }
 }
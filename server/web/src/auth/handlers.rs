@@ -1,15 +1,19 @@
 use std::sync::Arc;
 
-use super::model::{GetSaltSchema, LoginUserSchema, RegisterUserSchema, GetMeSchema, TokenClaims};
+use super::model::{
+    ChangePasswordSchema, GetMeSchema, GetSaltSchema, LoginUserSchema, RefreshSchema,
+    RegisterUserSchema, TokenClaims,
+};
+use super::{password, tokens};
 use crate::AppState;
 use axum::{
     extract::State,
-    http::{header, Response, StatusCode},
+    http::{Response, StatusCode},
     response::IntoResponse,
     Extension, Json, body::Body,
 };
-use axum_extra::extract::cookie::{Cookie, SameSite};
-use jsonwebtoken::{encode, EncodingKey, Header};
+use axum_extra::extract::cookie::CookieJar;
+use db::orm::RefreshToken;
 use serde_json::json;
 
 pub async fn register_user_handler(
@@ -29,46 +33,28 @@ pub async fn register_user_handler(
 
     let salt = body.salt;
     let hashed_password = body.hashed_password;
+    let password_hash =
+        tokio::task::spawn_blocking(move || password::hash(hashed_password.as_bytes()))
+            .await
+            .unwrap();
 
     let user = db::orm::User::create(
         &mut conn,
         &body.username,
         salt.as_bytes(),
-        hashed_password.as_bytes(),
+        password_hash.as_bytes(),
+        db::orm::PasswordScheme::Argon2,
     )
     .await
     .map_err(crate::errors::database_error)
     .map_err(IntoResponse::into_response)?;
 
-    let now = chrono::Utc::now();
-    let iat = now.timestamp() as usize;
-    let exp = (now + chrono::Duration::minutes(60)).timestamp() as usize;
-    let claims: TokenClaims = TokenClaims {
-        sub: user.id.to_string(),
-        exp,
-        iat,
-    };
-
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.jwt_config.secret.as_bytes()),
-    )
-    .unwrap();
-
-    let cookie = Cookie::build(("token", token.to_owned()))
-        .path("/")
-        .max_age(time::Duration::hours(1))
-        .same_site(SameSite::Lax)
-        .http_only(true);
-
-    let mut response = Response::new(json!({"status": "success", "token": token}).to_string());
-    let headers = response.headers_mut();
-        
-    headers.insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
-    headers.insert("HX-Trigger", "reload".parse().unwrap());
+    let tokens = tokens::issue(&state, &mut conn, user.id, uuid::Uuid::new_v4())
+        .await
+        .map_err(crate::errors::database_error)
+        .map_err(IntoResponse::into_response)?;
 
-    Ok(response)
+    Ok(tokens::response(&state, &tokens))
 }
 
 pub async fn login_user_handler(
@@ -83,41 +69,24 @@ pub async fn login_user_handler(
         .map_err(IntoResponse::into_response)?
         .ok_or_else(crate::errors::invalid_username_or_password_htmx)?;
 
-    let is_valid = user.password_hash == body.hashed_password.as_bytes();
+    let hashed_password = body.hashed_password;
+    let (user, is_valid) = tokio::task::spawn_blocking(move || {
+        let is_valid = password::verify(&user, hashed_password.as_bytes());
+        (user, is_valid)
+    })
+    .await
+    .unwrap();
 
     if !is_valid {
         return Err(crate::errors::invalid_username_or_password_htmx());
     }
 
-    let now = chrono::Utc::now();
-    let iat = now.timestamp() as usize;
-    let exp = (now + chrono::Duration::minutes(60)).timestamp() as usize;
-    let claims: TokenClaims = TokenClaims {
-        sub: user.id.to_string(),
-        exp,
-        iat,
-    };
-
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.jwt_config.secret.as_bytes()),
-    )
-    .unwrap();
-
-    let cookie = Cookie::build(("token", token.to_owned()))
-        .path("/")
-        .max_age(time::Duration::hours(1))
-        .same_site(SameSite::Lax)
-        .http_only(true);
-
-    let mut response = Response::new(json!({"status": "success", "token": token}).to_string());
-    let headers = response.headers_mut();
-
-    headers.insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
-    headers.insert("HX-Trigger", "reload".parse().unwrap());
+    let tokens = tokens::issue(&state, &mut conn, user.id, uuid::Uuid::new_v4())
+        .await
+        .map_err(crate::errors::database_error)
+        .map_err(IntoResponse::into_response)?;
 
-    Ok(response)
+    Ok(tokens::response(&state, &tokens))
 }
 
 pub async fn get_salt_handler(
@@ -143,20 +112,108 @@ pub async fn get_salt_handler(
     Ok::<_, Response<Body>>(response)
 }
 
-pub async fn logout_handler() -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let cookie = Cookie::build(("token", ""))
-        .path("/")
-        .max_age(time::Duration::hours(-1))
-        .same_site(SameSite::Lax)
-        .http_only(true);
+pub async fn refresh_handler(
+    cookie_jar: CookieJar,
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<RefreshSchema>>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let mut conn = state.db.get().await.unwrap();
+
+    let token = cookie_jar
+        .get(tokens::REFRESH_TOKEN_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .or_else(|| body.and_then(|Json(body)| body.refresh_token));
+
+    let token = token.ok_or_else(crate::errors::missing_token)?;
+    let row = tokens::refresh_row(&state, &mut conn, &token).await?;
+
+    // Rotating settles which of two concurrent exchanges of the same token
+    // came first; the other one revokes the family too.
+    let rotated = tokens::exchange(&row) == tokens::Exchange::Rotate
+        && RefreshToken::rotate(&mut conn, row.id)
+            .await
+            .map_err(crate::errors::database_error)?;
+    if !rotated {
+        RefreshToken::revoke_family(&mut conn, row.family_id)
+            .await
+            .map_err(crate::errors::database_error)?;
+        return Err(crate::errors::invalid_token());
+    }
+
+    let tokens = tokens::issue(&state, &mut conn, row.user_id, row.family_id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(tokens::response(&state, &tokens))
+}
 
+pub async fn logout_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let mut conn = state.db.get().await.unwrap();
+
+    let jti = uuid::Uuid::parse_str(&claims.jti).map_err(|_| crate::errors::invalid_token())?;
+    let row = RefreshToken::get(&mut conn, jti)
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::invalid_token)?;
+
+    RefreshToken::revoke_family(&mut conn, row.family_id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(logged_out_response())
+}
+
+pub async fn change_password_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<db::orm::User>,
+    Json(body): Json<ChangePasswordSchema>,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let mut conn = state.db.get().await.unwrap();
+
+    let user_id = user.id;
+    let hashed_password = body.hashed_password;
+    let new_hashed_password = body.new_hashed_password;
+    let new_password_hash = tokio::task::spawn_blocking(move || {
+        password::verify(&user, hashed_password.as_bytes())
+            .then(|| password::hash(new_hashed_password.as_bytes()))
+    })
+    .await
+    .unwrap();
+
+    let new_password_hash =
+        new_password_hash.ok_or_else(crate::errors::invalid_username_or_password_htmx)?;
+
+    db::orm::User::set_password(
+        &mut conn,
+        user_id,
+        body.new_salt.as_bytes(),
+        new_password_hash.as_bytes(),
+        db::orm::PasswordScheme::Argon2,
+    )
+    .await
+    .map_err(crate::errors::database_error)
+    .map_err(IntoResponse::into_response)?;
+
+    // Every session was started with the old password, this one included.
+    RefreshToken::revoke_all_for_user(&mut conn, user_id)
+        .await
+        .map_err(crate::errors::database_error)
+        .map_err(IntoResponse::into_response)?;
+
+    Ok::<_, Response<Body>>(logged_out_response())
+}
+
+fn logged_out_response() -> Response<String> {
     let mut response = Response::new(json!({"status": "success"}).to_string());
     let headers = response.headers_mut();
 
-    headers.insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
+    tokens::clear_cookies(headers);
     headers.insert("HX-Trigger", "reload".parse().unwrap());
 
-    Ok(response)
+    response
 }
 
 pub async fn get_me_handler(
@@ -164,19 +221,19 @@ pub async fn get_me_handler(
     Json(body): Json<GetMeSchema>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
   match body.query.as_str() {
-    "all" => return Ok(Json(serde_json::json!({
+    "all" => Ok(Json(serde_json::json!({
       "status":  "success",
       "data": serde_json::json!({
           "user": filter_user_record(&user)
       })
     })).into_response()),
-    "username" => return Ok(user.username.into_response()),
+    "username" => Ok(user.username.into_response()),
     _ => {
       let error_response = serde_json::json!({
         "status": "error",
         "message": "Invalid query",
       });
-      return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+      Err((StatusCode::BAD_REQUEST, Json(error_response)))
     }
   }
 }
@@ -185,11 +242,13 @@ pub async fn get_me_handler(
 pub struct FilteredUser<'a> {
     pub id: uuid::Uuid,
     pub username: &'a str,
+    pub role: &'static str,
 }
 
 fn filter_user_record(user: &db::orm::User) -> FilteredUser<'_> {
     FilteredUser {
         id: user.id,
         username: &user.username,
+        role: user.role.as_str(),
     }
 }
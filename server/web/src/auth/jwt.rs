@@ -3,9 +3,8 @@ use std::sync::Arc;
 use axum::{extract::State, http::header, middleware::Next, response::IntoResponse};
 
 use axum_extra::extract::cookie::CookieJar;
-use jsonwebtoken::{decode, DecodingKey, Validation};
 
-use crate::{auth::model::TokenClaims, AppState};
+use crate::{auth::tokens, AppState};
 
 #[derive(Clone, Copy)]
 pub struct UserLoggedIn(pub bool);
@@ -17,43 +16,25 @@ pub async fn jwt_layer(
     mut req: axum::extract::Request,
     next: Next,
 ) -> Result<impl IntoResponse, crate::errors::TyJson> {
-    let mut conn = &mut data.db.get().await.unwrap();
+    let conn = &mut data.db.get().await.unwrap();
 
     let token = cookie_jar
-        .get("token")
+        .get(tokens::ACCESS_TOKEN_COOKIE)
         .map(|cookie| cookie.value().to_string())
         .or_else(|| {
             req.headers()
                 .get(header::AUTHORIZATION)
                 .and_then(|auth_header| auth_header.to_str().ok())
-                .and_then(|auth_value| {
-                    if auth_value.starts_with("Bearer ") {
-                        Some(auth_value[7..].to_owned())
-                    } else {
-                        None
-                    }
-                })
+                .and_then(|auth_value| auth_value.strip_prefix("Bearer "))
+                .map(str::to_owned)
         });
 
     let token = token.ok_or_else(crate::errors::missing_token)?;
 
-    let claims = decode::<TokenClaims>(
-        &token,
-        &DecodingKey::from_secret(data.jwt_config.secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|_| crate::errors::invalid_token())?
-    .claims;
-
-    let user_id = uuid::Uuid::parse_str(&claims.sub).map_err(|_| crate::errors::invalid_token())?;
-
-    let user = db::orm::User::get(&mut conn, user_id)
-        .await
-        .map_err(crate::errors::database_error)?;
-
-    let user = user.ok_or_else(crate::errors::invalid_token)?;
+    let (user, claims) = tokens::authenticate(&data, conn, &token).await?;
 
     req.extensions_mut().insert(user);
+    req.extensions_mut().insert(claims);
     Ok(next.run(req).await)
 }
 
@@ -63,40 +44,25 @@ pub async fn jwt_layer_boolean(
   mut req: axum::extract::Request,
   next: Next,
 ) -> Result<impl IntoResponse, crate::errors::TyJson> {
-  let mut conn = &mut data.db.get().await.unwrap();
+  let conn = &mut data.db.get().await.unwrap();
 
   let token = cookie_jar
-      .get("token")
+      .get(tokens::ACCESS_TOKEN_COOKIE)
       .map(|cookie| cookie.value().to_string())
       .or_else(|| {
           req.headers()
               .get(header::AUTHORIZATION)
               .and_then(|auth_header| auth_header.to_str().ok())
-              .and_then(|auth_value| {
-                  if auth_value.starts_with("Bearer ") {
-                      Some(auth_value[7..].to_owned())
-                  } else {
-                      None
-                  }
-              })
+              .and_then(|auth_value| auth_value.strip_prefix("Bearer "))
+              .map(str::to_owned)
       });
 
   if let Some(token) = token {
-    let claims = decode::<TokenClaims>(
-        &token,
-        &DecodingKey::from_secret(data.jwt_config.secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|_| crate::errors::invalid_token())?
-    .claims;
-
-    if let Ok(user_id) = uuid::Uuid::parse_str(&claims.sub) {
-      if let Ok(_) = db::orm::User::get(&mut conn, user_id)
-      .await
-      .map_err(crate::errors::database_error) {
-        req.extensions_mut().insert(UserLoggedIn(true));
-        return Ok(next.run(req).await);
-      }
+    // An expired or revoked token just means the user is logged out.
+    if let Ok((user, _)) = tokens::authenticate(&data, conn, &token).await {
+      req.extensions_mut().insert(user);
+      req.extensions_mut().insert(UserLoggedIn(true));
+      return Ok(next.run(req).await);
     }
   };
 
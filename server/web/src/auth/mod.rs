@@ -2,7 +2,11 @@ pub mod config;
 pub mod handlers;
 pub mod jwt;
 pub mod model;
+pub mod password;
+pub mod permissions;
+pub mod tokens;
 
 pub use config::JWTConfig;
 pub use handlers::*;
 pub use jwt::{jwt_layer, jwt_layer_boolean};
+pub use permissions::{require_permission, set_role_handler, Permission};
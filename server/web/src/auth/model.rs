@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: String,
     pub iat: usize,
     pub exp: usize,
+    /// Id of the refresh token row the token was issued with.
+    pub jti: String,
+    /// Either `access` or `refresh`.
+    pub typ: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,6 +29,18 @@ pub struct GetSaltSchema {
     pub username: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshSchema {
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordSchema {
+    pub hashed_password: String,
+    pub new_salt: String,
+    pub new_hashed_password: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetMeSchema {
     pub query: String,
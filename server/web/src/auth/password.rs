@@ -0,0 +1,70 @@
+//! Server-side password hashing.
+//!
+//! The client hashes the password before sending it (see the `auth` crate),
+//! but that hash is as good as the password to anyone who can replay it. The
+//! server therefore only stores an Argon2 hash of what the client sends.
+//!
+//! Rows from before that stored the client-side hash as is. They are turned
+//! into Argon2 ones by [`upgrade_legacy`], which hashes the stored value just
+//! as if the client had sent it, so no row has to be trusted as is.
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use db::{
+    diesel::QueryResult,
+    orm::{PasswordScheme, User},
+};
+use diesel_async::AsyncPgConnection;
+use rand_core::OsRng;
+
+/// Hashes the client-side hash of a password into a PHC string to store.
+pub fn hash(client_hash: &[u8]) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(client_hash, &salt)
+        .expect("Argon2 with default parameters cannot fail")
+        .to_string()
+}
+
+/// Checks the client-side hash of a password against what is stored for
+/// `user`. Legacy rows are never accepted: a leaked one would be enough to
+/// log in.
+pub fn verify(user: &User, client_hash: &[u8]) -> bool {
+    match user.password_scheme {
+        PasswordScheme::Legacy => false,
+        PasswordScheme::Argon2 => std::str::from_utf8(&user.password_hash)
+            .ok()
+            .and_then(|hash| PasswordHash::new(hash).ok())
+            .is_some_and(|hash| {
+                Argon2::default()
+                    .verify_password(client_hash, &hash)
+                    .is_ok()
+            }),
+    }
+}
+
+/// Rehashes every `Legacy` row with Argon2, returning how many there were.
+pub async fn upgrade_legacy(conn: &mut AsyncPgConnection) -> QueryResult<usize> {
+    let users = User::list_by_password_scheme(conn, PasswordScheme::Legacy).await?;
+
+    for user in &users {
+        // The stored value is what the client sends.
+        let stored = user.password_hash.clone();
+        let password_hash = tokio::task::spawn_blocking(move || hash(&stored))
+            .await
+            .expect("Argon2 hashing panicked");
+
+        User::set_password_hash(
+            conn,
+            user.id,
+            password_hash.as_bytes(),
+            PasswordScheme::Argon2,
+        )
+        .await?;
+    }
+
+    Ok(users.len())
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    middleware::Next,
+    response::IntoResponse,
+    Extension, Json,
+};
+use db::orm::{Role, User};
+use serde_json::json;
+
+use crate::AppState;
+
+/// Something only some roles may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ManageUsers,
+    AuthorProblems,
+    Rejudge,
+    ManageContests,
+}
+
+impl Permission {
+    pub fn granted_to(self, role: Role) -> bool {
+        match role {
+            Role::Admin => true,
+            Role::ProblemSetter => matches!(self, Self::AuthorProblems | Self::Rejudge),
+            Role::ContestManager => matches!(self, Self::ManageContests | Self::Rejudge),
+            Role::Contestant => false,
+        }
+    }
+}
+
+/// Lets the request through only if the user has the permission given as
+/// state. Goes inside [`jwt_layer`](super::jwt_layer), which finds the user:
+///
+/// ```ignore
+/// post(handler)
+///     .route_layer(middleware::from_fn_with_state(
+///         Permission::AuthorProblems,
+///         auth::require_permission,
+///     ))
+///     .route_layer(middleware::from_fn_with_state(state.clone(), auth::jwt_layer))
+/// ```
+pub async fn require_permission(
+    State(permission): State<Permission>,
+    Extension(user): Extension<User>,
+    req: axum::extract::Request,
+    next: Next,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    if !permission.granted_to(user.role) {
+        return Err(crate::errors::forbidden());
+    }
+
+    Ok(next.run(req).await)
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SetRoleSchema {
+    pub role: String,
+}
+
+pub async fn set_role_handler(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<uuid::Uuid>,
+    Json(body): Json<SetRoleSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let mut conn = state.db.get().await.unwrap();
+
+    let role = body
+        .role
+        .parse::<Role>()
+        .map_err(|_| crate::errors::unknown_role())?;
+
+    let updated = User::set_role(&mut conn, user_id, role)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    if updated == 0 {
+        return Err(crate::errors::user_not_found());
+    }
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": {
+        "id": user_id,
+        "role": role.as_str(),
+      }
+    })))
+}
@@ -0,0 +1,298 @@
+//! Access and refresh tokens.
+//!
+//! A login issues a short-lived access token and a refresh token that can be
+//! exchanged once for a new pair. Both carry the id of a
+//! [`RefreshToken`] row as their `jti`; revoking the row, or the whole
+//! family of rows descending from the login, makes both stop working.
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Response},
+};
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use chrono::{DateTime, Utc};
+use db::{
+    diesel::QueryResult,
+    orm::{RefreshToken, User},
+};
+use diesel_async::AsyncPgConnection;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{config::JWTConfig, model::TokenClaims};
+use crate::AppState;
+
+pub const ACCESS_TOKEN_COOKIE: &str = "token";
+pub const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
+/// Refresh tokens are only ever sent to the auth endpoints.
+const REFRESH_TOKEN_PATH: &str = "/api/auth";
+
+const ACCESS: &str = "access";
+const REFRESH: &str = "refresh";
+
+pub struct Tokens {
+    pub access: String,
+    pub refresh: String,
+}
+
+/// Issues a new pair of tokens for `user_id`, continuing the login
+/// `family_id`.
+pub async fn issue(
+    state: &AppState,
+    conn: &mut AsyncPgConnection,
+    user_id: Uuid,
+    family_id: Uuid,
+) -> QueryResult<Tokens> {
+    let config = &state.jwt_config;
+    let now = chrono::Utc::now();
+
+    RefreshToken::delete_expired_for_user(conn, user_id).await?;
+    let row =
+        RefreshToken::create(conn, user_id, family_id, now + config.refresh_expires_in()).await?;
+
+    Ok(sign(config, &row, now))
+}
+
+/// The tokens of `row`, issued at `now`.
+fn sign(config: &JWTConfig, row: &RefreshToken, now: DateTime<Utc>) -> Tokens {
+    let claims = |typ: &str, expires_in: chrono::Duration| TokenClaims {
+        sub: row.user_id.to_string(),
+        iat: now.timestamp() as usize,
+        exp: (now + expires_in).timestamp() as usize,
+        jti: row.id.to_string(),
+        typ: typ.to_owned(),
+    };
+    let key = EncodingKey::from_secret(config.secret.as_bytes());
+
+    Tokens {
+        access: encode(&Header::default(), &claims(ACCESS, config.expires_in), &key).unwrap(),
+        refresh: encode(
+            &Header::default(),
+            &claims(REFRESH, config.refresh_expires_in()),
+            &key,
+        )
+        .unwrap(),
+    }
+}
+
+/// The user an access token belongs to, unless the token is invalid,
+/// expired or revoked.
+pub async fn authenticate(
+    state: &AppState,
+    conn: &mut AsyncPgConnection,
+    token: &str,
+) -> Result<(User, TokenClaims), crate::errors::TyJson> {
+    let claims =
+        decode_claims(&state.jwt_config, token, ACCESS).ok_or_else(crate::errors::invalid_token)?;
+    let (user_id, row) = active_row(conn, &claims).await?;
+
+    let user = User::get(conn, user_id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .filter(|user| user.id == row.user_id)
+        .ok_or_else(crate::errors::invalid_token)?;
+
+    Ok((user, claims))
+}
+
+/// The refresh token row a refresh token stands for, unless the token is
+/// invalid, expired or revoked.
+pub async fn refresh_row(
+    state: &AppState,
+    conn: &mut AsyncPgConnection,
+    token: &str,
+) -> Result<RefreshToken, crate::errors::TyJson> {
+    let claims = decode_claims(&state.jwt_config, token, REFRESH)
+        .ok_or_else(crate::errors::invalid_token)?;
+    let (_, row) = active_row(conn, &claims).await?;
+    Ok(row)
+}
+
+fn decode_claims(config: &JWTConfig, token: &str, typ: &str) -> Option<TokenClaims> {
+    let claims = decode::<TokenClaims>(
+        token,
+        &DecodingKey::from_secret(config.secret.as_bytes()),
+        &Validation::default(),
+    )
+    .ok()?
+    .claims;
+
+    (claims.typ == typ).then_some(claims)
+}
+
+async fn active_row(
+    conn: &mut AsyncPgConnection,
+    claims: &TokenClaims,
+) -> Result<(Uuid, RefreshToken), crate::errors::TyJson> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| crate::errors::invalid_token())?;
+    let jti = Uuid::parse_str(&claims.jti).map_err(|_| crate::errors::invalid_token())?;
+
+    let row = RefreshToken::get(conn, jti)
+        .await
+        .map_err(crate::errors::database_error)?
+        .filter(|row| is_active(row, user_id))
+        .ok_or_else(crate::errors::invalid_token)?;
+
+    Ok((user_id, row))
+}
+
+/// Whether `row` still backs the tokens of `user_id`.
+fn is_active(row: &RefreshToken, user_id: Uuid) -> bool {
+    row.user_id == user_id && row.revoked_at.is_none()
+}
+
+/// What presenting the refresh token of an active `row` leads to.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Exchange {
+    /// A new pair of tokens in the same family.
+    Rotate,
+    /// The token is only good once, so seeing it again means it was stolen
+    /// and the whole login goes.
+    RevokeFamily,
+}
+
+pub fn exchange(row: &RefreshToken) -> Exchange {
+    if row.rotated_at.is_some() {
+        Exchange::RevokeFamily
+    } else {
+        Exchange::Rotate
+    }
+}
+
+/// Response to a successful login or refresh: the tokens in the body and as
+/// cookies, and a `reload` trigger for htmx.
+pub fn response(state: &AppState, tokens: &Tokens) -> Response<Body> {
+    let config = &state.jwt_config;
+
+    let access_cookie = Cookie::build((ACCESS_TOKEN_COOKIE, tokens.access.clone()))
+        .path("/")
+        .max_age(cookie_age(config.expires_in))
+        .same_site(SameSite::Lax)
+        .secure(state.config.secure_cookies)
+        .http_only(true);
+    let refresh_cookie = Cookie::build((REFRESH_TOKEN_COOKIE, tokens.refresh.clone()))
+        .path(REFRESH_TOKEN_PATH)
+        .max_age(cookie_age(config.refresh_expires_in()))
+        .same_site(SameSite::Lax)
+        .secure(state.config.secure_cookies)
+        .http_only(true);
+
+    let mut response = Response::new(Body::from(
+        json!({
+            "status": "success",
+            "token": tokens.access,
+            "refresh_token": tokens.refresh,
+        })
+        .to_string(),
+    ));
+    let headers = response.headers_mut();
+
+    headers.append(header::SET_COOKIE, cookie_header(&access_cookie.build()));
+    headers.append(header::SET_COOKIE, cookie_header(&refresh_cookie.build()));
+    headers.insert("HX-Trigger", "reload".parse().unwrap());
+
+    response
+}
+
+/// Makes the browser forget both tokens.
+pub fn clear_cookies(headers: &mut HeaderMap) {
+    for (name, path) in [
+        (ACCESS_TOKEN_COOKIE, "/"),
+        (REFRESH_TOKEN_COOKIE, REFRESH_TOKEN_PATH),
+    ] {
+        let cookie = Cookie::build((name, ""))
+            .path(path)
+            .max_age(time::Duration::hours(-1))
+            .same_site(SameSite::Lax)
+            .http_only(true);
+        headers.append(header::SET_COOKIE, cookie_header(&cookie.build()));
+    }
+}
+
+fn cookie_age(duration: chrono::Duration) -> time::Duration {
+    time::Duration::seconds(duration.num_seconds())
+}
+
+fn cookie_header(cookie: &Cookie<'_>) -> HeaderValue {
+    cookie.to_string().parse().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> JWTConfig {
+        JWTConfig::new("secret".to_owned(), "15m", 60 * 24).unwrap()
+    }
+
+    fn row() -> RefreshToken {
+        let now = Utc::now();
+        RefreshToken {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            family_id: Uuid::new_v4(),
+            expires_at: now + config().refresh_expires_in(),
+            rotated_at: None,
+            revoked_at: None,
+            created_at: now,
+        }
+    }
+
+    #[test]
+    fn tokens_carry_their_row_and_lifetimes() {
+        let config = config();
+        let row = row();
+        let now = Utc::now();
+        let tokens = sign(&config, &row, now);
+
+        let access = decode_claims(&config, &tokens.access, ACCESS).unwrap();
+        assert_eq!(access.sub, row.user_id.to_string());
+        assert_eq!(access.jti, row.id.to_string());
+        assert_eq!(access.exp, (now.timestamp() + 15 * 60) as usize);
+
+        let refresh = decode_claims(&config, &tokens.refresh, REFRESH).unwrap();
+        assert_eq!(refresh.jti, row.id.to_string());
+        assert_eq!(refresh.exp, (now.timestamp() + 24 * 60 * 60) as usize);
+    }
+
+    #[test]
+    fn tokens_are_not_interchangeable() {
+        let config = config();
+        let tokens = sign(&config, &row(), Utc::now());
+
+        assert!(decode_claims(&config, &tokens.access, REFRESH).is_none());
+        assert!(decode_claims(&config, &tokens.refresh, ACCESS).is_none());
+    }
+
+    #[test]
+    fn rejects_foreign_and_expired_tokens() {
+        let config = config();
+        let foreign = JWTConfig::new("other".to_owned(), "15m", 60).unwrap();
+        let tokens = sign(&foreign, &row(), Utc::now());
+        assert!(decode_claims(&config, &tokens.access, ACCESS).is_none());
+
+        let tokens = sign(&config, &row(), Utc::now() - chrono::Duration::hours(1));
+        assert!(decode_claims(&config, &tokens.access, ACCESS).is_none());
+        assert!(decode_claims(&config, &tokens.refresh, REFRESH).is_some());
+    }
+
+    #[test]
+    fn revoked_rows_are_inactive() {
+        let mut row = row();
+        assert!(is_active(&row, row.user_id));
+        assert!(!is_active(&row, Uuid::new_v4()));
+
+        row.revoked_at = Some(Utc::now());
+        assert!(!is_active(&row, row.user_id));
+    }
+
+    #[test]
+    fn reuse_revokes_the_family() {
+        let mut row = row();
+        assert_eq!(exchange(&row), Exchange::Rotate);
+
+        row.rotated_at = Some(Utc::now());
+        assert_eq!(exchange(&row), Exchange::RevokeFamily);
+    }
+}
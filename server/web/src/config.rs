@@ -0,0 +1,53 @@
+use std::{net::SocketAddr, time::Duration};
+
+use axum::http::HeaderValue;
+
+/// How the web app is exposed.
+#[derive(Debug, Clone)]
+pub struct WebConfig {
+    pub bind: SocketAddr,
+    /// Address users reach the app at, such as `https://solve.aleq.dev`.
+    pub public_url: String,
+    /// Origins allowed to call the API with credentials.
+    pub cors_origins: Vec<HeaderValue>,
+    /// Send cookies over HTTPS only.
+    pub secure_cookies: bool,
+    /// How long a session lives without requests.
+    pub session_expiry: Duration,
+    /// Custom invocations a user may start in a minute.
+    pub runs_per_minute: u32,
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 32055)),
+            public_url: "http://localhost:32055".to_owned(),
+            cors_origins: vec![HeaderValue::from_static("http://localhost:32055")],
+            secure_cookies: false,
+            session_expiry: Duration::from_secs(10),
+            runs_per_minute: 10,
+        }
+    }
+}
+
+/// Parses a number of seconds, minutes, hours or days such as `15m`. A bare
+/// number is taken as minutes, like `JWT_MAXAGE`.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => text.split_at(index),
+        None => (text, "m"),
+    };
+    let number = number.parse::<u64>().ok()?;
+
+    let seconds = match unit {
+        "s" => number,
+        "m" => number.checked_mul(60)?,
+        "h" => number.checked_mul(60 * 60)?,
+        "d" => number.checked_mul(24 * 60 * 60)?,
+        _ => return None,
+    };
+
+    Some(Duration::from_secs(seconds))
+}
@@ -0,0 +1,693 @@
+pub mod scoreboard;
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use db::{
+    diesel::result::{DatabaseErrorKind, Error as DieselError},
+    orm::{
+        Contest, ContestParticipant, ContestProblem, ContestRules, ContestScoring, Feedback,
+        NewContest, Problem, Submission, SubtaskResult, Team, TeamMember, User,
+    },
+};
+use diesel_async::AsyncPgConnection;
+use serde_json::json;
+
+use crate::{auth::Permission, templates, AppState};
+
+/// Scoreboards of ICPC contests freeze for the last hour by default, those
+/// of IOI contests do not.
+const DEFAULT_ICPC_FREEZE_MINUTES: i32 = 60;
+const DEFAULT_PENALTY_MINUTES: i32 = 20;
+const MAX_TITLE_LEN: usize = 256;
+const MAX_LETTER_LEN: usize = 8;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateContestSchema {
+    pub title: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub freeze_minutes: Option<i32>,
+    pub penalty_minutes: Option<i32>,
+    /// `icpc` by default.
+    pub rules: Option<String>,
+    /// `best_subtasks` by default.
+    pub scoring: Option<String>,
+    /// `full` by default.
+    pub feedback: Option<String>,
+    pub submission_limit: Option<i32>,
+    pub submission_delay_seconds: Option<i32>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RegisterQuery {
+    /// Registers this team of the user instead of the user alone.
+    pub team_id: Option<uuid::Uuid>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AddContestProblemSchema {
+    pub problem_id: uuid::Uuid,
+    pub letter: String,
+}
+
+pub async fn list_contests_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contests = Contest::list(conn)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "contests": contests.iter().map(filter_contest_record).collect::<Vec<_>>()
+      })
+    })))
+}
+
+pub async fn create_contest_handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateContestSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let rules = parse_or(body.rules.as_deref(), ContestRules::Icpc)?;
+    let default_freeze_minutes = match rules {
+        ContestRules::Icpc => DEFAULT_ICPC_FREEZE_MINUTES,
+        ContestRules::Ioi => 0,
+    };
+    let new_contest = NewContest {
+        title: body.title.trim(),
+        starts_at: body.starts_at,
+        ends_at: body.ends_at,
+        freeze_minutes: body.freeze_minutes.unwrap_or(default_freeze_minutes),
+        penalty_minutes: body.penalty_minutes.unwrap_or(DEFAULT_PENALTY_MINUTES),
+        rules,
+        scoring: parse_or(body.scoring.as_deref(), ContestScoring::BestSubtasks)?,
+        feedback: parse_or(body.feedback.as_deref(), Feedback::Full)?,
+        submission_limit: body.submission_limit,
+        submission_delay_seconds: body.submission_delay_seconds.unwrap_or(0),
+    };
+    if new_contest.title.is_empty() || new_contest.title.chars().count() > MAX_TITLE_LEN {
+        return Err(crate::errors::invalid_contest(
+            "Title must be non-empty and at most 256 characters long",
+        ));
+    }
+    if new_contest.starts_at >= new_contest.ends_at {
+        return Err(crate::errors::invalid_contest(
+            "The contest must start before it ends",
+        ));
+    }
+    if new_contest.freeze_minutes < 0 || new_contest.penalty_minutes < 0 {
+        return Err(crate::errors::invalid_contest(
+            "Freeze and penalty minutes must not be negative",
+        ));
+    }
+    if new_contest.submission_limit.is_some_and(|limit| limit <= 0)
+        || new_contest.submission_delay_seconds < 0
+    {
+        return Err(crate::errors::invalid_contest(
+            "Submission limit must be positive and submission delay not negative",
+        ));
+    }
+
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = Contest::create(conn, &new_contest)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+          "status": "success",
+          "data": json!({
+              "contest": filter_contest_record(&contest)
+          })
+        })),
+    ))
+}
+
+/// A contest with its problems, which only contest managers see before it
+/// starts.
+pub async fn get_contest_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = get_contest(conn, id).await?;
+    let registered = ContestParticipant::exists(conn, id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    let problems = if can_see_problems(&contest, Some(&user)) {
+        list_problems(conn, id).await?
+    } else {
+        Vec::new()
+    };
+    let problems: Vec<_> = problems
+        .iter()
+        .map(|(contest_problem, problem)| {
+            json!({
+                "letter": contest_problem.letter,
+                "problem_id": problem.id,
+                "title": problem.title,
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "contest": filter_contest_record(&contest),
+          "problems": problems,
+          "registered": registered
+      })
+    })))
+}
+
+pub async fn add_contest_problem_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<uuid::Uuid>,
+    Json(body): Json<AddContestProblemSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let letter = body.letter.trim();
+    if letter.is_empty()
+        || letter.len() > MAX_LETTER_LEN
+        || !letter.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(crate::errors::invalid_contest(
+            "Letter must be 1 to 8 latin letters or digits",
+        ));
+    }
+
+    let conn = &mut state.db.get().await.unwrap();
+
+    get_contest(conn, id).await?;
+    Problem::get(conn, body.problem_id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::problem_not_found)?;
+
+    let contest_problem = ContestProblem {
+        contest_id: id,
+        problem_id: body.problem_id,
+        letter: letter.to_uppercase(),
+    };
+    let contest_problem = ContestProblem::create(conn, &contest_problem)
+        .await
+        .map_err(|e| match e {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                crate::errors::contest_problem_exists()
+            }
+            e => crate::errors::database_error(e),
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+          "status": "success",
+          "data": json!({
+              "letter": contest_problem.letter,
+              "problem_id": contest_problem.problem_id
+          })
+        })),
+    ))
+}
+
+pub async fn remove_contest_problem_handler(
+    State(state): State<Arc<AppState>>,
+    Path((id, letter)): Path<(uuid::Uuid, String)>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let deleted = ContestProblem::delete(conn, id, &letter.to_uppercase())
+        .await
+        .map_err(crate::errors::database_error)?;
+    if deleted == 0 {
+        return Err(crate::errors::problem_not_in_contest());
+    }
+
+    Ok(Json(json!({
+      "status": "success"
+    })))
+}
+
+/// Registers the user, or a team of the user with all its members, for a
+/// contest that is not over yet.
+pub async fn register_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+    Query(query): Query<RegisterQuery>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = get_contest(conn, id).await?;
+    if contest.ends_at <= Utc::now() {
+        return Err(crate::errors::contest_over());
+    }
+
+    match query.team_id {
+        Some(team_id) => {
+            crate::teams::get_own_team(conn, &user, team_id).await?;
+            let member_ids: Vec<_> = TeamMember::list_for_team(conn, team_id)
+                .await
+                .map_err(crate::errors::database_error)?
+                .into_iter()
+                .map(|(member, _)| member.user_id)
+                .collect();
+
+            ContestParticipant::create_team(conn, id, team_id, &member_ids)
+                .await
+                .map_err(|e| match e {
+                    DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                        crate::errors::already_registered()
+                    }
+                    e => crate::errors::database_error(e),
+                })?;
+        }
+        None => {
+            ContestParticipant::create(conn, id, user.id)
+                .await
+                .map_err(crate::errors::database_error)?;
+        }
+    }
+
+    Ok((
+        [("HX-Trigger", "reload")],
+        Json(json!({
+          "status": "success"
+        })),
+    ))
+}
+
+/// The scoreboard as the user sees it: frozen for anybody but contest
+/// managers until the contest is unfrozen.
+pub async fn scoreboard_handler(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<User>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = get_contest(conn, id).await?;
+    let hidden_from = hidden_from(&contest, user.as_deref());
+    let scoreboard = compute_scoreboard(conn, &contest, hidden_from).await?;
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "scoreboard": scoreboard
+      })
+    })))
+}
+
+/// Shows the final results of a contest that is over to everybody.
+pub async fn unfreeze_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = get_contest(conn, id).await?;
+    if Utc::now() < contest.ends_at {
+        return Err(crate::errors::contest_not_over());
+    }
+
+    Contest::unfreeze(conn, id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(Json(json!({
+      "status": "success"
+    })))
+}
+
+/// The frozen scoreboard of a contest that is over, the final one, and the
+/// steps revealing the hidden results one by one to go from the former to
+/// the latter, for presenting the results.
+pub async fn resolve_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = get_contest(conn, id).await?;
+    if Utc::now() < contest.ends_at {
+        return Err(crate::errors::contest_not_over());
+    }
+
+    let frozen = compute_scoreboard(conn, &contest, Some(contest.frozen_at())).await?;
+    let unfrozen = compute_scoreboard(conn, &contest, None).await?;
+    let steps = scoreboard::resolve(&frozen, &unfrozen);
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "frozen": frozen,
+          "steps": steps,
+          "unfrozen": unfrozen
+      })
+    })))
+}
+
+/// The list of contests on the contests page.
+pub async fn contests_content_handler(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<User>>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contests = Contest::list(conn)
+        .await
+        .map_err(crate::errors::database_error)?;
+    let now = Utc::now();
+
+    Ok(templates::ContestsTemplate {
+        user_logged_in: user.is_some(),
+        contests: contests
+            .iter()
+            .map(|contest| templates::ContestItem {
+                id: contest.id,
+                title: contest.title.clone(),
+                starts_at: format_time(contest.starts_at),
+                ends_at: format_time(contest.ends_at),
+                phase: phase_name(contest, now),
+            })
+            .collect(),
+    })
+}
+
+/// A contest page: its problems, registration and the scoreboard.
+pub async fn contest_content_handler(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<User>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = get_contest(conn, id).await?;
+    let registered = match user.as_deref() {
+        Some(user) => ContestParticipant::exists(conn, id, user.id)
+            .await
+            .map_err(crate::errors::database_error)?,
+        None => false,
+    };
+    let teams = match user.as_deref() {
+        Some(user) if !registered => Team::list_for_user(conn, user.id)
+            .await
+            .map_err(crate::errors::database_error)?,
+        _ => Vec::new(),
+    };
+    let problems = if can_see_problems(&contest, user.as_deref()) {
+        list_problems(conn, id).await?
+    } else {
+        Vec::new()
+    };
+    let now = Utc::now();
+
+    Ok(templates::ContestTemplate {
+        user_logged_in: user.is_some(),
+        id: contest.id,
+        title: contest.title.clone(),
+        starts_at: format_time(contest.starts_at),
+        ends_at: format_time(contest.ends_at),
+        phase: phase_name(&contest, now),
+        can_register: user.is_some() && !registered && now < contest.ends_at,
+        registered,
+        teams: teams.into_iter().map(|team| (team.id, team.name)).collect(),
+        problems: problems
+            .into_iter()
+            .map(|(contest_problem, problem)| (contest_problem.letter, problem.title))
+            .collect(),
+    })
+}
+
+pub async fn scoreboard_widget_handler(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<User>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let contest = get_contest(conn, id).await?;
+    let hidden_from = hidden_from(&contest, user.as_deref());
+    let scoreboard = compute_scoreboard(conn, &contest, hidden_from).await?;
+
+    Ok(templates::ScoreboardTemplate {
+        id: contest.id,
+        scoreboard,
+    })
+}
+
+/// Checks that the user may submit to `problem_id` in the contest with
+/// `contest_id`: it is running, the user is registered, the problem is part
+/// of it and no limit on submissions is reached.
+///
+/// Locks the participant until the end of the transaction, which has to
+/// create the submission too for the limits to hold up against concurrent
+/// requests.
+pub async fn check_submission(
+    conn: &mut AsyncPgConnection,
+    user: &User,
+    contest_id: uuid::Uuid,
+    problem_id: uuid::Uuid,
+) -> Result<(), crate::errors::TyJson> {
+    let contest = get_contest(conn, contest_id).await?;
+    if !contest.is_running(Utc::now()) {
+        return Err(crate::errors::contest_not_running());
+    }
+
+    let participant = ContestParticipant::lock(conn, contest_id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::not_registered)?;
+
+    let problems = ContestProblem::list_for_contest(conn, contest_id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    if !problems
+        .iter()
+        .any(|problem| problem.problem_id == problem_id)
+    {
+        return Err(crate::errors::problem_not_in_contest());
+    }
+
+    // Limits apply to teams as a whole.
+    let user_ids: Vec<_> = match participant.team_id {
+        Some(team_id) => ContestParticipant::list_for_contest(conn, contest_id)
+            .await
+            .map_err(crate::errors::database_error)?
+            .into_iter()
+            .filter(|(participant, _, _)| participant.team_id == Some(team_id))
+            .map(|(participant, _, _)| participant.user_id)
+            .collect(),
+        None => vec![user.id],
+    };
+    let submissions = Submission::list_for_participants(conn, contest_id, &user_ids)
+        .await
+        .map_err(crate::errors::database_error)?;
+    if let Some(last) = submissions.last() {
+        let delay = chrono::Duration::seconds(contest.submission_delay_seconds.into());
+        let wait = last.created_at + delay - Utc::now();
+        if wait > chrono::Duration::zero() {
+            return Err(crate::errors::submitting_too_often(wait.num_seconds() + 1));
+        }
+    }
+    if let Some(limit) = contest.submission_limit {
+        let made = submissions
+            .iter()
+            .filter(|submission| submission.problem_id == problem_id)
+            .count();
+        if made >= limit as usize {
+            return Err(crate::errors::submission_limit_reached());
+        }
+    }
+
+    Ok(())
+}
+
+/// What the user who made `submission` may learn about it: everything,
+/// unless it was made in a contest that is still running.
+pub async fn feedback(
+    conn: &mut AsyncPgConnection,
+    submission: &Submission,
+) -> Result<Feedback, crate::errors::TyJson> {
+    let Some(contest_id) = submission.contest_id else {
+        return Ok(Feedback::Full);
+    };
+    let Some(contest) = Contest::get(conn, contest_id)
+        .await
+        .map_err(crate::errors::database_error)?
+    else {
+        return Ok(Feedback::Full);
+    };
+
+    if Utc::now() < contest.ends_at {
+        Ok(contest.feedback)
+    } else {
+        Ok(Feedback::Full)
+    }
+}
+
+async fn get_contest(
+    conn: &mut AsyncPgConnection,
+    id: uuid::Uuid,
+) -> Result<Contest, crate::errors::TyJson> {
+    Contest::get(conn, id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::contest_not_found)
+}
+
+async fn list_problems(
+    conn: &mut AsyncPgConnection,
+    contest_id: uuid::Uuid,
+) -> Result<Vec<(ContestProblem, Problem)>, crate::errors::TyJson> {
+    let contest_problems = ContestProblem::list_for_contest(conn, contest_id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    let mut problems = Vec::with_capacity(contest_problems.len());
+    for contest_problem in contest_problems {
+        let problem = Problem::get(conn, contest_problem.problem_id)
+            .await
+            .map_err(crate::errors::database_error)?
+            .ok_or_else(crate::errors::problem_not_found)?;
+        problems.push((contest_problem, problem));
+    }
+
+    Ok(problems)
+}
+
+async fn compute_scoreboard(
+    conn: &mut AsyncPgConnection,
+    contest: &Contest,
+    hidden_from: Option<DateTime<Utc>>,
+) -> Result<scoreboard::Scoreboard, crate::errors::TyJson> {
+    let problems = ContestProblem::list_for_contest(conn, contest.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    let participants: Vec<_> = ContestParticipant::list_for_contest(conn, contest.id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .into_iter()
+        .map(
+            |(participant, username, team_name)| scoreboard::Participant {
+                user_id: participant.user_id,
+                username,
+                team: participant.team_id.zip(team_name),
+            },
+        )
+        .collect();
+    let submissions = Submission::list_for_contest(conn, contest.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    let subtask_results = match contest.rules {
+        ContestRules::Icpc => Vec::new(),
+        ContestRules::Ioi => SubtaskResult::list_for_contest(conn, contest.id)
+            .await
+            .map_err(crate::errors::database_error)?,
+    };
+
+    Ok(scoreboard::compute(
+        contest,
+        &problems,
+        &participants,
+        &submissions,
+        &subtask_results,
+        hidden_from,
+    ))
+}
+
+/// From when results are hidden from `user`: the freeze, unless the contest
+/// was unfrozen or the user manages contests.
+fn hidden_from(contest: &Contest, user: Option<&User>) -> Option<DateTime<Utc>> {
+    let manager = user.is_some_and(|user| Permission::ManageContests.granted_to(user.role));
+    if manager || contest.unfrozen_at.is_some() {
+        None
+    } else if contest.feedback == Feedback::None {
+        // Participants may not learn their results from the scoreboard
+        // either.
+        Some(contest.starts_at)
+    } else {
+        Some(contest.frozen_at())
+    }
+}
+
+/// `text` parsed, or `default` if there is none.
+fn parse_or<T: std::str::FromStr<Err = String>>(
+    text: Option<&str>,
+    default: T,
+) -> Result<T, crate::errors::TyJson> {
+    text.map_or(Ok(default), |text| {
+        text.parse()
+            .map_err(|e: String| crate::errors::invalid_contest(&e))
+    })
+}
+
+fn can_see_problems(contest: &Contest, user: Option<&User>) -> bool {
+    contest.starts_at <= Utc::now()
+        || user.is_some_and(|user| Permission::ManageContests.granted_to(user.role))
+}
+
+fn phase_name(contest: &Contest, now: DateTime<Utc>) -> &'static str {
+    if now < contest.starts_at {
+        "Не началось"
+    } else if now < contest.frozen_at() {
+        "Идёт"
+    } else if now < contest.ends_at {
+        "Идёт, таблица заморожена"
+    } else if contest.unfrozen_at.is_none() {
+        "Завершено, таблица заморожена"
+    } else {
+        "Завершено"
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%d.%m.%Y %H:%M UTC").to_string()
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredContest<'a> {
+    pub id: uuid::Uuid,
+    pub title: &'a str,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub frozen_at: DateTime<Utc>,
+    pub penalty_minutes: i32,
+    pub unfrozen_at: Option<DateTime<Utc>>,
+    pub rules: &'static str,
+    pub scoring: &'static str,
+    pub feedback: &'static str,
+    pub submission_limit: Option<i32>,
+    pub submission_delay_seconds: i32,
+}
+
+fn filter_contest_record(contest: &Contest) -> FilteredContest<'_> {
+    FilteredContest {
+        id: contest.id,
+        title: &contest.title,
+        starts_at: contest.starts_at,
+        ends_at: contest.ends_at,
+        frozen_at: contest.frozen_at(),
+        penalty_minutes: contest.penalty_minutes,
+        unfrozen_at: contest.unfrozen_at,
+        rules: contest.rules.as_str(),
+        scoring: contest.scoring.as_str(),
+        feedback: contest.feedback.as_str(),
+        submission_limit: contest.submission_limit,
+        submission_delay_seconds: contest.submission_delay_seconds,
+    }
+}
@@ -0,0 +1,793 @@
+//! Contest scoreboards.
+//!
+//! Under ICPC rules participants are ranked by the number of problems
+//! solved, then by penalty time: for every solved problem, the minutes from
+//! the start of the contest to the accepted submission plus the contest's
+//! penalty for every rejected submission before it. Compilation errors and
+//! submissions the judge failed on do not count as attempts.
+//!
+//! Under IOI rules they are ranked by points. A problem with subtasks is
+//! worth their points and one without them is worth [`FULL_SCORE`], all or
+//! nothing. Depending on the contest's scoring, a participant gets the best
+//! points on each subtask across their submissions, or the points of their
+//! best submission.
+
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use chrono::{DateTime, Utc};
+use db::orm::{
+    Contest, ContestProblem, ContestRules, ContestScoring, Submission, SubmissionStatus,
+    SubtaskResult, Verdict,
+};
+use uuid::Uuid;
+
+/// Points for solving a problem without subtasks under IOI rules.
+pub const FULL_SCORE: f64 = 100.0;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Scoreboard {
+    pub problems: Vec<ScoreboardProblem>,
+    /// Best first.
+    pub rows: Vec<ScoreboardRow>,
+    /// Whether results of the last submissions are hidden.
+    pub frozen: bool,
+    #[serde(skip)]
+    rules: ContestRules,
+    #[serde(skip)]
+    penalty_minutes: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScoreboardProblem {
+    pub letter: String,
+    pub problem_id: Uuid,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScoreboardRow {
+    /// Shared by participants with the same results.
+    pub rank: usize,
+    /// The team, or the user competing alone.
+    pub participant_id: Uuid,
+    pub name: String,
+    pub is_team: bool,
+    pub solved: usize,
+    pub penalty: i64,
+    /// Points on all problems, under IOI rules.
+    pub score: f64,
+    /// One for each problem, in the order of [`Scoreboard::problems`].
+    pub cells: Vec<ScoreboardCell>,
+}
+
+/// How a participant did on a problem.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ScoreboardCell {
+    /// Minutes from the start of the contest to the accepted submission.
+    pub solved_minute: Option<i64>,
+    /// Rejected submissions, only those before the accepted one if any.
+    pub rejected: usize,
+    /// Submissions not judged yet or made after the freeze, before the
+    /// accepted one if any.
+    pub pending: usize,
+    /// Whether nobody solved the problem earlier.
+    pub first_to_solve: bool,
+    /// Points under IOI rules, once a submission is judged.
+    pub score: Option<f64>,
+    #[serde(skip)]
+    solved_at: Option<DateTime<Utc>>,
+}
+
+impl ScoreboardCell {
+    pub fn is_solved(&self) -> bool {
+        self.solved_minute.is_some()
+    }
+
+    /// The cell as shown on an ICPC scoreboard: `+` with the number of
+    /// rejected attempts for a solved problem, `-` with it otherwise, and
+    /// `?` with the number of pending ones.
+    pub fn text(&self) -> String {
+        let text = match (self.is_solved(), self.rejected) {
+            (true, 0) => "+".to_owned(),
+            (true, rejected) => format!("+{rejected}"),
+            (false, 0) => String::new(),
+            (false, rejected) => format!("-{rejected}"),
+        };
+        self.with_pending(text)
+    }
+
+    /// The cell as shown on an IOI scoreboard: the points, and `?` with the
+    /// number of pending submissions.
+    pub fn score_text(&self) -> String {
+        let text = self.score.map(format_score).unwrap_or_default();
+        self.with_pending(text)
+    }
+
+    fn with_pending(&self, mut text: String) -> String {
+        if self.pending > 0 {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&format!("?{}", self.pending));
+        }
+        text
+    }
+
+    fn penalty(&self, penalty_minutes: i64) -> i64 {
+        self.solved_minute
+            .map_or(0, |minute| minute + self.rejected as i64 * penalty_minutes)
+    }
+}
+
+impl ScoreboardRow {
+    pub fn score_text(&self) -> String {
+        format_score(self.score)
+    }
+}
+
+/// One problem of one participant revealed while resolving a frozen
+/// scoreboard.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResolveStep {
+    pub participant_id: Uuid,
+    pub letter: String,
+    pub solved: bool,
+    pub rank_before: usize,
+    pub rank_after: usize,
+}
+
+/// A user registered for a contest, alone or with a team.
+#[derive(Debug, Clone)]
+pub struct Participant {
+    pub user_id: Uuid,
+    pub username: String,
+    /// The id and name of the user's team.
+    pub team: Option<(Uuid, String)>,
+}
+
+/// The scoreboard of `contest`.
+///
+/// Every team gets a row for the submissions of all its members, and every
+/// other participant one of their own. Only submissions made while the
+/// contest was running count. Results of those made at
+/// `hidden_from` or later are not shown, as if they were not judged yet.
+/// `subtask_results` are the points of the submissions on subtasks, needed
+/// under IOI rules.
+pub fn compute(
+    contest: &Contest,
+    problems: &[ContestProblem],
+    participants: &[Participant],
+    submissions: &[Submission],
+    subtask_results: &[SubtaskResult],
+    hidden_from: Option<DateTime<Utc>>,
+) -> Scoreboard {
+    let mut rows: Vec<ScoreboardRow> = Vec::new();
+    // The row of every participant by user id.
+    let mut row_of = BTreeMap::new();
+    for participant in participants {
+        let (participant_id, name) = match &participant.team {
+            Some((team_id, team_name)) => (*team_id, team_name),
+            None => (participant.user_id, &participant.username),
+        };
+        let index = match rows
+            .iter()
+            .position(|row| row.participant_id == participant_id)
+        {
+            Some(index) => index,
+            None => {
+                rows.push(ScoreboardRow {
+                    rank: 0,
+                    participant_id,
+                    name: name.clone(),
+                    is_team: participant.team.is_some(),
+                    solved: 0,
+                    penalty: 0,
+                    score: 0.0,
+                    cells: vec![ScoreboardCell::default(); problems.len()],
+                });
+                rows.len() - 1
+            }
+        };
+        row_of.insert(participant.user_id, index);
+    }
+
+    let mut submissions: Vec<_> = submissions
+        .iter()
+        .filter(|submission| contest.is_running(submission.created_at))
+        .collect();
+    submissions.sort_by_key(|submission| submission.created_at);
+
+    // Best points on every group of every problem of every participant.
+    let mut best_subtasks = BTreeMap::<_, BTreeMap<i32, f64>>::new();
+
+    for submission in submissions {
+        let Some(&row) = row_of.get(&submission.user_id) else {
+            continue;
+        };
+        let row = &mut rows[row];
+        let Some(index) = problems
+            .iter()
+            .position(|problem| problem.problem_id == submission.problem_id)
+        else {
+            continue;
+        };
+
+        let cell = &mut row.cells[index];
+        if cell.is_solved() {
+            continue;
+        }
+
+        let hidden = hidden_from.is_some_and(|hidden_from| submission.created_at >= hidden_from);
+        if hidden || submission.status != SubmissionStatus::Finished {
+            cell.pending += 1;
+            continue;
+        }
+
+        match contest.rules {
+            ContestRules::Icpc => match submission.verdict {
+                Some(Verdict::Accepted) => {
+                    let minute = (submission.created_at - contest.starts_at).num_minutes();
+                    cell.solved_minute = Some(minute);
+                    cell.solved_at = Some(submission.created_at);
+                }
+                Some(Verdict::CompilationError | Verdict::JudgementFailed) | None => {}
+                Some(_) => cell.rejected += 1,
+            },
+            ContestRules::Ioi => {
+                if submission.verdict == Some(Verdict::JudgementFailed) {
+                    continue;
+                }
+
+                let points = submission_points(submission, subtask_results);
+                let score = match contest.scoring {
+                    ContestScoring::BestSubmission => points.values().sum(),
+                    ContestScoring::BestSubtasks => {
+                        let best = best_subtasks
+                            .entry((row.participant_id, index))
+                            .or_default();
+                        for (group, points) in points {
+                            let best = best.entry(group).or_insert(0.0);
+                            *best = best.max(points);
+                        }
+                        best.values().sum()
+                    }
+                };
+                cell.score = Some(cell.score.map_or(score, |best| best.max(score)));
+            }
+        }
+    }
+
+    let mut scoreboard = Scoreboard {
+        problems: problems
+            .iter()
+            .map(|problem| ScoreboardProblem {
+                letter: problem.letter.clone(),
+                problem_id: problem.problem_id,
+            })
+            .collect(),
+        rows,
+        frozen: hidden_from.is_some_and(|hidden_from| hidden_from <= Utc::now()),
+        rules: contest.rules,
+        penalty_minutes: contest.penalty_minutes.into(),
+    };
+    scoreboard.rank();
+
+    scoreboard
+}
+
+/// Points of a judged submission by test group. A problem without subtasks
+/// counts as a single group worth [`FULL_SCORE`].
+fn submission_points(
+    submission: &Submission,
+    subtask_results: &[SubtaskResult],
+) -> BTreeMap<i32, f64> {
+    let points: BTreeMap<_, _> = subtask_results
+        .iter()
+        .filter(|result| result.submission_id == submission.id)
+        .map(|result| (result.test_group, result.points))
+        .collect();
+    if !points.is_empty() {
+        return points;
+    }
+
+    let score = submission.score.unwrap_or(match submission.verdict {
+        Some(Verdict::Accepted) => FULL_SCORE,
+        _ => 0.0,
+    });
+    BTreeMap::from([(0, score)])
+}
+
+/// The order in which the results hidden on `frozen` are revealed to get
+/// `unfrozen`: each time the first hidden problem of the lowest ranked
+/// participant with any.
+pub fn resolve(frozen: &Scoreboard, unfrozen: &Scoreboard) -> Vec<ResolveStep> {
+    let mut scoreboard = frozen.clone();
+    let mut revealed = Vec::new();
+    let mut steps = Vec::new();
+
+    loop {
+        let next = scoreboard.rows.iter().rev().find_map(|row| {
+            let index = row.cells.iter().enumerate().position(|(index, cell)| {
+                cell.pending > 0 && !revealed.contains(&(row.participant_id, index))
+            })?;
+            Some((row.participant_id, row.rank, index))
+        });
+        let Some((participant_id, rank_before, index)) = next else {
+            break;
+        };
+        revealed.push((participant_id, index));
+
+        let Some(cell) = unfrozen
+            .rows
+            .iter()
+            .find(|row| row.participant_id == participant_id)
+            .map(|row| row.cells[index].clone())
+        else {
+            continue;
+        };
+        let solved = cell.is_solved();
+        if let Some(row) = scoreboard
+            .rows
+            .iter_mut()
+            .find(|row| row.participant_id == participant_id)
+        {
+            row.cells[index] = cell;
+        }
+        scoreboard.rank();
+
+        let rank_after = scoreboard
+            .rows
+            .iter()
+            .find(|row| row.participant_id == participant_id)
+            .map_or(rank_before, |row| row.rank);
+        steps.push(ResolveStep {
+            participant_id,
+            letter: scoreboard.problems[index].letter.clone(),
+            solved,
+            rank_before,
+            rank_after,
+        });
+    }
+
+    steps
+}
+
+impl Scoreboard {
+    pub fn is_ioi(&self) -> bool {
+        self.rules == ContestRules::Ioi
+    }
+
+    /// Totals up the rows, sorts them and marks who solved each problem
+    /// first.
+    fn rank(&mut self) {
+        for row in &mut self.rows {
+            row.solved = row.cells.iter().filter(|cell| cell.is_solved()).count();
+            row.penalty = row
+                .cells
+                .iter()
+                .map(|cell| cell.penalty(self.penalty_minutes))
+                .sum();
+            // Not `sum`, which gives -0 without any points.
+            row.score = row
+                .cells
+                .iter()
+                .filter_map(|cell| cell.score)
+                .fold(0.0, |total, score| total + score);
+        }
+
+        let rules = self.rules;
+        self.rows
+            .sort_by(|a, b| compare(rules, a, b).then_with(|| a.name.cmp(&b.name)));
+        for index in 0..self.rows.len() {
+            self.rows[index].rank = match index.checked_sub(1) {
+                Some(previous)
+                    if compare(rules, &self.rows[previous], &self.rows[index])
+                        == Ordering::Equal =>
+                {
+                    self.rows[previous].rank
+                }
+                _ => index + 1,
+            };
+        }
+
+        for index in 0..self.problems.len() {
+            let first_solved_at = self
+                .rows
+                .iter()
+                .filter_map(|row| row.cells[index].solved_at)
+                .min();
+            for row in &mut self.rows {
+                let cell = &mut row.cells[index];
+                cell.first_to_solve = cell.solved_at.is_some() && cell.solved_at == first_solved_at;
+            }
+        }
+    }
+}
+
+/// Which of two rows ranks higher, `Less` for `a`.
+fn compare(rules: ContestRules, a: &ScoreboardRow, b: &ScoreboardRow) -> Ordering {
+    match rules {
+        ContestRules::Icpc => {
+            // Ties are broken by who got their last problem accepted earlier.
+            let key = |row: &ScoreboardRow| {
+                let last_solved_at = row.cells.iter().filter_map(|cell| cell.solved_at).max();
+                (std::cmp::Reverse(row.solved), row.penalty, last_solved_at)
+            };
+            key(a).cmp(&key(b))
+        }
+        ContestRules::Ioi => b.score.total_cmp(&a.score),
+    }
+}
+
+/// Points with at most two decimals.
+fn format_score(score: f64) -> String {
+    let rounded = (score * 100.0).round() / 100.0;
+    rounded.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use db::orm::Feedback;
+
+    use super::*;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap()
+    }
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        start() + Duration::minutes(minute)
+    }
+
+    fn contest(rules: ContestRules, scoring: ContestScoring) -> Contest {
+        Contest {
+            id: Uuid::nil(),
+            title: "Contest".to_owned(),
+            starts_at: start(),
+            ends_at: at(300),
+            freeze_minutes: 60,
+            penalty_minutes: 20,
+            unfrozen_at: None,
+            created_at: start(),
+            rules,
+            scoring,
+            feedback: Feedback::Full,
+            submission_limit: None,
+            submission_delay_seconds: 0,
+        }
+    }
+
+    fn problem_id(letter: char) -> Uuid {
+        Uuid::from_u128(letter as u128)
+    }
+
+    fn problems(letters: &str) -> Vec<ContestProblem> {
+        letters
+            .chars()
+            .map(|letter| ContestProblem {
+                contest_id: Uuid::nil(),
+                problem_id: problem_id(letter),
+                letter: letter.to_string(),
+            })
+            .collect()
+    }
+
+    /// Ids spelled by the names, which are shorter than an id.
+    fn user_id(name: &str) -> Uuid {
+        Uuid::from_u128(name.bytes().fold(0, |id, byte| id << 8 | u128::from(byte)))
+    }
+
+    fn participants(names: &[&str]) -> Vec<Participant> {
+        names
+            .iter()
+            .map(|&name| Participant {
+                user_id: user_id(name),
+                username: name.to_owned(),
+                team: None,
+            })
+            .collect()
+    }
+
+    /// A submission of `user` on problem `letter` at `minute`, judged if it
+    /// has a verdict.
+    fn submission(user: &str, letter: char, minute: i64, verdict: Option<Verdict>) -> Submission {
+        Submission {
+            id: Uuid::new_v4(),
+            user_id: user_id(user),
+            problem_id: problem_id(letter),
+            language: "cpp".to_owned(),
+            source: String::new(),
+            status: match verdict {
+                Some(_) => SubmissionStatus::Finished,
+                None => SubmissionStatus::Queued,
+            },
+            verdict,
+            time_ms: None,
+            memory_bytes: None,
+            created_at: at(minute),
+            heartbeat_at: None,
+            compile_log: None,
+            score: None,
+            current_test: None,
+            contest_id: Some(Uuid::nil()),
+            language_version: None,
+            compiled_from_cache: None,
+            claim_id: None,
+        }
+    }
+
+    fn points(submission: &Submission, points: &[(i32, f64)]) -> Vec<SubtaskResult> {
+        points
+            .iter()
+            .map(|&(test_group, points)| SubtaskResult {
+                submission_id: submission.id,
+                test_group,
+                points,
+            })
+            .collect()
+    }
+
+    fn row<'a>(scoreboard: &'a Scoreboard, name: &str) -> &'a ScoreboardRow {
+        scoreboard.rows.iter().find(|row| row.name == name).unwrap()
+    }
+
+    fn icpc(
+        names: &[&str],
+        submissions: &[Submission],
+        hidden_from: Option<DateTime<Utc>>,
+    ) -> Scoreboard {
+        compute(
+            &contest(ContestRules::Icpc, ContestScoring::BestSubtasks),
+            &problems("AB"),
+            &participants(names),
+            submissions,
+            &[],
+            hidden_from,
+        )
+    }
+
+    #[test]
+    fn icpc_penalty_counts_rejections_before_acceptance() {
+        use Verdict::*;
+        let scoreboard = icpc(
+            &["alice", "bob"],
+            &[
+                submission("alice", 'A', 10, Some(WrongAnswer)),
+                submission("alice", 'A', 30, Some(Accepted)),
+                submission("alice", 'A', 40, Some(WrongAnswer)),
+                submission("alice", 'B', 60, Some(Accepted)),
+                submission("bob", 'A', 20, Some(TimeLimitExceeded)),
+                submission("bob", 'B', 50, Some(RuntimeError)),
+            ],
+            None,
+        );
+
+        let alice = row(&scoreboard, "alice");
+        assert_eq!((alice.rank, alice.solved, alice.penalty), (1, 2, 110));
+        assert_eq!(alice.cells[0].solved_minute, Some(30));
+        assert_eq!(alice.cells[0].text(), "+1");
+        assert_eq!(alice.cells[1].text(), "+");
+
+        let bob = row(&scoreboard, "bob");
+        assert_eq!((bob.rank, bob.solved, bob.penalty), (2, 0, 0));
+        assert_eq!(bob.cells[0].text(), "-1");
+        assert!(!scoreboard.frozen);
+    }
+
+    #[test]
+    fn icpc_ignores_compilation_errors_and_judge_failures() {
+        use Verdict::*;
+        let scoreboard = icpc(
+            &["alice"],
+            &[
+                submission("alice", 'A', 5, Some(CompilationError)),
+                submission("alice", 'A', 10, Some(JudgementFailed)),
+                submission("alice", 'A', 15, Some(Accepted)),
+            ],
+            None,
+        );
+
+        let alice = row(&scoreboard, "alice");
+        assert_eq!(alice.cells[0].rejected, 0);
+        assert_eq!(alice.penalty, 15);
+    }
+
+    #[test]
+    fn icpc_ignores_submissions_outside_the_contest() {
+        let scoreboard = icpc(
+            &["alice"],
+            &[
+                submission("alice", 'A', -5, Some(Verdict::Accepted)),
+                submission("alice", 'A', 301, Some(Verdict::Accepted)),
+            ],
+            None,
+        );
+
+        assert_eq!(row(&scoreboard, "alice").solved, 0);
+    }
+
+    #[test]
+    fn freeze_hides_later_results_as_pending() {
+        use Verdict::*;
+        let submissions = [
+            submission("alice", 'A', 100, Some(WrongAnswer)),
+            submission("alice", 'A', 250, Some(Accepted)),
+            submission("alice", 'B', 260, None),
+        ];
+        let scoreboard = icpc(&["alice"], &submissions, Some(at(240)));
+
+        let alice = row(&scoreboard, "alice");
+        assert!(scoreboard.frozen);
+        assert_eq!(alice.solved, 0);
+        assert_eq!(alice.cells[0].text(), "-1 ?1");
+        assert_eq!(alice.cells[1].text(), "?1");
+    }
+
+    #[test]
+    fn first_to_solve_is_the_earliest_acceptance() {
+        let scoreboard = icpc(
+            &["alice", "bob"],
+            &[
+                submission("alice", 'A', 30, Some(Verdict::Accepted)),
+                submission("bob", 'A', 20, Some(Verdict::Accepted)),
+                submission("alice", 'B', 40, Some(Verdict::Accepted)),
+            ],
+            None,
+        );
+
+        assert!(row(&scoreboard, "bob").cells[0].first_to_solve);
+        assert!(!row(&scoreboard, "alice").cells[0].first_to_solve);
+        assert!(row(&scoreboard, "alice").cells[1].first_to_solve);
+        assert!(!row(&scoreboard, "bob").cells[1].first_to_solve);
+    }
+
+    #[test]
+    fn ties_share_a_rank() {
+        use Verdict::*;
+        let scoreboard = icpc(
+            &["carol", "alice", "bob", "dave"],
+            &[
+                submission("alice", 'A', 30, Some(Accepted)),
+                submission("bob", 'A', 30, Some(Accepted)),
+                // The same penalty, but the last acceptance is later.
+                submission("carol", 'A', 10, Some(Accepted)),
+                submission("carol", 'B', 50, Some(Accepted)),
+                submission("dave", 'A', 20, Some(Accepted)),
+                submission("dave", 'B', 40, Some(Accepted)),
+            ],
+            None,
+        );
+
+        let ranks: Vec<_> = scoreboard
+            .rows
+            .iter()
+            .map(|row| (row.name.as_str(), row.rank))
+            .collect();
+        assert_eq!(ranks, [("dave", 1), ("carol", 2), ("alice", 3), ("bob", 3)]);
+    }
+
+    #[test]
+    fn team_members_share_a_row() {
+        let team_id = Uuid::from_u128(7);
+        let mut participants = participants(&["alice", "bob"]);
+        for participant in &mut participants {
+            participant.team = Some((team_id, "team".to_owned()));
+        }
+        let scoreboard = compute(
+            &contest(ContestRules::Icpc, ContestScoring::BestSubtasks),
+            &problems("AB"),
+            &participants,
+            &[
+                submission("alice", 'A', 10, Some(Verdict::Accepted)),
+                submission("bob", 'B', 20, Some(Verdict::Accepted)),
+            ],
+            &[],
+            None,
+        );
+
+        assert_eq!(scoreboard.rows.len(), 1);
+        let team = row(&scoreboard, "team");
+        assert!(team.is_team);
+        assert_eq!(team.participant_id, team_id);
+        assert_eq!((team.solved, team.penalty), (2, 30));
+    }
+
+    /// Alice's score on A under `scoring`, with one submission earning the
+    /// first subtask and another the second.
+    fn ioi_score(scoring: ContestScoring) -> f64 {
+        let first = submission("alice", 'A', 10, Some(Verdict::WrongAnswer));
+        let second = submission("alice", 'A', 20, Some(Verdict::WrongAnswer));
+        let subtask_results = [
+            points(&first, &[(1, 30.0), (2, 0.0)]),
+            points(&second, &[(1, 0.0), (2, 60.0)]),
+        ]
+        .concat();
+
+        let scoreboard = compute(
+            &contest(ContestRules::Ioi, scoring),
+            &problems("A"),
+            &participants(&["alice"]),
+            &[first, second],
+            &subtask_results,
+            None,
+        );
+        row(&scoreboard, "alice").score
+    }
+
+    #[test]
+    fn ioi_best_subtasks_combine_submissions() {
+        assert_eq!(ioi_score(ContestScoring::BestSubtasks), 90.0);
+    }
+
+    #[test]
+    fn ioi_best_submission_takes_a_single_submission() {
+        assert_eq!(ioi_score(ContestScoring::BestSubmission), 60.0);
+    }
+
+    #[test]
+    fn ioi_problem_without_subtasks_is_all_or_nothing() {
+        use Verdict::*;
+        let scoreboard = compute(
+            &contest(ContestRules::Ioi, ContestScoring::BestSubtasks),
+            &problems("AB"),
+            &participants(&["alice", "bob"]),
+            &[
+                submission("alice", 'A', 10, Some(Accepted)),
+                submission("alice", 'B', 10, Some(JudgementFailed)),
+                submission("bob", 'A', 10, Some(WrongAnswer)),
+                submission("bob", 'B', 20, None),
+            ],
+            &[],
+            None,
+        );
+
+        let alice = row(&scoreboard, "alice");
+        assert_eq!((alice.rank, alice.score), (1, FULL_SCORE));
+        assert_eq!(alice.cells[1].score, None);
+
+        let bob = row(&scoreboard, "bob");
+        assert_eq!((bob.rank, bob.score), (2, 0.0));
+        assert_eq!(bob.score_text(), "0");
+        assert_eq!(bob.cells[1].score_text(), "?1");
+    }
+
+    #[test]
+    fn resolve_reveals_from_the_bottom() {
+        use Verdict::*;
+        let submissions = [
+            submission("alice", 'A', 10, Some(Accepted)),
+            submission("alice", 'B', 250, Some(WrongAnswer)),
+            submission("bob", 'A', 20, Some(Accepted)),
+            submission("bob", 'B', 260, Some(Accepted)),
+            submission("carol", 'A', 270, Some(WrongAnswer)),
+        ];
+        let names = ["alice", "bob", "carol"];
+        let frozen = icpc(&names, &submissions, Some(at(240)));
+        let unfrozen = icpc(&names, &submissions, None);
+
+        let steps: Vec<_> = resolve(&frozen, &unfrozen)
+            .into_iter()
+            .map(|step| {
+                let name = names
+                    .into_iter()
+                    .find(|&name| user_id(name) == step.participant_id)
+                    .unwrap();
+                (
+                    name,
+                    step.letter,
+                    step.solved,
+                    step.rank_before,
+                    step.rank_after,
+                )
+            })
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("carol", "A".to_owned(), false, 3, 3),
+                ("bob", "B".to_owned(), true, 2, 1),
+                ("alice", "B".to_owned(), false, 2, 2),
+            ]
+        );
+    }
+}
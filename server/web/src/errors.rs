@@ -34,6 +34,38 @@ pub fn invalid_username_or_password_htmx() -> Response<Body> {
   response
 }
 
+pub fn problem_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Problem not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn unknown_language() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Unknown language",
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn invalid_source() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Source must be non-empty and at most 64 KiB long",
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn submission_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Submission not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
 pub fn missing_token() -> TyJson {
     let error_response = serde_json::json!({
       "status": "error",
@@ -49,3 +81,243 @@ pub fn invalid_token() -> TyJson {
     });
     (StatusCode::UNAUTHORIZED, Json(error_response))
 }
+
+pub fn forbidden() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "You do not have permission to do this",
+    });
+    (StatusCode::FORBIDDEN, Json(error_response))
+}
+
+pub fn user_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "User not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn unknown_role() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Unknown role",
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn invalid_package(message: String) -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": message,
+    });
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(error_response))
+}
+
+pub fn io_error(e: std::io::Error) -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": format!("I/O error: {}", e),
+    });
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+}
+
+pub fn cannot_export(message: String) -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": message,
+    });
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(error_response))
+}
+
+pub fn contest_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Contest not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn invalid_contest(message: &str) -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": message,
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn contest_problem_exists() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "The contest already has this problem or letter",
+    });
+    (StatusCode::CONFLICT, Json(error_response))
+}
+
+pub fn contest_not_running() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "The contest is not running",
+    });
+    (StatusCode::FORBIDDEN, Json(error_response))
+}
+
+pub fn contest_over() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "The contest is over",
+    });
+    (StatusCode::CONFLICT, Json(error_response))
+}
+
+pub fn contest_not_over() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "The contest is not over yet",
+    });
+    (StatusCode::CONFLICT, Json(error_response))
+}
+
+pub fn not_registered() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "You are not registered for the contest",
+    });
+    (StatusCode::FORBIDDEN, Json(error_response))
+}
+
+pub fn problem_not_in_contest() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "The problem is not part of the contest",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn submitting_too_often(wait_seconds: i64) -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": format!("You can submit again in {} seconds", wait_seconds),
+    });
+    (StatusCode::TOO_MANY_REQUESTS, Json(error_response))
+}
+
+pub fn submission_limit_reached() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "You have used up your submissions to this problem",
+    });
+    (StatusCode::FORBIDDEN, Json(error_response))
+}
+
+pub fn team_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Team not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn invalid_team_name() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Team name must be non-empty and at most 64 characters long",
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn team_name_taken() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "A team with this name already exists",
+    });
+    (StatusCode::CONFLICT, Json(error_response))
+}
+
+pub fn not_team_member() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "You are not a member of the team",
+    });
+    (StatusCode::FORBIDDEN, Json(error_response))
+}
+
+pub fn already_team_member() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "The user is already a member of the team",
+    });
+    (StatusCode::CONFLICT, Json(error_response))
+}
+
+pub fn invitation_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "You are not invited to the team",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn already_registered() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "A member of the team is already registered for the contest",
+    });
+    (StatusCode::CONFLICT, Json(error_response))
+}
+
+pub fn unknown_verdict() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Unknown verdict",
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn invalid_rejudge() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Choose submissions to rejudge by problem, contest, verdict or id",
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn rejudge_target_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Problem, contest or submission to rejudge not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn rejudge_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Rejudge not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub fn invalid_stdin() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Input must be at most 1 MiB long",
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub fn running_too_often() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Too many runs, wait a minute",
+    });
+    (StatusCode::TOO_MANY_REQUESTS, Json(error_response))
+}
+
+pub fn run_not_found() -> TyJson {
+    let error_response = serde_json::json!({
+      "status": "error",
+      "message": "Run not found",
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
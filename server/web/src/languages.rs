@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use axum::{extract::State, response::IntoResponse, Json};
+use language_module::RegisteredLanguage;
+
+use crate::AppState;
+
+pub async fn list_languages_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let languages: Vec<_> = state.languages.iter().map(filter_language_record).collect();
+
+    Json(serde_json::json!({
+      "status": "success",
+      "data": serde_json::json!({
+          "languages": languages
+      })
+    }))
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredLimits {
+    pub cpu_time_ms: u128,
+    pub wall_time_ms: u128,
+    pub memory_bytes: u64,
+    pub output_bytes: u64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredLanguage<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub version: &'a str,
+    pub extension: &'a str,
+    pub default_limits: FilteredLimits,
+    /// Problem time limits are multiplied by this for the language.
+    pub time_multiplier: f64,
+    /// Memory given on top of problem memory limits for the runtime.
+    pub memory_overhead_bytes: u64,
+}
+
+fn filter_language_record(language: &RegisteredLanguage) -> FilteredLanguage<'_> {
+    let limits = language.module.default_limits();
+
+    FilteredLanguage {
+        id: language.module.id(),
+        name: language.module.name(),
+        version: &language.version,
+        extension: language.module.extension(),
+        default_limits: FilteredLimits {
+            cpu_time_ms: limits.cpu_time.as_millis(),
+            wall_time_ms: limits.wall_time.as_millis(),
+            memory_bytes: limits.memory,
+            output_bytes: limits.output,
+        },
+        time_multiplier: language.module.time_multiplier(),
+        memory_overhead_bytes: language.module.memory_overhead(),
+    }
+}
@@ -1,17 +1,27 @@
 pub mod auth;
+pub mod config;
+pub mod contests;
 pub mod errors;
+pub mod languages;
+pub mod problems;
+pub mod rejudges;
+pub mod runs;
+pub mod submissions;
+pub mod teams;
 pub mod templates;
 
 use std::sync::Arc;
 
 use auth::jwt::UserLoggedIn;
 use axum::{
+    extract::{DefaultBodyLimit, Path},
     http::{
         header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
-        HeaderValue, Method,
+        Method,
     },
     middleware,
-    routing::{get, post},
+    response::IntoResponse,
+    routing::{delete, get, post, put},
     Router, Extension,
 };
 use diesel_async::{
@@ -20,28 +30,33 @@ use diesel_async::{
 };
 use tower_http::{cors::CorsLayer, services::ServeDir};
 
+pub use config::WebConfig;
 pub use diesel_async;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: Pool<AsyncPgConnection>,
+    pub config: WebConfig,
     pub jwt_config: auth::JWTConfig,
+    pub languages: language_module::LanguageRegistry,
+    pub importer: judge::package::Importer,
 }
 
-pub fn build_connection_pool(url: &str) -> Pool<AsyncPgConnection> {
+pub fn build_connection_pool(url: &str, max_size: usize) -> Pool<AsyncPgConnection> {
     let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(url);
 
     Pool::builder(manager)
+        .max_size(max_size)
         .build()
         .expect("Could not build connection pool")
 }
 
 pub async fn serve_web_app(state: AppState) {
     use axum::{error_handling::HandleErrorLayer, http::StatusCode, *};
-    use time::Duration;
     use tower::ServiceBuilder;
     use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
 
+    let config = state.config.clone();
     let state = std::sync::Arc::new(state);
 
     let session_store = MemoryStore::default();
@@ -51,21 +66,31 @@ pub async fn serve_web_app(state: AppState) {
         }))
         .layer(
             SessionManagerLayer::new(session_store)
-                .with_secure(false)
-                .with_expiry(Expiry::OnInactivity(Duration::seconds(10))),
+                .with_secure(config.secure_cookies)
+                .with_expiry(Expiry::OnInactivity(
+                    time::Duration::try_from(config.session_expiry).unwrap_or(time::Duration::MAX),
+                )),
         );
 
     let cors = CorsLayer::new()
-        .allow_origin("http://localhost:32055".parse::<HeaderValue>().unwrap())
-        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
+        .allow_origin(config.cors_origins)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
         .allow_credentials(true)
         .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE]);
 
     let app = create_router(state).layer(session_service).layer(cors);
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:32055")
+    println!("Serving {} on {}", config.public_url, config.bind);
+
+    let listener = tokio::net::TcpListener::bind(config.bind)
         .await
-        .unwrap();
+        .unwrap_or_else(|e| panic!("Could not listen on {}: {e}", config.bind));
     axum::serve(listener, app).await.unwrap()
 }
 
@@ -77,9 +102,51 @@ pub fn create_router(state: Arc<AppState>) -> Router {
             get(|| async {
                 templates::BaseTemplate {
                     title: "Главная",
+                    content: "/content",
                 }
             })
         )
+        .route(
+            "/contests",
+            get(|| async {
+                templates::BaseTemplate {
+                    title: "Соревнования",
+                    content: "/content/contests",
+                }
+            }),
+        )
+        .route(
+            "/contests/:id",
+            get(|Path(id): Path<uuid::Uuid>| async move {
+                let content = format!("/content/contests/{id}");
+                templates::BaseTemplate {
+                    title: "Соревнование",
+                    content: &content,
+                }
+                .into_response()
+            }),
+        )
+        .route(
+            "/content/contests",
+            get(contests::contests_content_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer_boolean,
+            )),
+        )
+        .route(
+            "/content/contests/:id",
+            get(contests::contest_content_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer_boolean,
+            )),
+        )
+        .route(
+            "/widgets/contests/:id/scoreboard",
+            get(contests::scoreboard_widget_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer_boolean,
+            )),
+        )
         .route(
           "/content",
           get(|Extension(user_logged_in): Extension<UserLoggedIn>,| async move {
@@ -103,9 +170,18 @@ pub fn create_router(state: Arc<AppState>) -> Router {
                 templates::LoginFormTemplate {}
             }),
         )
+        .route("/api/languages", get(languages::list_languages_handler))
         .route("/api/auth/register", post(auth::register_user_handler))
         .route("/api/auth/login", post(auth::login_user_handler))
         .route("/api/auth/get_salt", post(auth::get_salt_handler))
+        .route("/api/auth/refresh", post(auth::refresh_handler))
+        .route(
+            "/api/auth/change_password",
+            post(auth::change_password_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
         .route(
             "/api/auth/logout",
             get(auth::logout_handler).route_layer(middleware::from_fn_with_state(
@@ -113,6 +189,227 @@ pub fn create_router(state: Arc<AppState>) -> Router {
                 auth::jwt_layer,
             )),
         )
+        .route(
+            "/widgets/submission/:id",
+            get(submissions::submission_widget_handler).route_layer(
+                middleware::from_fn_with_state(state.clone(), auth::jwt_layer),
+            ),
+        )
+        .route(
+            "/api/submissions",
+            post(submissions::create_submission_handler).route_layer(
+                middleware::from_fn_with_state(state.clone(), auth::jwt_layer),
+            ),
+        )
+        .route(
+            "/api/submissions/:id",
+            get(submissions::get_submission_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/submissions/:id/events",
+            get(submissions::submission_events_handler).route_layer(
+                middleware::from_fn_with_state(state.clone(), auth::jwt_layer),
+            ),
+        )
+        .route(
+            "/api/run",
+            post(runs::create_run_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/run/:id",
+            get(runs::get_run_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/problems/import",
+            post(problems::import_problem_handler)
+                .layer(DefaultBodyLimit::max(problems::MAX_PACKAGE_SIZE))
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::AuthorProblems,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/problems/:id/export",
+            get(problems::export_problem_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::AuthorProblems,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/rejudges",
+            get(rejudges::list_rejudges_handler)
+                .post(rejudges::create_rejudge_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::Rejudge,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/rejudges/:id",
+            get(rejudges::rejudge_report_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::Rejudge,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/contests",
+            get(contests::list_contests_handler).merge(
+                post(contests::create_contest_handler)
+                    .route_layer(middleware::from_fn_with_state(
+                        auth::Permission::ManageContests,
+                        auth::require_permission,
+                    ))
+                    .route_layer(middleware::from_fn_with_state(
+                        state.clone(),
+                        auth::jwt_layer,
+                    )),
+            ),
+        )
+        .route(
+            "/api/contests/:id",
+            get(contests::get_contest_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/contests/:id/problems",
+            post(contests::add_contest_problem_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::ManageContests,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/contests/:id/problems/:letter",
+            delete(contests::remove_contest_problem_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::ManageContests,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/contests/:id/register",
+            post(contests::register_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/contests/:id/scoreboard",
+            get(contests::scoreboard_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer_boolean,
+            )),
+        )
+        .route(
+            "/api/contests/:id/unfreeze",
+            post(contests::unfreeze_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::ManageContests,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/contests/:id/resolve",
+            get(contests::resolve_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::ManageContests,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/teams",
+            get(teams::list_teams_handler)
+                .post(teams::create_team_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
+        .route(
+            "/api/teams/:id/invitations",
+            post(teams::invite_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/teams/:id/accept",
+            post(teams::accept_invitation_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/teams/:id/decline",
+            post(teams::decline_invitation_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/teams/:id/leave",
+            post(teams::leave_team_handler).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::jwt_layer,
+            )),
+        )
+        .route(
+            "/api/users/:id/role",
+            put(auth::set_role_handler)
+                .route_layer(middleware::from_fn_with_state(
+                    auth::Permission::ManageUsers,
+                    auth::require_permission,
+                ))
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth::jwt_layer,
+                )),
+        )
         .route(
             "/api/users/me",
             post(auth::get_me_handler).route_layer(middleware::from_fn_with_state(
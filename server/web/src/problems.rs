@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use db::orm::CheckerKind;
+use judge::package::{ExportError, ImportError, ImportOptions};
+use serde_json::json;
+
+use crate::AppState;
+
+/// Largest package accepted for upload.
+pub const MAX_PACKAGE_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ImportProblemQuery {
+    pub title: Option<String>,
+    pub time_limit_ms: Option<i32>,
+    pub memory_limit_mb: Option<i64>,
+    pub checker: Option<String>,
+}
+
+/// Imports a problem from a Polygon or Kattis package uploaded as a zip
+/// archive in the request body.
+pub async fn import_problem_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ImportProblemQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let checker = query
+        .checker
+        .as_deref()
+        .map(str::parse::<CheckerKind>)
+        .transpose()
+        .map_err(crate::errors::invalid_package)?;
+    let options = ImportOptions {
+        title: query.title,
+        time_limit_ms: query.time_limit_ms,
+        memory_limit_bytes: query.memory_limit_mb.map(|mb| mb * 1024 * 1024),
+        checker,
+    };
+
+    let upload = state
+        .importer
+        .workdir
+        .join(format!("upload-{}.zip", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&state.importer.workdir)
+        .await
+        .map_err(crate::errors::io_error)?;
+    tokio::fs::write(&upload, &body)
+        .await
+        .map_err(crate::errors::io_error)?;
+
+    let conn = &mut state.db.get().await.unwrap();
+    let imported = state.importer.import(conn, &upload, &options).await;
+    tokio::fs::remove_file(&upload).await.ok();
+
+    let problem = imported.map_err(|e| match e {
+        ImportError::Database(e) => crate::errors::database_error(e),
+        ImportError::Io(e) => crate::errors::io_error(e),
+        e => crate::errors::invalid_package(e.to_string()),
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+          "status": "success",
+          "data": json!({
+              "problem": json!({
+                  "id": problem.id,
+                  "title": problem.title,
+                  "time_limit_ms": problem.time_limit_ms,
+                  "memory_limit_bytes": problem.memory_limit_bytes,
+                  "checker": problem.checker.as_str(),
+              })
+          })
+        })),
+    ))
+}
+
+/// Exports a problem as a zipped Kattis package.
+pub async fn export_problem_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let download = state
+        .importer
+        .workdir
+        .join(format!("export-{}.zip", uuid::Uuid::new_v4()));
+
+    let conn = &mut state.db.get().await.unwrap();
+    let exported = judge::package::export(
+        conn,
+        &state.importer.data_dir,
+        &state.importer.languages,
+        id,
+        &download,
+    )
+    .await;
+    let package = match exported {
+        Ok(_) => tokio::fs::read(&download)
+            .await
+            .map_err(crate::errors::io_error),
+        Err(e) => Err(match e {
+            ExportError::Database(e) => crate::errors::database_error(e),
+            ExportError::Io(e) => crate::errors::io_error(e),
+            ExportError::ProblemNotFound => crate::errors::problem_not_found(),
+            e => crate::errors::cannot_export(e.to_string()),
+        }),
+    };
+    tokio::fs::remove_file(&download).await.ok();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_owned()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{id}.zip\""),
+            ),
+        ],
+        package?,
+    ))
+}
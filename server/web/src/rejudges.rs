@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use db::{
+    diesel::result::{DatabaseErrorKind, Error as DieselError},
+    orm::{NewRejudge, Rejudge, RejudgeSubmission, Submission, SubmissionStatus, User, Verdict},
+};
+use serde_json::json;
+
+use crate::AppState;
+
+/// Which submissions to rejudge: those matching every given filter. At
+/// least one is required.
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateRejudgeSchema {
+    pub problem_id: Option<uuid::Uuid>,
+    pub contest_id: Option<uuid::Uuid>,
+    pub verdict: Option<String>,
+    pub submission_id: Option<uuid::Uuid>,
+}
+
+/// Queues the finished submissions matching the filters again. Scoreboards
+/// show them as pending until they are judged.
+pub async fn create_rejudge_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Json(body): Json<CreateRejudgeSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let verdict = body
+        .verdict
+        .as_deref()
+        .map(str::parse::<Verdict>)
+        .transpose()
+        .map_err(|_| crate::errors::unknown_verdict())?;
+
+    let new_rejudge = NewRejudge {
+        created_by: Some(user.id),
+        problem_id: body.problem_id,
+        contest_id: body.contest_id,
+        verdict,
+        submission_id: body.submission_id,
+    };
+    if !new_rejudge.has_filters() {
+        return Err(crate::errors::invalid_rejudge());
+    }
+
+    let conn = &mut state.db.get().await.unwrap();
+
+    let (rejudge, queued) = Rejudge::create(conn, &new_rejudge)
+        .await
+        .map_err(|e| match e {
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                crate::errors::rejudge_target_not_found()
+            }
+            e => crate::errors::database_error(e),
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+          "status": "success",
+          "data": json!({
+              "rejudge": filter_rejudge_record(&rejudge),
+              "queued": queued,
+          })
+        })),
+    ))
+}
+
+pub async fn list_rejudges_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let rejudges = Rejudge::list(conn)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "rejudges": rejudges.iter().map(filter_rejudge_record).collect::<Vec<_>>()
+      })
+    })))
+}
+
+/// Which submissions of a rejudge changed verdict or points, and how many
+/// are still being judged.
+pub async fn rejudge_report_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let rejudge = Rejudge::get(conn, id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::rejudge_not_found)?;
+    let submissions = RejudgeSubmission::list_for_rejudge(conn, id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    let pending = submissions
+        .iter()
+        .filter(|(_, submission)| submission.status != SubmissionStatus::Finished)
+        .count();
+    let changed: Vec<_> = submissions
+        .iter()
+        .filter(|(before, submission)| {
+            submission.status == SubmissionStatus::Finished && before.is_changed(submission)
+        })
+        .map(|(before, submission)| filter_change_record(before, submission))
+        .collect();
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "rejudge": filter_rejudge_record(&rejudge),
+          "total": submissions.len(),
+          "pending": pending,
+          "changed": changed,
+      })
+    })))
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredRejudge {
+    pub id: uuid::Uuid,
+    pub created_by: Option<uuid::Uuid>,
+    pub problem_id: Option<uuid::Uuid>,
+    pub contest_id: Option<uuid::Uuid>,
+    pub verdict: Option<&'static str>,
+    pub submission_id: Option<uuid::Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredChange {
+    pub submission_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub problem_id: uuid::Uuid,
+    pub old_verdict: Option<&'static str>,
+    pub new_verdict: Option<&'static str>,
+    pub old_score: Option<f64>,
+    pub new_score: Option<f64>,
+}
+
+fn filter_rejudge_record(rejudge: &Rejudge) -> FilteredRejudge {
+    FilteredRejudge {
+        id: rejudge.id,
+        created_by: rejudge.created_by,
+        problem_id: rejudge.problem_id,
+        contest_id: rejudge.contest_id,
+        verdict: rejudge.verdict.map(|verdict| verdict.as_str()),
+        submission_id: rejudge.submission_id,
+        created_at: rejudge.created_at,
+    }
+}
+
+fn filter_change_record(before: &RejudgeSubmission, submission: &Submission) -> FilteredChange {
+    FilteredChange {
+        submission_id: submission.id,
+        user_id: submission.user_id,
+        problem_id: submission.problem_id,
+        old_verdict: before.verdict.map(|verdict| verdict.as_str()),
+        new_verdict: submission.verdict.map(|verdict| verdict.as_str()),
+        old_score: before.score,
+        new_score: submission.score,
+    }
+}
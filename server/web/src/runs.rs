@@ -0,0 +1,174 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::Utc;
+use db::orm::{NewRun, Run, SubmissionStatus, User};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection};
+use serde_json::json;
+
+use crate::{submissions::MAX_SOURCE_LEN, AppState};
+
+const MAX_STDIN_LEN: usize = 1024 * 1024;
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long `POST /api/run` waits for the judge before answering with a run
+/// that is still going.
+const MAX_WAIT: Duration = Duration::from_secs(20);
+/// Finished runs are forgotten after this many hours.
+const KEPT_FOR_HOURS: i64 = 1;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateRunSchema {
+    pub language: String,
+    pub source: String,
+    #[serde(default)]
+    pub stdin: String,
+}
+
+/// Runs a program once on the given input, under the default limits of its
+/// language, without submitting it anywhere.
+///
+/// Answers once the run is finished, or with `202 Accepted` if the judge is
+/// busy for too long; [`get_run_handler`] has the result later.
+pub async fn create_run_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Json(body): Json<CreateRunSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    if state.languages.get(&body.language).is_none() {
+        return Err(crate::errors::unknown_language());
+    }
+    if body.source.trim().is_empty() || body.source.len() > MAX_SOURCE_LEN {
+        return Err(crate::errors::invalid_source());
+    }
+    if body.stdin.len() > MAX_STDIN_LEN {
+        return Err(crate::errors::invalid_stdin());
+    }
+
+    let mut conn = state.db.get().await.unwrap();
+
+    // The user stays locked from counting the recent runs to creating the
+    // new one, so concurrent requests cannot all slip under the limit. A
+    // rejected run leaves nothing to roll back, so the transaction only
+    // fails on database errors.
+    let runs_per_minute = i64::from(state.config.runs_per_minute);
+    let mut run = conn
+        .transaction(|conn| {
+            async move {
+                User::lock(conn, user.id).await?;
+
+                let now = Utc::now();
+                let recent =
+                    Run::count_since(conn, user.id, now - chrono::Duration::minutes(1)).await?;
+                if recent >= runs_per_minute {
+                    return Ok(Err(crate::errors::running_too_often()));
+                }
+                Run::delete_finished_before(
+                    conn,
+                    user.id,
+                    now - chrono::Duration::hours(KEPT_FOR_HOURS),
+                )
+                .await?;
+
+                let new_run = NewRun {
+                    user_id: user.id,
+                    language: &body.language,
+                    source: &body.source,
+                    stdin: &body.stdin,
+                };
+                Run::create(conn, &new_run).await.map(Ok)
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(crate::errors::database_error)??;
+    // Other requests need the connection more while the judge is busy.
+    drop(conn);
+
+    let deadline = tokio::time::Instant::now() + MAX_WAIT;
+    while run.status != SubmissionStatus::Finished && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let conn = &mut state.db.get().await.unwrap();
+        run = Run::get(conn, run.id)
+            .await
+            .map_err(crate::errors::database_error)?
+            .ok_or_else(crate::errors::run_not_found)?;
+    }
+
+    let status = if run.status == SubmissionStatus::Finished {
+        StatusCode::OK
+    } else {
+        StatusCode::ACCEPTED
+    };
+
+    Ok((
+        status,
+        Json(json!({
+          "status": "success",
+          "data": json!({
+              "run": filter_run_record(&run)
+          })
+        })),
+    ))
+}
+
+pub async fn get_run_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let run = Run::get(conn, id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .filter(|run| run.user_id == user.id)
+        .ok_or_else(crate::errors::run_not_found)?;
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "run": filter_run_record(&run)
+      })
+    })))
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredRun<'a> {
+    pub id: uuid::Uuid,
+    pub language: &'a str,
+    pub status: &'static str,
+    pub compile_log: Option<&'a str>,
+    /// Whether the judge took the program from its compile cache.
+    pub compiled_from_cache: Option<bool>,
+    /// Why the program did not exit on its own, if it did not.
+    pub verdict: Option<&'static str>,
+    pub exit_code: Option<i32>,
+    pub stdout: Option<&'a str>,
+    pub stderr: Option<&'a str>,
+    pub time_ms: Option<i32>,
+    pub memory_bytes: Option<i64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn filter_run_record(run: &Run) -> FilteredRun<'_> {
+    FilteredRun {
+        id: run.id,
+        language: &run.language,
+        status: run.status.as_str(),
+        compile_log: run.compile_log.as_deref(),
+        compiled_from_cache: run.compiled_from_cache,
+        verdict: run.verdict.map(|verdict| verdict.as_str()),
+        exit_code: run.exit_code,
+        stdout: run.stdout.as_deref(),
+        stderr: run.stderr.as_deref(),
+        time_ms: run.time_ms,
+        memory_bytes: run.memory_bytes,
+        created_at: run.created_at,
+    }
+}
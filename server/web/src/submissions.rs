@@ -0,0 +1,440 @@
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use askama::Template;
+use axum::{
+    extract::{Path, State},
+    http::{header, Response, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Extension, Json,
+};
+use chrono::Utc;
+use db::orm::{
+    Contest, Feedback, Problem, Submission, SubmissionStatus, Subtask, SubtaskResult, TestCase,
+    TestResult, User, Verdict,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection};
+use futures_util::stream::{self, Stream};
+use serde_json::json;
+
+use crate::{templates, AppState};
+
+pub(crate) const MAX_SOURCE_LEN: usize = 64 * 1024;
+const EVENTS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateSubmissionSchema {
+    pub problem_id: uuid::Uuid,
+    pub language: String,
+    pub source: String,
+    /// Submits to the problem as part of a running contest. Without it, a
+    /// submission to a problem of a contest the user takes part in right
+    /// now still counts towards that contest.
+    pub contest_id: Option<uuid::Uuid>,
+}
+
+pub async fn create_submission_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Json(body): Json<CreateSubmissionSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    if state.languages.get(&body.language).is_none() {
+        return Err(crate::errors::unknown_language());
+    }
+    if body.source.trim().is_empty() || body.source.len() > MAX_SOURCE_LEN {
+        return Err(crate::errors::invalid_source());
+    }
+
+    let conn = &mut state.db.get().await.unwrap();
+
+    Problem::get(conn, body.problem_id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::problem_not_found)?;
+
+    // A rejected submission leaves nothing to roll back, so the transaction
+    // only fails on database errors.
+    let submission = conn
+        .transaction(|conn| {
+            async move {
+                // Otherwise the contest's limits and feedback could be
+                // sidestepped by leaving it out.
+                let contest_id = match body.contest_id {
+                    Some(contest_id) => Some(contest_id),
+                    None => {
+                        Contest::running_with_problem(conn, user.id, body.problem_id, Utc::now())
+                            .await?
+                    }
+                };
+                if let Some(contest_id) = contest_id {
+                    let check =
+                        crate::contests::check_submission(conn, &user, contest_id, body.problem_id);
+                    if let Err(e) = check.await {
+                        return Ok(Err(e));
+                    }
+                }
+
+                Submission::create(
+                    conn,
+                    user.id,
+                    body.problem_id,
+                    &body.language,
+                    &body.source,
+                    contest_id,
+                )
+                .await
+                .map(Ok)
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(crate::errors::database_error)??;
+
+    let mut response = Response::new(
+        json!({
+          "status": "success",
+          "data": json!({
+              "submission": filter_submission_record(&submission)
+          })
+        })
+        .to_string(),
+    );
+    *response.status_mut() = StatusCode::CREATED;
+
+    let hx_trigger = format!(r#"{{"submission_created":{{"id": "{}"}}}}"#, submission.id);
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+    headers.insert("HX-Trigger", hx_trigger.parse().unwrap());
+
+    Ok(response)
+}
+
+/// Streams the progress of a submission: a `status` event whenever it
+/// changes, then a single `finished` event with the result, after which the
+/// stream ends.
+///
+/// The widget from [`submission_widget_handler`] swaps both into itself
+/// through htmx's SSE extension.
+pub async fn submission_events_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, crate::errors::TyJson> {
+    let (_, feedback) = get_own_submission(&state, &user, id).await?;
+
+    let events = stream::unfold(
+        (state, None::<String>, false),
+        move |(state, last_status, finished)| async move {
+            if finished {
+                return None;
+            }
+
+            loop {
+                let mut submission = match poll_submission(&state, id).await {
+                    Ok(Some(submission)) => submission,
+                    Ok(None) => return None,
+                    Err(e) => {
+                        eprintln!("Failed to poll submission {id}: {e}");
+                        return None;
+                    }
+                };
+
+                restrict(&mut submission, feedback);
+
+                if submission.status == SubmissionStatus::Finished {
+                    let message = verdict_message(&state, &submission, feedback)
+                        .await
+                        .unwrap_or_else(|e| {
+                            eprintln!("Failed to load the tests of submission {id}: {e}");
+                            None
+                        });
+                    let html = result_template(&submission, message)
+                        .render()
+                        .unwrap_or_default();
+                    let event = Event::default().event("finished").data(html);
+                    return Some((Ok(event), (state, last_status, true)));
+                }
+
+                let status = status_text(&submission);
+                if last_status.as_ref() != Some(&status) {
+                    let html = templates::SubmissionStatusTemplate {
+                        status: status.clone(),
+                    }
+                    .render()
+                    .unwrap_or_default();
+                    let event = Event::default().event("status").data(html);
+                    return Some((Ok(event), (state, Some(status), false)));
+                }
+
+                tokio::time::sleep(EVENTS_POLL_INTERVAL).await;
+            }
+        },
+    );
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// The live status widget of a submission, or its result once it is judged.
+pub async fn submission_widget_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let (submission, feedback) = get_own_submission(&state, &user, id).await?;
+
+    if submission.status == SubmissionStatus::Finished {
+        let message = verdict_message(&state, &submission, feedback)
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load the tests of submission {id}: {e}");
+                None
+            });
+        return Ok(result_template(&submission, message).into_response());
+    }
+
+    Ok(templates::SubmissionTemplate {
+        id: submission.id,
+        status: status_text(&submission),
+    }
+    .into_response())
+}
+
+pub async fn get_submission_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let (submission, feedback) = get_own_submission(&state, &user, id).await?;
+    let conn = &mut state.db.get().await.unwrap();
+
+    let tests = TestCase::list_for_problem(conn, submission.problem_id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    let test_results = TestResult::list_for_submission(conn, submission.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    let subtasks = Subtask::list_for_problem(conn, submission.problem_id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    let subtask_results = SubtaskResult::list_for_submission(conn, submission.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    let tests: Vec<_> = test_results
+        .iter()
+        .filter(|_| feedback == Feedback::Full)
+        .filter_map(|result| {
+            let test = tests.iter().find(|test| test.id == result.test_case_id)?;
+            Some(filter_test_result_record(test, result))
+        })
+        .collect();
+    let subtasks: Vec<_> = subtasks
+        .iter()
+        .filter(|_| feedback != Feedback::None)
+        .map(|subtask| FilteredSubtaskResult {
+            test_group: subtask.test_group,
+            max_points: subtask.points,
+            points: subtask_results
+                .iter()
+                .find(|result| result.test_group == subtask.test_group)
+                .map(|result| result.points),
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+      "status": "success",
+      "data": serde_json::json!({
+          "submission": filter_submission_record(&submission),
+          "subtasks": subtasks,
+          "tests": tests
+      })
+    })))
+}
+
+/// A submission of `user`, showing only what the feedback of its contest
+/// allows, and that feedback. Anybody else's is reported as not found.
+async fn get_own_submission(
+    state: &AppState,
+    user: &User,
+    id: uuid::Uuid,
+) -> Result<(Submission, Feedback), crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let mut submission = Submission::get(conn, id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .filter(|submission| submission.user_id == user.id)
+        .ok_or_else(crate::errors::submission_not_found)?;
+    let feedback = crate::contests::feedback(conn, &submission).await?;
+    restrict(&mut submission, feedback);
+
+    Ok((submission, feedback))
+}
+
+/// Hides what `feedback` does not let the user see: the test being run
+/// unless it is full, and the outcome other than a compilation error if
+/// there is none.
+fn restrict(submission: &mut Submission, feedback: Feedback) {
+    if feedback != Feedback::Full {
+        submission.current_test = None;
+    }
+    if feedback == Feedback::None {
+        if submission.verdict != Some(Verdict::CompilationError) {
+            submission.verdict = None;
+        }
+        submission.time_ms = None;
+        submission.memory_bytes = None;
+        submission.score = None;
+    }
+}
+
+async fn poll_submission(
+    state: &AppState,
+    id: uuid::Uuid,
+) -> Result<Option<Submission>, Box<dyn std::error::Error>> {
+    let conn = &mut state.db.get().await?;
+    Ok(Submission::get(conn, id).await?)
+}
+
+/// What the checker or the sandbox said about the first test failed with
+/// the submission's verdict, such as the system call that broke the
+/// sandbox. Only full feedback shows it, like the tests themselves.
+async fn verdict_message(
+    state: &AppState,
+    submission: &Submission,
+    feedback: Feedback,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let Some(verdict) = submission.verdict else {
+        return Ok(None);
+    };
+    if feedback != Feedback::Full || verdict == Verdict::Accepted {
+        return Ok(None);
+    }
+
+    let conn = &mut state.db.get().await?;
+    Ok(TestResult::list_for_submission(conn, submission.id)
+        .await?
+        .into_iter()
+        .find(|result| result.verdict == verdict)
+        .and_then(|result| result.checker_message))
+}
+
+fn status_text(submission: &Submission) -> String {
+    match (submission.status, submission.current_test) {
+        (SubmissionStatus::Queued, _) => "В очереди".to_owned(),
+        (SubmissionStatus::Compiling, _) => "Компиляция".to_owned(),
+        (SubmissionStatus::Running, Some(test)) => format!("Выполняется на тесте {test}"),
+        (SubmissionStatus::Running, None) => "Выполняется".to_owned(),
+        (SubmissionStatus::Finished, _) => submission
+            .verdict
+            .map_or("Проверено", verdict_name)
+            .to_owned(),
+    }
+}
+
+fn verdict_name(verdict: Verdict) -> &'static str {
+    match verdict {
+        Verdict::Accepted => "Принято",
+        Verdict::WrongAnswer => "Неправильный ответ",
+        Verdict::PresentationError => "Неправильный формат вывода",
+        Verdict::PartiallyCorrect => "Частичное решение",
+        Verdict::TimeLimitExceeded => "Превышено ограничение времени",
+        Verdict::MemoryLimitExceeded => "Превышено ограничение памяти",
+        Verdict::OutputLimitExceeded => "Превышено ограничение вывода",
+        Verdict::IdlenessLimitExceeded => "Превышено время простоя",
+        Verdict::RuntimeError => "Ошибка выполнения",
+        Verdict::CompilationError => "Ошибка компиляции",
+        Verdict::SecurityViolation => "Нарушение безопасности",
+        Verdict::JudgementFailed => "Ошибка проверки",
+    }
+}
+
+fn result_template(
+    submission: &Submission,
+    message: Option<String>,
+) -> templates::SubmissionResultTemplate {
+    templates::SubmissionResultTemplate {
+        id: submission.id,
+        verdict: submission.verdict.map_or("Проверено", verdict_name),
+        message,
+        score: submission.score,
+        time_ms: submission.time_ms,
+        memory_kib: submission.memory_bytes.map(|bytes| bytes / 1024),
+        compile_log: submission.compile_log.clone(),
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredSubmission<'a> {
+    pub id: uuid::Uuid,
+    pub problem_id: uuid::Uuid,
+    pub contest_id: Option<uuid::Uuid>,
+    pub language: &'a str,
+    /// Toolchain of the language that judged the submission.
+    pub language_version: Option<&'a str>,
+    pub status: &'static str,
+    pub verdict: Option<&'static str>,
+    pub time_ms: Option<i32>,
+    pub memory_bytes: Option<i64>,
+    pub score: Option<f64>,
+    pub compile_log: Option<&'a str>,
+    /// Whether the judge took the program from its compile cache.
+    pub compiled_from_cache: Option<bool>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredSubtaskResult {
+    pub test_group: i32,
+    pub max_points: i32,
+    /// `None` until the submission is judged.
+    pub points: Option<f64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FilteredTestResult<'a> {
+    pub ordinal: i32,
+    pub test_group: i32,
+    pub verdict: &'static str,
+    /// What the checker said, or how the program ended if it did not exit
+    /// cleanly.
+    pub message: Option<&'a str>,
+    pub time_ms: i32,
+    pub memory_bytes: i64,
+    pub score: f64,
+}
+
+fn filter_submission_record(submission: &Submission) -> FilteredSubmission<'_> {
+    FilteredSubmission {
+        id: submission.id,
+        problem_id: submission.problem_id,
+        contest_id: submission.contest_id,
+        language: &submission.language,
+        language_version: submission.language_version.as_deref(),
+        status: submission.status.as_str(),
+        verdict: submission.verdict.as_ref().map(|verdict| verdict.as_str()),
+        time_ms: submission.time_ms,
+        memory_bytes: submission.memory_bytes,
+        score: submission.score,
+        compile_log: submission.compile_log.as_deref(),
+        compiled_from_cache: submission.compiled_from_cache,
+        created_at: submission.created_at,
+    }
+}
+
+fn filter_test_result_record<'a>(
+    test: &TestCase,
+    result: &'a TestResult,
+) -> FilteredTestResult<'a> {
+    FilteredTestResult {
+        ordinal: test.ordinal,
+        test_group: test.test_group,
+        verdict: result.verdict.as_str(),
+        message: result.checker_message.as_deref(),
+        time_ms: result.time_ms,
+        memory_bytes: result.memory_bytes,
+        score: result.score,
+    }
+}
@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use db::{
+    diesel::result::{DatabaseErrorKind, Error as DieselError},
+    orm::{Team, TeamInvitation, TeamMember, User},
+};
+use diesel_async::AsyncPgConnection;
+use serde_json::json;
+
+use crate::AppState;
+
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateTeamSchema {
+    pub name: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct InviteSchema {
+    pub username: String,
+}
+
+/// Creates a team with the user as its only member.
+pub async fn create_team_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Json(body): Json<CreateTeamSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(crate::errors::invalid_team_name());
+    }
+
+    let conn = &mut state.db.get().await.unwrap();
+
+    let team = Team::create(conn, name).await.map_err(|e| match e {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            crate::errors::team_name_taken()
+        }
+        e => crate::errors::database_error(e),
+    })?;
+    TeamMember::create(conn, team.id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+          "status": "success",
+          "data": json!({
+              "team": json!({
+                  "id": team.id,
+                  "name": team.name,
+              })
+          })
+        })),
+    ))
+}
+
+/// The teams of the user with their members and invited users, and the
+/// teams the user is invited to.
+pub async fn list_teams_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let teams = Team::list_for_user(conn, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    let mut filtered_teams = Vec::with_capacity(teams.len());
+    for team in &teams {
+        let members = TeamMember::list_for_team(conn, team.id)
+            .await
+            .map_err(crate::errors::database_error)?;
+        let invited = TeamInvitation::list_for_team(conn, team.id)
+            .await
+            .map_err(crate::errors::database_error)?;
+
+        filtered_teams.push(json!({
+            "id": team.id,
+            "name": team.name,
+            "members": members
+                .iter()
+                .map(|(member, username)| json!({ "id": member.user_id, "username": username }))
+                .collect::<Vec<_>>(),
+            "invited": invited
+                .iter()
+                .map(|(invitation, username)| {
+                    json!({ "id": invitation.user_id, "username": username })
+                })
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    let invitations = Team::list_invited(conn, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(Json(json!({
+      "status": "success",
+      "data": json!({
+          "teams": filtered_teams,
+          "invitations": invitations
+              .iter()
+              .map(|team| json!({ "id": team.id, "name": team.name }))
+              .collect::<Vec<_>>()
+      })
+    })))
+}
+
+/// Invites a user to a team the user is a member of.
+pub async fn invite_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+    Json(body): Json<InviteSchema>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    get_own_team(conn, &user, id).await?;
+
+    let invited = User::get_by_username(conn, body.username.trim())
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::user_not_found)?;
+    let member = TeamMember::exists(conn, id, invited.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    if member {
+        return Err(crate::errors::already_team_member());
+    }
+
+    TeamInvitation::create(conn, id, invited.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(Json(json!({
+      "status": "success"
+    })))
+}
+
+/// Joins a team the user is invited to.
+pub async fn accept_invitation_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let deleted = TeamInvitation::delete(conn, id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    if deleted == 0 {
+        return Err(crate::errors::invitation_not_found());
+    }
+
+    TeamMember::create(conn, id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+
+    Ok(Json(json!({
+      "status": "success"
+    })))
+}
+
+pub async fn decline_invitation_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let deleted = TeamInvitation::delete(conn, id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    if deleted == 0 {
+        return Err(crate::errors::invitation_not_found());
+    }
+
+    Ok(Json(json!({
+      "status": "success"
+    })))
+}
+
+/// Leaves a team. Contests the team is registered for still count the
+/// user's submissions for it.
+pub async fn leave_team_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<impl IntoResponse, crate::errors::TyJson> {
+    let conn = &mut state.db.get().await.unwrap();
+
+    let deleted = TeamMember::delete(conn, id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    if deleted == 0 {
+        return Err(crate::errors::not_team_member());
+    }
+
+    Ok(Json(json!({
+      "status": "success"
+    })))
+}
+
+/// A team `user` is a member of.
+pub async fn get_own_team(
+    conn: &mut AsyncPgConnection,
+    user: &User,
+    id: uuid::Uuid,
+) -> Result<Team, crate::errors::TyJson> {
+    let team = Team::get(conn, id)
+        .await
+        .map_err(crate::errors::database_error)?
+        .ok_or_else(crate::errors::team_not_found)?;
+
+    let member = TeamMember::exists(conn, id, user.id)
+        .await
+        .map_err(crate::errors::database_error)?;
+    if !member {
+        return Err(crate::errors::not_team_member());
+    }
+
+    Ok(team)
+}
@@ -4,6 +4,8 @@ use askama::Template;
 #[template(path = "base.html")]
 pub struct BaseTemplate<'a> {
     pub title: &'a str,
+    /// Where the page's content is loaded from.
+    pub content: &'a str,
 }
 
 #[derive(Template)]
@@ -19,3 +21,68 @@ pub struct LoginFormTemplate {}
 #[derive(Template)]
 #[template(path = "widgets/register-form.html")]
 pub struct RegisterFormTemplate {}
+
+#[derive(Template)]
+#[template(path = "widgets/submission.html")]
+pub struct SubmissionTemplate {
+    pub id: uuid::Uuid,
+    pub status: String,
+}
+
+#[derive(Template)]
+#[template(path = "widgets/submission-status.html")]
+pub struct SubmissionStatusTemplate {
+    pub status: String,
+}
+
+#[derive(Template)]
+#[template(path = "widgets/submission-result.html")]
+pub struct SubmissionResultTemplate {
+    pub id: uuid::Uuid,
+    pub verdict: &'static str,
+    /// What was said about the test that decided the verdict.
+    pub message: Option<String>,
+    pub score: Option<f64>,
+    pub time_ms: Option<i32>,
+    pub memory_kib: Option<i64>,
+    pub compile_log: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "contests.html")]
+pub struct ContestsTemplate {
+    pub user_logged_in: bool,
+    pub contests: Vec<ContestItem>,
+}
+
+pub struct ContestItem {
+    pub id: uuid::Uuid,
+    pub title: String,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub phase: &'static str,
+}
+
+#[derive(Template)]
+#[template(path = "contest.html")]
+pub struct ContestTemplate {
+    pub user_logged_in: bool,
+    pub id: uuid::Uuid,
+    pub title: String,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub phase: &'static str,
+    pub registered: bool,
+    pub can_register: bool,
+    /// Teams of the user that can be registered instead, by id and name.
+    pub teams: Vec<(uuid::Uuid, String)>,
+    /// Letters and titles.
+    pub problems: Vec<(String, String)>,
+}
+
+#[derive(Template)]
+#[template(path = "widgets/scoreboard.html")]
+pub struct ScoreboardTemplate {
+    pub id: uuid::Uuid,
+    pub scoreboard: crate::contests::scoreboard::Scoreboard,
+}